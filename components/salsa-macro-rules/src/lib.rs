@@ -25,4 +25,5 @@ mod setup_tracked_assoc_fn_body;
 mod setup_tracked_fn;
 mod setup_tracked_method_body;
 mod setup_tracked_struct;
+mod stable_id_of;
 mod unexpected_cycle_recovery;
@@ -26,6 +26,9 @@ macro_rules! setup_input_struct {
         // Names for field setter methods (typically `set_foo`)
         field_setters: [$($field_setter_vis:vis $field_setter_id:ident),*],
 
+        // Names for the fallible field setter methods (typically `try_set_foo`)
+        field_try_setters: [$($field_try_setter_id:ident),*],
+
         // Field types
         field_tys: [$($field_ty:ty),*],
 
@@ -50,6 +53,12 @@ macro_rules! setup_input_struct {
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, track the stable identity of each input.
+        stable_id: $stable_id:tt,
+
+        // The function returning the key the stable identity is derived from, if any.
+        stable_id_fn: $($stable_id_fn:path)?,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -98,6 +107,16 @@ macro_rules! setup_input_struct {
 
                 type Revisions = [$zalsa::Revision; $N];
                 type Durabilities = [$zalsa::Durability; $N];
+
+                $zalsa::macro_if! { $stable_id =>
+                    fn stable_id(fields: &Self::Fields) -> $zalsa::Option<$zalsa::StableId> {
+                        $zalsa::Some($zalsa::stable_id_of!($zalsa, Self::DEBUG_NAME, fields, $($stable_id_fn)?))
+                    }
+
+                    fn value_hash(fields: &Self::Fields) -> $zalsa::Option<u64> {
+                        $zalsa::Some($zalsa::stable_hash(fields))
+                    }
+                }
             }
 
             impl $Configuration {
@@ -239,6 +258,24 @@ macro_rules! setup_input_struct {
                             |fields, f| std::mem::replace(&mut fields.$field_index, f),
                         )
                     }
+
+                    /// Like the setter of this field, but gives up if other database handles
+                    /// are still alive after `timeout`. A zero `timeout` does not cancel them.
+                    $field_setter_vis fn $field_try_setter_id<'db, $Db>(self, db: &'db mut $Db, timeout: std::time::Duration) -> Result<impl salsa::Setter<FieldTy = $field_ty> + use<'db, $Db>, salsa::WouldBlock>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        let zalsa = db.zalsa_mut_timeout(timeout)?;
+                        let (ingredient, revision) = $Configuration::ingredient_mut(zalsa);
+                        Ok($zalsa::input::SetterImpl::new(
+                            revision,
+                            self,
+                            $field_index,
+                            ingredient,
+                            |fields, f| std::mem::replace(&mut fields.$field_index, f),
+                        ))
+                    }
                 )*
 
                 $zalsa::macro_if! { $is_singleton =>
@@ -261,6 +298,30 @@ macro_rules! setup_input_struct {
                     }
                 }
 
+                $zalsa::macro_if! { $stable_id =>
+                    /// Returns the stable identity of this input, which is derived from the
+                    /// field values it was created with.
+                    pub fn stable_id<$Db>(self, db: &$Db) -> $zalsa::StableId
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        $Configuration::ingredient_(db.zalsa())
+                            .stable_id(self)
+                            .expect("inputs with `stable_id` always have a stable identity")
+                    }
+
+                    /// Returns the input with the given stable identity, if any.
+                    pub fn from_stable_id<$Db>(db: &$Db, stable_id: $zalsa::StableId) -> $zalsa::Option<Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        let zalsa = db.zalsa();
+                        $Configuration::ingredient_(zalsa).lookup_stable_id(zalsa, stable_id)
+                    }
+                }
+
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
                 where
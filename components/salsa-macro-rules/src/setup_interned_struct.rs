@@ -66,6 +66,12 @@ macro_rules! setup_interned_struct {
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, track the stable identity of each interned value.
+        stable_id: $stable_id:tt,
+
+        // The function returning the key the stable identity is derived from, if any.
+        stable_id_fn: $($stable_id_fn:path)?,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -144,6 +150,11 @@ macro_rules! setup_interned_struct {
                 $(
                     const REVISIONS: ::core::num::NonZeroUsize = ::core::num::NonZeroUsize::new($revisions).unwrap();
                 )?
+                $zalsa::macro_if! { $stable_id =>
+                    fn stable_id(fields: &Self::Fields<'_>) -> $zalsa::Option<$zalsa::StableId> {
+                        $zalsa::Some($zalsa::stable_id_of!($zalsa, Self::DEBUG_NAME, fields, $($stable_id_fn)?))
+                    }
+                }
                 type Fields<'a> = $StructDataIdent<'a>;
                 type Struct<'db> = $Struct< $($db_lt_arg)? >;
             }
@@ -257,6 +268,30 @@ macro_rules! setup_interned_struct {
                     }
                 )*
 
+                $zalsa::macro_if! { $stable_id =>
+                    /// Returns the stable identity of this interned value, which is derived
+                    /// from its fields.
+                    pub fn stable_id<$Db>(self, db: &$db_lt $Db) -> $zalsa::StableId
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $Configuration::ingredient(db.zalsa())
+                            .stable_id($zalsa::AsId::as_id(&self))
+                            .expect("interned structs with `stable_id` always have a stable identity")
+                    }
+
+                    /// Returns the interned value with the given stable identity, if any.
+                    pub fn from_stable_id<$Db>(db: &$db_lt $Db, stable_id: $zalsa::StableId) -> $zalsa::Option<Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        let (zalsa, zalsa_local) = db.zalsas();
+                        $Configuration::ingredient(zalsa).lookup_stable_id(zalsa, zalsa_local, stable_id)
+                    }
+                }
+
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     $zalsa::with_attached_database(|db| {
@@ -49,6 +49,12 @@ macro_rules! setup_tracked_fn {
         // If true, this is specifiable.
         is_specifiable: $is_specifiable:tt,
 
+        // If true, the memoized values are persisted.
+        is_persistable: $is_persistable:tt,
+
+        // If true, the memoized values are backdated by comparing fingerprints.
+        is_fingerprinted: $is_fingerprinted:tt,
+
         // Equality check strategy function
         values_equal: {$($values_equal:tt)+},
 
@@ -61,6 +67,9 @@ macro_rules! setup_tracked_fn {
         // LRU capacity (a literal, maybe 0)
         lru: $lru:tt,
 
+        // Number of revisions after which unverified memoized values are evicted (a literal, maybe 0)
+        evict_after_revisions: $evict_after_revisions:tt,
+
         // The return mode for the function, see `salsa_macros::options::Option::returns`
         return_mode: $return_mode:tt,
 
@@ -245,6 +254,34 @@ macro_rules! setup_tracked_fn {
                     }
                 )?
 
+                $zalsa::macro_if! {
+                    if $is_fingerprinted {
+                        const FINGERPRINT: bool = true;
+
+                        type Fingerprint = $zalsa::Option<$zalsa::Fingerprint>;
+
+                        fn fingerprint(value: &Self::Output<'_>) -> $zalsa::Fingerprint {
+                            $zalsa::Fingerprint::of(value)
+                        }
+                    } else {
+                        type Fingerprint = ();
+                    }
+                }
+
+                $zalsa::macro_if! { $is_persistable =>
+                    const PERSIST: bool = true;
+
+                    fn encode_value(value: &Self::Output<'_>, buf: &mut Vec<u8>) {
+                        $zalsa::Persist::encode(value, buf)
+                    }
+
+                    fn decode_value(
+                        buf: &mut &[u8],
+                    ) -> Result<Self::Output<'static>, $zalsa::PersistenceError> {
+                        <Self::Output<'static> as $zalsa::Persist>::decode(buf)
+                    }
+                }
+
                 fn execute<$db_lt>($db: &$db_lt Self::DbView, ($($input_id),*): ($($interned_input_ty),*)) -> Self::Output<$db_lt> {
                     $($assert_return_type_is_update)*
 
@@ -321,6 +358,7 @@ macro_rules! setup_tracked_fn {
                         first_index,
                         memo_ingredient_indices,
                         $lru,
+                        $evict_after_revisions,
                     );
                     $zalsa::macro_if! {
                         if $needs_interner {
@@ -343,6 +381,36 @@ macro_rules! setup_tracked_fn {
 
             #[allow(non_local_definitions)]
             impl $fn_name {
+                /// Like calling the tracked function, but returns an error instead of panicking
+                /// if the query is cancelled, hits an unrecoverable cycle, or is blocked on a
+                /// thread that panicked.
+                ///
+                /// When called from another query, that query fails as well.
+                #[allow(clippy::needless_lifetimes)]
+                pub fn try_fetch<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $interned_input_ty,)*
+                ) -> Result<salsa::plumbing::return_mode_ty!(($return_mode, __, __), $db_lt, $output_ty), salsa::SalsaError> {
+                    use salsa::plumbing as $zalsa;
+                    $zalsa::attach($db, || {
+                        let (zalsa, zalsa_local) = $db.zalsas();
+                        let result = $zalsa::macro_if! {
+                            if $needs_interner {
+                                {
+                                    let key = $Configuration::intern_ingredient_(zalsa).intern_id(zalsa, zalsa_local, ($($input_id),*), |_, data| data);
+                                    $Configuration::fn_ingredient_($db, zalsa).try_fetch($db, zalsa, zalsa_local, key)
+                                }
+                            } else {
+                                {
+                                    $Configuration::fn_ingredient_($db, zalsa).try_fetch($db, zalsa, zalsa_local, $zalsa::AsId::as_id(&($($input_id),*)))
+                                }
+                            }
+                        };
+
+                        result.map(|result| $zalsa::return_mode_expression!(($return_mode, __, __), $output_ty, result,))
+                    })
+                }
+
                 $zalsa::gate_accumulated! {
                     pub fn accumulated<$db_lt, A: salsa::Accumulator>(
                         $db: &$db_lt dyn $Db,
@@ -389,6 +457,20 @@ macro_rules! setup_tracked_fn {
                         $Configuration::fn_ingredient_mut(db).set_capacity(value);
                     }
                 } }
+
+                $zalsa::macro_if! { if0 $evict_after_revisions { } else {
+                    /// Sets the number of revisions after which memoized values that haven't
+                    /// been verified are evicted
+                    ///
+                    /// **WARNING:** Just like an ordinary write, this method triggers
+                    /// cancellation. If you invoke it while a snapshot exists, it
+                    /// will block until that snapshot is dropped -- if that snapshot
+                    /// is owned by the current thread, this could trigger deadlock.
+                    #[allow(dead_code)]
+                    fn set_evict_after_revisions(db: &mut dyn $Db, value: usize) {
+                        $Configuration::fn_ingredient_mut(db).set_evict_after_revisions(value);
+                    }
+                } }
             }
 
             $zalsa::attach($db, || {
@@ -0,0 +1,12 @@
+/// Generate the `StableId` of a salsa struct with the given fields, either from the hash
+/// of the fields or from the key returned by the `stable_id = key_fn` function.
+#[macro_export]
+macro_rules! stable_id_of {
+    ($zalsa:ident, $debug_name:expr, $fields:expr,) => {
+        $zalsa::StableId::of($debug_name, $fields)
+    };
+
+    ($zalsa:ident, $debug_name:expr, $fields:expr, $key_fn:path) => {
+        $zalsa::StableId::from_key($debug_name, &$key_fn($fields))
+    };
+}
@@ -31,17 +31,22 @@ struct Accumulator;
 impl AllowedOptions for Accumulator {
     const RETURNS: bool = false;
     const SPECIFY: bool = false;
+    const PERSIST: bool = false;
+    const FINGERPRINT: bool = false;
     const NO_EQ: bool = false;
     const DEBUG: bool = false;
     const NON_UPDATE_RETURN_TYPE: bool = false;
     const NO_LIFETIME: bool = false;
     const SINGLETON: bool = false;
+    const STABLE_ID: bool = false;
     const DATA: bool = false;
     const DB: bool = false;
     const CYCLE_FN: bool = false;
     const CYCLE_INITIAL: bool = false;
     const CYCLE_RESULT: bool = false;
     const LRU: bool = false;
+
+    const EVICT_AFTER_REVISIONS: bool = false;
     const CONSTRUCTOR_NAME: bool = false;
     const ID: bool = false;
     const REVISIONS: bool = false;
@@ -36,6 +36,8 @@ impl crate::options::AllowedOptions for InputStruct {
     const RETURNS: bool = false;
 
     const SPECIFY: bool = false;
+    const PERSIST: bool = false;
+    const FINGERPRINT: bool = false;
 
     const NO_EQ: bool = false;
 
@@ -47,6 +49,8 @@ impl crate::options::AllowedOptions for InputStruct {
 
     const SINGLETON: bool = true;
 
+    const STABLE_ID: bool = true;
+
     const DATA: bool = true;
 
     const DB: bool = false;
@@ -59,6 +63,8 @@ impl crate::options::AllowedOptions for InputStruct {
 
     const LRU: bool = false;
 
+    const EVICT_AFTER_REVISIONS: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 
     const ID: bool = false;
@@ -105,6 +111,7 @@ impl Macro {
         let field_vis = salsa_struct.field_vis();
         let field_getter_ids = salsa_struct.field_getter_ids();
         let field_setter_ids = salsa_struct.field_setter_ids();
+        let field_try_setter_ids = salsa_struct.field_try_setter_ids();
         let required_fields = salsa_struct.required_fields();
         let field_options = salsa_struct.field_options();
         let field_tys = salsa_struct.field_tys();
@@ -112,6 +119,8 @@ impl Macro {
         let field_attrs = salsa_struct.field_attrs();
         let is_singleton = self.args.singleton.is_some();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
+        let stable_id = self.args.stable_id.is_some();
+        let stable_id_fn = self.args.stable_id_fn.iter();
 
         let zalsa = self.hygiene.ident("zalsa");
         let zalsa_struct = self.hygiene.ident("zalsa_struct");
@@ -132,6 +141,7 @@ impl Macro {
                     field_ids: [#(#field_ids),*],
                     field_getters: [#(#field_vis #field_getter_ids),*],
                     field_setters: [#(#field_vis #field_setter_ids),*],
+                    field_try_setters: [#(#field_try_setter_ids),*],
                     field_tys: [#(#field_tys),*],
                     field_indices: [#(#field_indices),*],
                     field_attrs: [#([#(#field_attrs),*]),*],
@@ -140,6 +150,8 @@ impl Macro {
                     num_fields: #num_fields,
                     is_singleton: #is_singleton,
                     generate_debug_impl: #generate_debug_impl,
+                    stable_id: #stable_id,
+                    stable_id_fn: #(#stable_id_fn)*,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
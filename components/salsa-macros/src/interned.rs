@@ -36,6 +36,8 @@ impl crate::options::AllowedOptions for InternedStruct {
     const RETURNS: bool = false;
 
     const SPECIFY: bool = false;
+    const PERSIST: bool = false;
+    const FINGERPRINT: bool = false;
 
     const NO_EQ: bool = false;
 
@@ -47,6 +49,8 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const SINGLETON: bool = true;
 
+    const STABLE_ID: bool = true;
+
     const DATA: bool = true;
 
     const DB: bool = false;
@@ -59,6 +63,8 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const LRU: bool = false;
 
+    const EVICT_AFTER_REVISIONS: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 
     const ID: bool = true;
@@ -111,6 +117,8 @@ impl Macro {
         let field_indexed_tys = salsa_struct.field_indexed_tys();
         let field_unused_attrs = salsa_struct.field_attrs();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
+        let stable_id = self.args.stable_id.is_some();
+        let stable_id_fn = self.args.stable_id_fn.iter();
         let has_lifetime = salsa_struct.generate_lifetime();
         let id = salsa_struct.id();
         let revisions = salsa_struct.revisions();
@@ -161,6 +169,8 @@ impl Macro {
                     field_attrs: [#([#(#field_unused_attrs),*]),*],
                     num_fields: #num_fields,
                     generate_debug_impl: #generate_debug_impl,
+                    stable_id: #stable_id,
+                    stable_id_fn: #(#stable_id_fn)*,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
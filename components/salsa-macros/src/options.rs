@@ -37,12 +37,37 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// It allows the creation of convenient methods
     pub singleton: Option<syn::Ident>,
 
+    /// The `stable_id` option is used on inputs and interned structs to track
+    /// a content-addressed identity for each instance.
+    ///
+    /// If this is `Some`, the value is the `stable_id` identifier.
+    pub stable_id: Option<syn::Ident>,
+
+    /// The `stable_id = <path>` option is used to derive the stable identity from the
+    /// key returned by the given function instead of from the hash of the fields.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub stable_id_fn: Option<syn::Path>,
+
     /// The `specify` option is used to signal that a tracked function can
     /// have its value externally specified (at least some of the time).
     ///
     /// If this is `Some`, the value is the `specify` identifier.
     pub specify: Option<syn::Ident>,
 
+    /// The `persist` option is used to signal that the memoized values of a tracked
+    /// function are written by `Database::persist_memos`.
+    ///
+    /// If this is `Some`, the value is the `persist` identifier.
+    pub persist: Option<syn::Ident>,
+
+    /// The `fingerprint` option is used to signal that the memoized values of a tracked
+    /// function are backdated by comparing a stable hash of the values instead of the
+    /// values themselves.
+    ///
+    /// If this is `Some`, the value is the `fingerprint` identifier.
+    pub fingerprint: Option<syn::Ident>,
+
     /// The `non_update_return_type` option is used to signal that a tracked function's
     /// return type does not require `Update` to be implemented. This is unsafe and
     /// generally discouraged as it allows for dangling references.
@@ -81,6 +106,12 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<usize>`.
     pub lru: Option<usize>,
 
+    /// The `evict_after_revisions = <usize>` option is used to evict the memoized values of a
+    /// tracked function that haven't been verified in the last `<usize>` revisions.
+    ///
+    /// If this is `Some`, the value is the `<usize>`.
+    pub evict_after_revisions: Option<usize>,
+
     /// The `constructor = <ident>` option lets the user specify the name of
     /// the constructor of a salsa struct.
     ///
@@ -118,6 +149,8 @@ impl<A: AllowedOptions> Default for Options<A> {
         Self {
             returns: Default::default(),
             specify: Default::default(),
+            persist: Default::default(),
+            fingerprint: Default::default(),
             non_update_return_type: Default::default(),
             no_eq: Default::default(),
             debug: Default::default(),
@@ -130,7 +163,10 @@ impl<A: AllowedOptions> Default for Options<A> {
             constructor_name: Default::default(),
             phantom: Default::default(),
             lru: Default::default(),
+            evict_after_revisions: Default::default(),
             singleton: Default::default(),
+            stable_id: Default::default(),
+            stable_id_fn: Default::default(),
             id: Default::default(),
             revisions: Default::default(),
             heap_size_fn: Default::default(),
@@ -143,17 +179,21 @@ impl<A: AllowedOptions> Default for Options<A> {
 pub(crate) trait AllowedOptions {
     const RETURNS: bool;
     const SPECIFY: bool;
+    const PERSIST: bool;
+    const FINGERPRINT: bool;
     const NO_EQ: bool;
     const DEBUG: bool;
     const NO_LIFETIME: bool;
     const NON_UPDATE_RETURN_TYPE: bool;
     const SINGLETON: bool;
+    const STABLE_ID: bool;
     const DATA: bool;
     const DB: bool;
     const CYCLE_FN: bool;
     const CYCLE_INITIAL: bool;
     const CYCLE_RESULT: bool;
     const LRU: bool;
+    const EVICT_AFTER_REVISIONS: bool;
     const CONSTRUCTOR_NAME: bool;
     const ID: bool;
     const REVISIONS: bool;
@@ -275,6 +315,52 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`specify` option not allowed here",
                     ));
                 }
+            } else if ident == "stable_id" {
+                if A::STABLE_ID {
+                    if let Some(old) = options.stable_id.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `stable_id` provided twice",
+                        ));
+                    }
+                    if input.peek(syn::Token![=]) {
+                        let _eq = Equals::parse(input)?;
+                        options.stable_id_fn = Some(syn::Path::parse(input)?);
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`stable_id` option not allowed here",
+                    ));
+                }
+            } else if ident == "persist" {
+                if A::PERSIST {
+                    if let Some(old) = options.persist.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `persist` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`persist` option not allowed here",
+                    ));
+                }
+            } else if ident == "fingerprint" {
+                if A::FINGERPRINT {
+                    if let Some(old) = options.fingerprint.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `fingerprint` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`fingerprint` option not allowed here",
+                    ));
+                }
             } else if ident == "db" {
                 if A::DB {
                     let _eq = Equals::parse(input)?;
@@ -363,6 +449,23 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`lru` option not allowed here",
                     ));
                 }
+            } else if ident == "evict_after_revisions" {
+                if A::EVICT_AFTER_REVISIONS {
+                    let _eq = Equals::parse(input)?;
+                    let lit = syn::LitInt::parse(input)?;
+                    let value = lit.base10_parse::<usize>()?;
+                    if let Some(old) = options.evict_after_revisions.replace(value) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `evict_after_revisions` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`evict_after_revisions` option not allowed here",
+                    ));
+                }
             } else if ident == "constructor" {
                 if A::CONSTRUCTOR_NAME {
                     let _eq = Equals::parse(input)?;
@@ -463,7 +566,11 @@ impl<A: AllowedOptions> quote::ToTokens for Options<A> {
             debug,
             no_lifetime,
             singleton,
+            stable_id,
+            stable_id_fn,
             specify,
+            persist,
+            fingerprint,
             non_update_return_type,
             db_path,
             cycle_fn,
@@ -471,6 +578,7 @@ impl<A: AllowedOptions> quote::ToTokens for Options<A> {
             cycle_result,
             data,
             lru,
+            evict_after_revisions,
             constructor_name,
             id,
             revisions,
@@ -493,9 +601,20 @@ impl<A: AllowedOptions> quote::ToTokens for Options<A> {
         if singleton.is_some() {
             tokens.extend(quote::quote! { singleton, });
         }
+        if let Some(stable_id_fn) = stable_id_fn {
+            tokens.extend(quote::quote! { stable_id = #stable_id_fn, });
+        } else if stable_id.is_some() {
+            tokens.extend(quote::quote! { stable_id, });
+        }
         if specify.is_some() {
             tokens.extend(quote::quote! { specify, });
         }
+        if persist.is_some() {
+            tokens.extend(quote::quote! { persist, });
+        }
+        if fingerprint.is_some() {
+            tokens.extend(quote::quote! { fingerprint, });
+        }
         if non_update_return_type.is_some() {
             tokens.extend(quote::quote! { unsafe(non_update_return_type), });
         }
@@ -517,6 +636,9 @@ impl<A: AllowedOptions> quote::ToTokens for Options<A> {
         if let Some(lru) = lru {
             tokens.extend(quote::quote! { lru = #lru, });
         }
+        if let Some(evict_after_revisions) = evict_after_revisions {
+            tokens.extend(quote::quote! { evict_after_revisions = #evict_after_revisions, });
+        }
         if let Some(constructor_name) = constructor_name {
             tokens.extend(quote::quote! { constructor = #constructor_name, });
         }
@@ -347,6 +347,13 @@ where
         self.fields.iter().map(|f| &f.set_name).collect()
     }
 
+    pub(crate) fn field_try_setter_ids(&self) -> Vec<syn::Ident> {
+        self.fields
+            .iter()
+            .map(|f| quote::format_ident!("try_{}", f.set_name))
+            .collect()
+    }
+
     pub(crate) fn field_durability_ids(&self) -> Vec<syn::Ident> {
         self.fields
             .iter()
@@ -29,6 +29,8 @@ impl crate::options::AllowedOptions for TrackedFn {
     const RETURNS: bool = true;
 
     const SPECIFY: bool = true;
+    const PERSIST: bool = true;
+    const FINGERPRINT: bool = true;
 
     const NO_EQ: bool = true;
 
@@ -40,6 +42,8 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const SINGLETON: bool = false;
 
+    const STABLE_ID: bool = false;
+
     const DATA: bool = false;
 
     const DB: bool = false;
@@ -52,6 +56,8 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const LRU: bool = true;
 
+    const EVICT_AFTER_REVISIONS: bool = true;
+
     const CONSTRUCTOR_NAME: bool = false;
 
     const ID: bool = false;
@@ -100,6 +106,8 @@ impl Macro {
         let (cycle_recovery_fn, cycle_recovery_initial, cycle_recovery_strategy) =
             self.cycle_recovery()?;
         let is_specifiable = self.args.specify.is_some();
+        let is_persistable = self.args.persist.is_some();
+        let is_fingerprinted = self.args.fingerprint.is_some();
         let requires_update = self.args.non_update_return_type.is_none();
         let heap_size_fn = self.args.heap_size_fn.iter();
         let eq = if let Some(token) = &self.args.no_eq {
@@ -109,6 +117,12 @@ impl Macro {
                     "the `no_eq` option cannot be used with `cycle_fn`",
                 ));
             }
+            if self.args.fingerprint.is_some() {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `no_eq` option cannot be used with `fingerprint`",
+                ));
+            }
             quote!(false)
         } else {
             quote_spanned!(output_ty.span() =>
@@ -152,6 +166,18 @@ impl Macro {
             }
         }
 
+        if let Some(token) = &self.args.persist {
+            match function_type {
+                FunctionType::Constant | FunctionType::RequiresInterning => {
+                    return Err(syn::Error::new_spanned(
+                        token,
+                        "only functions with a single salsa struct as their input can be persisted",
+                    ))
+                }
+                FunctionType::SalsaStruct => {}
+            }
+        }
+
         if let (Some(_), Some(token)) = (&self.args.lru, &self.args.specify) {
             return Err(syn::Error::new_spanned(
                 token,
@@ -159,12 +185,21 @@ impl Macro {
             ));
         }
 
+        if let (Some(_), Some(token)) = (&self.args.evict_after_revisions, &self.args.specify) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `specify` and `evict_after_revisions` options cannot be used together",
+            ));
+        }
+
         let needs_interner = match function_type {
             FunctionType::Constant | FunctionType::RequiresInterning => true,
             FunctionType::SalsaStruct => false,
         };
 
         let lru = Literal::usize_unsuffixed(self.args.lru.unwrap_or(0));
+        let evict_after_revisions =
+            Literal::usize_unsuffixed(self.args.evict_after_revisions.unwrap_or(0));
 
         let return_mode = self
             .args
@@ -224,10 +259,13 @@ impl Macro {
                 cycle_recovery_initial: #cycle_recovery_initial,
                 cycle_recovery_strategy: #cycle_recovery_strategy,
                 is_specifiable: #is_specifiable,
+                is_persistable: #is_persistable,
+                is_fingerprinted: #is_fingerprinted,
                 values_equal: {#eq},
                 needs_interner: #needs_interner,
                 heap_size_fn: #(#heap_size_fn)*,
                 lru: #lru,
+                evict_after_revisions: #evict_after_revisions,
                 return_mode: #return_mode,
                 assert_return_type_is_update: { #assert_return_type_is_update },
                 #self_ty
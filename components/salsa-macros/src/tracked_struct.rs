@@ -32,6 +32,8 @@ impl crate::options::AllowedOptions for TrackedStruct {
     const RETURNS: bool = false;
 
     const SPECIFY: bool = false;
+    const PERSIST: bool = false;
+    const FINGERPRINT: bool = false;
 
     const NO_EQ: bool = false;
 
@@ -43,6 +45,8 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const SINGLETON: bool = true;
 
+    const STABLE_ID: bool = false;
+
     const DATA: bool = true;
 
     const DB: bool = false;
@@ -55,6 +59,8 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const LRU: bool = false;
 
+    const EVICT_AFTER_REVISIONS: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 
     const ID: bool = false;
@@ -11,6 +11,7 @@ use crate::cycle::CycleHeadKeys;
 use crate::function::VerifyResult;
 use crate::ingredient::{Ingredient, Jar};
 use crate::plumbing::ZalsaLocal;
+use crate::salsa_error::SalsaError;
 use crate::sync::Arc;
 use crate::table::memo::MemoTableTypes;
 use crate::zalsa::{IngredientIndex, Zalsa};
@@ -107,7 +108,7 @@ impl<A: Accumulator> Ingredient for IngredientImpl<A> {
         _input: Id,
         _revision: Revision,
         _cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         panic!("nothing should ever depend on an accumulator directly")
     }
 
@@ -10,6 +10,7 @@ use crate::durability::Durability;
 use crate::hash::FxIndexSet;
 use crate::key::DatabaseKeyIndex;
 use crate::runtime::Stamp;
+use crate::salsa_error::SalsaError;
 use crate::sync::atomic::AtomicBool;
 use crate::tracked_struct::{Disambiguator, DisambiguatorMap, IdentityHash, IdentityMap};
 use crate::zalsa_local::{QueryEdge, QueryOrigin, QueryRevisions, QueryRevisionsExtra};
@@ -66,6 +67,10 @@ pub(crate) struct ActiveQuery {
 
     /// If this query is a cycle head, iteration count of that cycle.
     iteration_count: IterationCount,
+
+    /// The first error returned by a `try_fetch` call of this query, which means that
+    /// this query fails as well.
+    failure: Option<SalsaError>,
 }
 
 impl ActiveQuery {
@@ -122,6 +127,15 @@ impl ActiveQuery {
         self.changed_at = changed_at;
     }
 
+    /// Records that a query read by this query failed, see [`ActiveQuery::failure`].
+    pub(super) fn add_failure(&mut self, error: &SalsaError) {
+        self.failure.get_or_insert_with(|| error.clone());
+    }
+
+    pub(super) fn take_failure(&mut self) -> Option<SalsaError> {
+        self.failure.take()
+    }
+
     pub(super) fn add_synthetic_read(&mut self, durability: Durability, revision: Revision) {
         self.untracked_read = true;
         self.durability = self.durability.min(durability);
@@ -179,6 +193,7 @@ impl ActiveQuery {
             tracked_struct_ids: Default::default(),
             cycle_heads: Default::default(),
             iteration_count,
+            failure: None,
             #[cfg(feature = "accumulator")]
             accumulated: Default::default(),
             #[cfg(feature = "accumulator")]
@@ -197,6 +212,7 @@ impl ActiveQuery {
             ref mut tracked_struct_ids,
             ref mut cycle_heads,
             iteration_count,
+            ref mut failure,
             #[cfg(feature = "accumulator")]
             ref mut accumulated,
             #[cfg(feature = "accumulator")]
@@ -209,6 +225,7 @@ impl ActiveQuery {
             QueryOrigin::derived(input_outputs.drain(..))
         };
         disambiguator_map.clear();
+        *failure = None;
 
         #[cfg(feature = "accumulator")]
         let accumulated_inputs = AtomicInputAccumulatedValues::new(accumulated_inputs);
@@ -243,6 +260,7 @@ impl ActiveQuery {
             tracked_struct_ids,
             cycle_heads,
             iteration_count,
+            failure,
             #[cfg(feature = "accumulator")]
             accumulated,
             #[cfg(feature = "accumulator")]
@@ -253,6 +271,7 @@ impl ActiveQuery {
         tracked_struct_ids.clear();
         *cycle_heads = Default::default();
         *iteration_count = IterationCount::initial();
+        *failure = None;
         #[cfg(feature = "accumulator")]
         accumulated.clear();
     }
@@ -272,6 +291,7 @@ impl ActiveQuery {
             tracked_struct_ids,
             cycle_heads,
             iteration_count,
+            failure,
             #[cfg(feature = "accumulator")]
             accumulated,
             #[cfg(feature = "accumulator")]
@@ -298,6 +318,10 @@ impl ActiveQuery {
             cycle_heads.is_empty(),
             "`ActiveQuery::clear` or `ActiveQuery::into_revisions` should've been called"
         );
+        debug_assert!(
+            failure.is_none(),
+            "`ActiveQuery::clear` or `ActiveQuery::into_revisions` should've been called"
+        );
         #[cfg(feature = "accumulator")]
         {
             *accumulated_inputs = Default::default();
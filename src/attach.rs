@@ -79,6 +79,26 @@ impl Attached {
         op()
     }
 
+    /// Detaches the attached database while executing `op`, and attaches it again afterwards.
+    fn detach<R>(&self, op: impl FnOnce() -> R) -> R {
+        struct DetachGuard<'s> {
+            state: &'s Attached,
+            database: Option<NonNull<dyn Database>>,
+        }
+
+        impl Drop for DetachGuard<'_> {
+            fn drop(&mut self) {
+                self.state.database.set(self.database);
+            }
+        }
+
+        let _guard = DetachGuard {
+            state: self,
+            database: self.database.take(),
+        };
+        op()
+    }
+
     /// Access the "attached" database. Returns `None` if no database is attached.
     /// Databases are attached with `attach_database`.
     #[inline]
@@ -104,6 +124,12 @@ where
     )
 }
 
+/// Execute `op` without the database attached to the current thread, so that it can
+/// attach another database, e.g. a fork executed by a [`crate::Executor`] on this thread.
+pub(crate) fn detach<R>(op: impl FnOnce() -> R) -> R {
+    ATTACHED.with(|a| a.detach(op))
+}
+
 /// Access the "attached" database. Returns `None` if no database is attached.
 /// Databases are attached with `attach_database`.
 #[inline]
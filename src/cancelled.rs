@@ -1,5 +1,11 @@
+use std::any::Any;
+use std::cell::Cell;
 use std::fmt;
 use std::panic::{self, UnwindSafe};
+use std::task::Waker;
+
+use crate::sync::atomic::{AtomicBool, Ordering};
+use crate::sync::{Arc, Mutex};
 
 /// A panic payload indicating that execution of a salsa query was cancelled.
 ///
@@ -7,7 +13,7 @@ use std::panic::{self, UnwindSafe};
 /// *
 /// *
 /// *
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[non_exhaustive]
 pub enum Cancelled {
     /// The query was operating on revision R, but there is a pending write to move to revision R+1.
@@ -17,21 +23,57 @@ pub enum Cancelled {
     /// The query was blocked on another thread, and that thread panicked.
     #[non_exhaustive]
     PropagatedPanic,
+
+    /// The [`CancellationToken`] of the database handle executing the query was cancelled.
+    #[non_exhaustive]
+    Requested,
+
+    /// The deadline of the database handle executing the query has passed.
+    #[non_exhaustive]
+    DeadlineExceeded,
+}
+
+crate::sync::thread_local! {
+    /// Whether this thread is unwinding because of a cancellation that only applies to
+    /// its database handle, see [`Cancelled::is_unwinding_handle`].
+    static UNWINDING_HANDLE: Cell<bool> = const { Cell::new(false) };
 }
 
 impl Cancelled {
     pub(crate) fn throw(self) -> ! {
+        UNWINDING_HANDLE.with(|unwinding| {
+            unwinding.set(matches!(
+                self,
+                Cancelled::Requested | Cancelled::DeadlineExceeded
+            ))
+        });
         // We use resume and not panic here to avoid running the panic
         // hook (that is, to avoid collecting and printing backtrace).
         panic::resume_unwind(Box::new(self));
     }
 
+    /// Unwinds with `payload`, like a cancellation that only applies to the database handle
+    /// of this thread.
+    pub(crate) fn unwind_handle(payload: Box<dyn Any + Send>) -> ! {
+        UNWINDING_HANDLE.with(|unwinding| unwinding.set(true));
+        panic::resume_unwind(payload);
+    }
+
+    /// Returns `true` if this thread is unwinding because of a [`Cancelled::Requested`]
+    /// or [`Cancelled::DeadlineExceeded`].
+    ///
+    /// Threads blocked on a query that is unwound this way can execute the query themselves,
+    /// as the cancellation only applies to the handle executing it.
+    pub(crate) fn is_unwinding_handle() -> bool {
+        crate::sync::thread::panicking() && UNWINDING_HANDLE.with(Cell::get)
+    }
+
     /// Runs `f`, and catches any salsa cancellation.
     pub fn catch<F, T>(f: F) -> Result<T, Cancelled>
     where
         F: FnOnce() -> T + UnwindSafe,
     {
-        match panic::catch_unwind(f) {
+        match catch_unwind(f) {
             Ok(t) => Ok(t),
             Err(payload) => match payload.downcast() {
                 Ok(cancelled) => Err(*cancelled),
@@ -41,11 +83,28 @@ impl Cancelled {
     }
 }
 
+/// Like [`panic::catch_unwind`], but also resets [`Cancelled::is_unwinding_handle`] if it
+/// catches a panic, so that later panics of this thread aren't mistaken for cancellations.
+///
+/// Salsa catches all panics with this function.
+pub(crate) fn catch_unwind<F, T>(f: F) -> std::thread::Result<T>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    let result = panic::catch_unwind(f);
+    if result.is_err() {
+        UNWINDING_HANDLE.with(|unwinding| unwinding.set(false));
+    }
+    result
+}
+
 impl std::fmt::Display for Cancelled {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let why = match self {
             Cancelled::PendingWrite => "pending write",
             Cancelled::PropagatedPanic => "propagated panic",
+            Cancelled::Requested => "cancellation request",
+            Cancelled::DeadlineExceeded => "exceeded deadline",
         };
         f.write_str("cancelled because of ")?;
         f.write_str(why)
@@ -53,3 +112,58 @@ impl std::fmt::Display for Cancelled {
 }
 
 impl std::error::Error for Cancelled {}
+
+/// A token to cancel the queries executed with specific database handles, without
+/// cancelling the other handles like a write does.
+///
+/// Attach the token to a handle with `Storage::set_cancellation_token`. Once the token is
+/// cancelled, the queries executed with that handle unwind with [`Cancelled::Requested`]
+/// the next time they check for cancellation.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<TokenState>);
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+
+    /// The wakers of the pending [`RunAsync`](crate::RunAsync) futures of the handles this
+    /// token is attached to, woken once the token is cancelled.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the queries executed with the handles this token is attached to.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+        self.wake_all();
+    }
+
+    /// Returns `true` if the token was cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Wakes `waker` once the token is cancelled.
+    pub(crate) fn register_waker(&self, waker: &Waker) {
+        {
+            let mut wakers = self.0.wakers.lock();
+            if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+        }
+        if self.is_cancelled() {
+            self.wake_all();
+        }
+    }
+
+    fn wake_all(&self) {
+        let wakers = std::mem::take(&mut *self.0.wakers.lock());
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
@@ -0,0 +1,206 @@
+//! Recording of salsa events as a timeline in the Chrome Trace Event Format.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use rustc_hash::FxHashMap;
+
+use crate::dependency_graph::{FmtIndex, JsonString};
+use crate::sync::thread::ThreadId;
+use crate::sync::{Arc, Mutex};
+use crate::{Database, DatabaseKeyIndex, Event, EventKind, EventKinds, EventSubscriber};
+
+/// Records salsa events as a trace in the [Chrome Trace Event Format], which can be viewed
+/// in [Perfetto](https://ui.perfetto.dev) or `chrome://tracing`.
+///
+/// Every thread gets its own track, showing the queries executed by it as nested spans
+/// and the time spent blocked on other threads as `blocked on` spans. The span of a query
+/// also ends if it panics, is cancelled or yields. Cancellations and
+/// new revisions are shown as markers.
+///
+/// Register the recorder as an [`EventSubscriber`] of the database:
+///
+/// ```
+/// #[salsa::db]
+/// #[derive(Clone)]
+/// struct MyDatabase {
+///     storage: salsa::Storage<Self>,
+/// }
+///
+/// #[salsa::db]
+/// impl salsa::Database for MyDatabase {}
+///
+/// let recorder = salsa::ChromeTraceRecorder::new();
+/// let db = MyDatabase {
+///     storage: salsa::Storage::builder()
+///         .event_subscriber(recorder.clone())
+///         .build(),
+/// };
+/// // ... use `db` ...
+/// let json = recorder.to_json(&db);
+/// ```
+///
+/// [Chrome Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+#[derive(Clone)]
+pub struct ChromeTraceRecorder {
+    start: Instant,
+    trace: Arc<Mutex<Trace>>,
+}
+
+#[derive(Default)]
+struct Trace {
+    /// Map from the id of a thread to its track.
+    threads: FxHashMap<ThreadId, usize>,
+    entries: Vec<TraceEntry>,
+}
+
+struct TraceEntry {
+    track: usize,
+    timestamp: Duration,
+    phase: Phase,
+}
+
+/// The kind of a trace entry. Keys are only formatted when rendering the trace, as the
+/// events are not necessarily reported with a database attached.
+enum Phase {
+    /// The start of the span of an execution of the query.
+    Execute(DatabaseKeyIndex),
+    /// The start of the span of a thread blocking on a query executing on another thread.
+    Block(DatabaseKeyIndex, ThreadId),
+    /// The end of the innermost span.
+    End,
+    /// A marker for an iteration of a cycle on the track of the thread.
+    CycleIteration(DatabaseKeyIndex, u32),
+    /// A marker across all tracks.
+    GlobalMarker(String),
+}
+
+impl Default for ChromeTraceRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChromeTraceRecorder {
+    /// Creates a recorder, whose trace starts now.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            trace: Arc::default(),
+        }
+    }
+
+    /// Records `event`, if it is relevant for the trace.
+    pub fn record(&self, event: &Event) {
+        let phase = match event.kind {
+            EventKind::WillExecute { database_key } => Phase::Execute(database_key),
+            EventKind::DidExecute { .. }
+            | EventKind::DidUnwindExecute { .. }
+            | EventKind::DidBlockOn { .. } => Phase::End,
+            EventKind::WillBlockOn {
+                other_thread_id,
+                database_key,
+            } => Phase::Block(database_key, other_thread_id),
+            EventKind::WillIterateCycle {
+                database_key,
+                iteration_count,
+                ..
+            } => Phase::CycleIteration(database_key, iteration_count.as_u32()),
+            EventKind::DidSetCancellationFlag => Phase::GlobalMarker(String::from("cancellation")),
+            EventKind::DidStartNewRevision { revision } => {
+                Phase::GlobalMarker(format!("new revision {revision:?}"))
+            }
+            _ => return,
+        };
+
+        let timestamp = self.start.elapsed();
+        let mut trace = self.trace.lock();
+        let tracks = trace.threads.len();
+        let track = *trace.threads.entry(event.thread_id).or_insert(tracks);
+        trace.entries.push(TraceEntry {
+            track,
+            timestamp,
+            phase,
+        });
+    }
+
+    /// Renders the events recorded so far as a JSON array of trace events, naming the queries
+    /// after the ingredients of `db`.
+    pub fn to_json(&self, db: &dyn Database) -> String {
+        let zalsa = db.zalsa();
+        let key = |key: DatabaseKeyIndex| {
+            FmtIndex(
+                zalsa.lookup_ingredient(key.ingredient_index()),
+                key.key_index(),
+            )
+        };
+        let trace = self.trace.lock();
+        let mut out = String::from("[");
+
+        let mut threads = trace.threads.iter().collect::<Vec<_>>();
+        threads.sort_by_key(|&(_, &track)| track);
+        for (thread_id, track) in threads {
+            if out.len() > 1 {
+                out.push(',');
+            }
+            _ = write!(
+                out,
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{track},\"args\":{{\"name\":{}}}}}",
+                JsonString(&format!("{thread_id:?}")),
+            );
+        }
+
+        for entry in &trace.entries {
+            if out.len() > 1 {
+                out.push(',');
+            }
+            let (name, ph, cat, scope) = match &entry.phase {
+                Phase::Execute(database_key) => {
+                    (key(*database_key).to_string(), "B", "execute", "")
+                }
+                Phase::Block(database_key, other_thread_id) => (
+                    format!("blocked on {} ({other_thread_id:?})", key(*database_key)),
+                    "B",
+                    "block",
+                    "",
+                ),
+                Phase::End => (String::new(), "E", "", ""),
+                Phase::CycleIteration(database_key, iteration_count) => (
+                    format!("{}: cycle iteration {iteration_count}", key(*database_key)),
+                    "i",
+                    "marker",
+                    ",\"s\":\"t\"",
+                ),
+                Phase::GlobalMarker(name) => (name.clone(), "i", "marker", ",\"s\":\"g\""),
+            };
+            _ = write!(
+                out,
+                "{{\"name\":{},\"cat\":\"{cat}\",\"ph\":\"{ph}\",\"ts\":{}.{:03},\"pid\":1,\"tid\":{}{scope}}}",
+                JsonString(&name),
+                entry.timestamp.as_micros(),
+                entry.timestamp.subsec_nanos() % 1000,
+                entry.track,
+            );
+        }
+
+        out.push(']');
+        out
+    }
+}
+
+impl EventSubscriber for ChromeTraceRecorder {
+    fn interests(&self) -> EventKinds {
+        EventKinds::WILL_EXECUTE
+            | EventKinds::DID_EXECUTE
+            | EventKinds::DID_UNWIND_EXECUTE
+            | EventKinds::WILL_BLOCK_ON
+            | EventKinds::DID_BLOCK_ON
+            | EventKinds::WILL_ITERATE_CYCLE
+            | EventKinds::DID_SET_CANCELLATION_FLAG
+            | EventKinds::DID_START_NEW_REVISION
+    }
+
+    fn on_event(&self, event: &Event) {
+        self.record(event);
+    }
+}
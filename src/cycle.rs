@@ -260,6 +260,13 @@ impl CycleHeadKeys {
     pub(crate) fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Inserts the cycle heads of `other`.
+    pub(crate) fn extend(&mut self, other: CycleHeadKeys) {
+        for database_key_index in other.0 {
+            self.insert(database_key_index);
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
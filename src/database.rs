@@ -3,7 +3,7 @@ use std::ptr::NonNull;
 
 use crate::views::DatabaseDownCaster;
 use crate::zalsa::{IngredientIndex, ZalsaDatabase};
-use crate::{Durability, Revision};
+use crate::{Durability, Prefetched, Revision, RunAsync, SalsaError};
 
 #[derive(Copy, Clone)]
 pub struct RawDatabase<'db> {
@@ -59,6 +59,159 @@ pub trait Database: Send + ZalsaDatabase + AsDynDatabase {
         zalsa_mut.runtime_mut().report_tracked_write(durability);
     }
 
+    /// Enables or disables explanations of why queries are re-executed.
+    ///
+    /// When enabled, salsa records which dependencies failed verification and emits an
+    /// [`EventKind::WillReexecute`](`crate::EventKind::WillReexecute`) event before a
+    /// memoized query is executed again. This has a cost, so it is disabled by default.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn set_explain_reexecutions(&mut self, enabled: bool) {
+        self.zalsa_mut().set_explain_reexecutions(enabled);
+    }
+
+    /// Enables or disables the built-in profiler.
+    ///
+    /// While enabled, salsa aggregates the execution counts and times, validation hits,
+    /// cycle iterations and blocking of each tracked function. The results can be
+    /// retrieved with `dyn Database::query_profiles`.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn set_profiling(&mut self, enabled: bool) {
+        self.zalsa_mut().set_profiling(enabled);
+    }
+
+    /// Enables or disables the blocking watchdog.
+    ///
+    /// When a thread has been blocked on a query executing on another thread for longer
+    /// than `timeout`, the watchdog logs a warning with the current wait graph: which
+    /// thread waits on which, for which query, and the query stacks of the blocked threads.
+    /// The same snapshot is available at any time through `dyn Database::wait_graph`, which
+    /// only includes the query stacks while the watchdog is enabled, as capturing them
+    /// slows down every blocking wait.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn set_blocking_watchdog(&mut self, timeout: Option<std::time::Duration>) {
+        self.zalsa_mut().set_blocking_watchdog(timeout);
+    }
+
+    /// Enables or disables the parallel verification of memos.
+    ///
+    /// When salsa deeply verifies a memo, e.g. after a change to many inputs, it checks the
+    /// dependencies of the memo one by one, in the order they were read. With parallel
+    /// verification, the dependencies that were read one after the other are checked in
+    /// parallel instead, if there are at least `min_edges` of them. They are checked on forks
+    /// of the database executed by the [`Executor`](crate::Executor) of the storage, including
+    /// their own dependencies, and the remaining checks are skipped as soon as one dependency
+    /// changed.
+    ///
+    /// The forks never execute a query, so that no query is executed that would not have
+    /// been executed if an earlier dependency had been found changed first. Dependencies that
+    /// need a query to be executed, e.g. to backdate its value, are checked again in order.
+    /// The same applies to dependencies that panic or that need a query executing on another
+    /// thread, instead of blocking the forks or propagating the panic.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn set_parallel_verification(&mut self, min_edges: Option<usize>) {
+        self.zalsa_mut().set_parallel_verification(min_edges);
+    }
+
+    /// Sets a database-wide budget, in bytes, for the memoized values of all tracked functions.
+    ///
+    /// While a budget is set, salsa records the order in which memos are used across all
+    /// tracked functions. [`Database::trigger_lru_eviction`] then evicts the least recently
+    /// used values until the total size of the values that can be evicted falls below the
+    /// budget. The size of a value is its stack size plus the result of the `heap_size`
+    /// function of its tracked function. This is independent of the per-function `lru` limits.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.zalsa_mut().set_memory_budget(budget);
+    }
+
+    /// Frees the interned values of all interned structs that were not read in the current
+    /// revision or the `revisions` revisions before it, returning the number of freed values.
+    ///
+    /// Interned values are otherwise only reclaimed lazily, when a new value is interned and
+    /// a stale slot can be reused (see the `revisions` option of `#[salsa::interned]`). This
+    /// method instead drops the fields and memos of the freed values immediately and shrinks
+    /// the hash tables mapping the fields to their ids, e.g. to release memory after closing
+    /// a project. The slots of freed values are reused by values interned later, and pages
+    /// of which all slots were freed can be released with [`Database::release_empty_pages`].
+    ///
+    /// Values that are never reclaimed lazily are not freed either, i.e. values interned
+    /// outside of a tracked function or by a query with a durability higher than
+    /// [`Durability::LOW`](crate::Durability::LOW), and values of interned structs with
+    /// `revisions = usize::MAX`.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn collect_interned_garbage(&mut self, revisions: usize) -> usize {
+        self.zalsa_mut().collect_interned_garbage(revisions)
+    }
+
+    /// Releases the memory of table pages whose slots were all freed, returning the number
+    /// of bytes released.
+    ///
+    /// Salsa structs are stored in pages of slots. The slots of deleted tracked structs and
+    /// of interned values freed by [`Database::collect_interned_garbage`] are reused for new
+    /// values, but the pages themselves are otherwise never released. This releases every
+    /// page of which all slots were freed, e.g. after a large number of tracked structs was
+    /// deleted. Queries that depended on values in the released pages are re-executed.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn release_empty_pages(&mut self) -> usize {
+        self.zalsa_mut().release_empty_pages()
+    }
+
+    /// Serializes the memoized values of all `#[salsa::tracked(persist)]` functions,
+    /// together with their dependency edges.
+    ///
+    /// Only memos that are up-to-date in the current revision, that are keyed by a
+    /// `#[salsa::input(stable_id)]` struct, and whose dependencies are all keyed by
+    /// `#[salsa::input(stable_id)]` structs are written. See [`Database::restore_memos`].
+    fn persist_memos(&self) -> Vec<u8> {
+        crate::persistence::persist_memos(self.zalsa())
+    }
+
+    /// Restores memos written by [`Database::persist_memos`], returning how many were restored.
+    ///
+    /// The inputs that the memos were computed from must have been recreated, with the same
+    /// stable identities, before calling this method. Memos that depend on an input whose
+    /// values differ from when they were persisted are not restored, nor are memos of
+    /// functions whose database trait has not been used with this database yet (this never
+    /// applies to functions taking `&dyn salsa::Database`). Restored memos are validated
+    /// against the revisions of their inputs before being reused, so changing an input
+    /// afterwards re-executes the dependent functions as usual.
+    ///
+    /// **WARNING:** Just like an ordinary write, this method triggers
+    /// cancellation. If you invoke it while a snapshot exists, it
+    /// will block until that snapshot is dropped -- if that snapshot
+    /// is owned by the current thread, this could trigger deadlock.
+    fn restore_memos(&mut self, data: &[u8]) -> Result<usize, crate::PersistenceError> {
+        crate::persistence::restore_memos(self.zalsa_mut(), data)
+    }
+
     /// Reports that the query depends on some state unknown to salsa.
     ///
     /// Queries which report untracked reads will be re-executed in the next
@@ -98,6 +251,58 @@ pub trait Database: Send + ZalsaDatabase + AsDynDatabase {
         zalsa.unwind_if_revision_cancelled(zalsa_local);
     }
 
+    /// Execute `op`, returning an error instead of unwinding if a query executed by it is
+    /// cancelled, hits an unrecoverable cycle, or is blocked on a thread that panicked.
+    ///
+    /// The errors are still raised as unwinding panics internally, so this requires
+    /// `panic = "unwind"`. Panics that are not raised by salsa are resumed. The `try_fetch`
+    /// functions generated for tracked functions return these errors without unwinding.
+    fn try_run<R>(&self, op: impl FnOnce(&Self) -> R) -> Result<R, SalsaError>
+    where
+        Self: Sized,
+    {
+        SalsaError::catch(|| op(self))
+    }
+
+    /// Returns a future that executes `op`, retrying it instead of blocking the thread when
+    /// a query is executing on another thread.
+    ///
+    /// This is not a suspension point: `op` is unwound and the future is woken to execute it
+    /// again from the start once the other thread completes the query, see [`RunAsync`]. As
+    /// `op` may be executed several times, it should only fetch queries, whose results are
+    /// memoized across the retries. Like [`Self::try_run`], the future returns an error if a
+    /// query is cancelled, e.g. by the [`CancellationToken`](crate::CancellationToken) of the
+    /// handle.
+    fn run_async<R, F>(self, op: F) -> RunAsync<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self) -> R,
+    {
+        RunAsync::new(self, op)
+    }
+
+    /// Executes `op` with every key in `keys` in parallel, e.g. to compute the diagnostics
+    /// of all open files after a change before they are requested.
+    ///
+    /// Every execution gets its own fork of the database, and the executions are dispatched
+    /// through the [`Executor`](crate::Executor) of the storage. To prefetch different
+    /// queries together, pass closures or an enum of the queries as keys.
+    ///
+    /// An execution is skipped if the first query it claims is already executing with
+    /// another handle, which computes the query instead. Executions are cancelled if a
+    /// query they execute is cancelled, e.g. because of a pending write or the
+    /// [`CancellationToken`](crate::CancellationToken) of the handle. Executions started
+    /// after the handle was cancelled return right away. Other panics are resumed once all
+    /// executions completed.
+    fn prefetch<T, F>(&self, keys: impl IntoIterator<Item = T>, op: F) -> Prefetched
+    where
+        Self: Sized,
+        T: Send,
+        F: Fn(&Self, T) + Sync,
+    {
+        crate::prefetch::prefetch(self, keys, op)
+    }
+
     /// Execute `op` with the database in thread-local storage for debug print-outs.
     #[inline(always)]
     fn attach<R>(&self, op: impl FnOnce(&Self) -> R) -> R
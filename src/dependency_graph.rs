@@ -0,0 +1,310 @@
+//! Export of the dependency graph recorded by tracked functions, for debugging.
+
+use std::fmt::{self, Write};
+
+use crate::hash::FxIndexSet;
+use crate::ingredient::Ingredient;
+use crate::zalsa::Zalsa;
+use crate::zalsa_local::{QueryEdge, QueryEdgeKind};
+use crate::{Database, DatabaseKeyIndex, Durability, Id, Revision};
+
+impl dyn Database {
+    /// Returns the dependency graph of all memoized tracked function values.
+    ///
+    /// The graph also contains the inputs and outputs of those memos, e.g. input fields.
+    pub fn dependency_graph(&self) -> QueryGraph {
+        let zalsa = self.zalsa();
+        let mut keys = FxIndexSet::default();
+        for (struct_index, function_index) in zalsa.memo_ingredients() {
+            let function = zalsa.lookup_ingredient(function_index);
+            let salsa_struct = zalsa.lookup_ingredient(struct_index);
+            for slot_id in zalsa.table().ids_of(struct_index) {
+                // The ids of reused slots have a later generation than the slot ids.
+                let Some(id) = salsa_struct.current_id(zalsa, slot_id) else {
+                    continue;
+                };
+                if function.dependency_info(zalsa, id).is_some() {
+                    keys.insert(DatabaseKeyIndex::new(function_index, id));
+                }
+            }
+        }
+        QueryGraph::collect(zalsa, keys)
+    }
+
+    /// Returns the dependency graph reachable from `key`, following both the
+    /// inputs and the outputs of memoized values.
+    pub fn dependency_graph_from(&self, key: DatabaseKeyIndex) -> QueryGraph {
+        let mut keys = FxIndexSet::default();
+        keys.insert(key);
+        QueryGraph::collect(self.zalsa(), keys)
+    }
+}
+
+/// The revision information and dependencies of a key, as reported by
+/// [`Ingredient::dependency_info`].
+pub struct DependencyInfo<'db> {
+    pub(crate) changed_at: Revision,
+    pub(crate) verified_at: Option<Revision>,
+    pub(crate) durability: Durability,
+    pub(crate) edges: &'db [QueryEdge],
+}
+
+/// A snapshot of the dependencies between queries, see `dyn Database::dependency_graph`.
+#[derive(Debug)]
+pub struct QueryGraph {
+    nodes: Vec<DependencyNode>,
+    edges: Vec<DependencyEdge>,
+}
+
+/// A query, tracked struct or input field in a [`QueryGraph`].
+#[derive(Debug)]
+pub struct DependencyNode {
+    key: DatabaseKeyIndex,
+    debug_name: &'static str,
+    label: String,
+    changed_at: Option<Revision>,
+    verified_at: Option<Revision>,
+    durability: Option<Durability>,
+}
+
+/// An edge of a [`QueryGraph`], from the node at index `from` to the node at index `to`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DependencyEdge {
+    from: usize,
+    to: usize,
+    kind: DependencyEdgeKind,
+}
+
+/// The kind of a [`DependencyEdge`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DependencyEdgeKind {
+    /// `from` read `to`.
+    Input,
+    /// `from` created or specified `to`.
+    Output,
+}
+
+impl QueryGraph {
+    /// Collects the graph reachable from `keys`, which are extended in breadth-first order.
+    fn collect(zalsa: &Zalsa, mut keys: FxIndexSet<DatabaseKeyIndex>) -> Self {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+
+        let mut index = 0;
+        while let Some(&key) = keys.get_index(index) {
+            let ingredient = zalsa.lookup_ingredient(key.ingredient_index());
+            // Slots released with their page have no dependencies anymore.
+            let info = zalsa
+                .table()
+                .try_ingredient_index(key.key_index())
+                .and_then(|_| ingredient.dependency_info(zalsa, key.key_index()));
+
+            for edge in info.iter().flat_map(|info| info.edges) {
+                let (to, kind) = match edge.kind() {
+                    QueryEdgeKind::Input(to) => (to, DependencyEdgeKind::Input),
+                    QueryEdgeKind::Output(to) => (to, DependencyEdgeKind::Output),
+                };
+                let (to, _) = keys.insert_full(to);
+                edges.push(DependencyEdge {
+                    from: index,
+                    to,
+                    kind,
+                });
+            }
+
+            nodes.push(DependencyNode {
+                key,
+                debug_name: ingredient.debug_name(),
+                label: FmtIndex(ingredient, key.key_index()).to_string(),
+                changed_at: info.as_ref().map(|info| info.changed_at),
+                verified_at: info.as_ref().and_then(|info| info.verified_at),
+                durability: info.as_ref().map(|info| info.durability),
+            });
+            index += 1;
+        }
+
+        QueryGraph { nodes, edges }
+    }
+
+    /// The nodes of the graph, starting with the keys the graph was collected from.
+    pub fn nodes(&self) -> &[DependencyNode] {
+        &self.nodes
+    }
+
+    /// The edges of the graph, in the order they were recorded for each node.
+    pub fn edges(&self) -> &[DependencyEdge] {
+        &self.edges
+    }
+
+    /// Renders the graph in the Graphviz DOT language.
+    ///
+    /// Input edges are drawn as solid arrows, output edges as dashed arrows.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph salsa {\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut label = node.label.clone();
+            if let Some(changed_at) = node.changed_at {
+                _ = write!(label, "\nchanged_at: {changed_at:?}");
+            }
+            if let Some(verified_at) = node.verified_at {
+                _ = write!(label, "\nverified_at: {verified_at:?}");
+            }
+            if let Some(durability) = node.durability {
+                _ = write!(label, "\ndurability: {}", durability.index());
+            }
+            _ = writeln!(out, "    n{index} [label={}];", DotString(&label));
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                DependencyEdgeKind::Input => "",
+                DependencyEdgeKind::Output => " [style=dashed]",
+            };
+            _ = writeln!(out, "    n{} -> n{}{style};", edge.from, edge.to);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the graph as JSON, in the form
+    /// `{"nodes": [{"id", "ingredient", "key", ...}], "edges": [{"from", "to", "kind"}]}`.
+    ///
+    /// Revisions and durabilities are rendered as numbers, or `null` if unknown.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"nodes\":[");
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            _ = write!(
+                out,
+                "{{\"id\":{index},\"ingredient\":{},\"key\":{},\"changed_at\":{},\"verified_at\":{},\"durability\":{}}}",
+                JsonString(node.debug_name),
+                JsonString(&node.label),
+                JsonNumber(node.changed_at.map(Revision::as_usize)),
+                JsonNumber(node.verified_at.map(Revision::as_usize)),
+                JsonNumber(node.durability.map(Durability::index)),
+            );
+        }
+        out.push_str("],\"edges\":[");
+        for (index, edge) in self.edges.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            let kind = match edge.kind {
+                DependencyEdgeKind::Input => "input",
+                DependencyEdgeKind::Output => "output",
+            };
+            _ = write!(
+                out,
+                "{{\"from\":{},\"to\":{},\"kind\":\"{kind}\"}}",
+                edge.from, edge.to
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl DependencyNode {
+    /// The key identifying the node.
+    pub fn key(&self) -> DatabaseKeyIndex {
+        self.key
+    }
+
+    /// The debug name of the node's ingredient.
+    pub fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    /// The node's key, formatted by its ingredient, e.g. `my_query(Id(0))`.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The revision in which the node's value last changed, if known.
+    pub fn changed_at(&self) -> Option<Revision> {
+        self.changed_at
+    }
+
+    /// The revision in which the node's memo was last verified, if it is a memo.
+    pub fn verified_at(&self) -> Option<Revision> {
+        self.verified_at
+    }
+
+    /// The durability of the node's value, if known.
+    pub fn durability(&self) -> Option<Durability> {
+        self.durability
+    }
+}
+
+impl DependencyEdge {
+    /// The index of the dependent node in [`QueryGraph::nodes`].
+    pub fn from(&self) -> usize {
+        self.from
+    }
+
+    /// The index of the dependency in [`QueryGraph::nodes`].
+    pub fn to(&self) -> usize {
+        self.to
+    }
+
+    /// Whether the dependency was read or created by the dependent node.
+    pub fn kind(&self) -> DependencyEdgeKind {
+        self.kind
+    }
+}
+
+/// Formats the key `Id` of an ingredient, e.g. as `query(Id(0))`.
+pub(crate) struct FmtIndex<'a>(pub(crate) &'a dyn Ingredient, pub(crate) Id);
+
+impl fmt::Display for FmtIndex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_index(self.1, f)
+    }
+}
+
+struct DotString<'a>(&'a str);
+
+impl fmt::Display for DotString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+pub(crate) struct JsonString<'a>(pub(crate) &'a str);
+
+impl fmt::Display for JsonString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+struct JsonNumber(Option<usize>);
+
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(n) => write!(f, "{n}"),
+            None => f.write_str("null"),
+        }
+    }
+}
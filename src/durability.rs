@@ -16,16 +16,21 @@
 /// frequently editing. Medium or high durabilities are used for
 /// configuration, the source from library crates, or other things
 /// that are unlikely to be edited.
+///
+/// There are three durability levels by default. Enabling the `more_durabilities` feature
+/// raises that to eight, with the additional levels between [`Durability::MEDIUM`] and
+/// [`Durability::HIGH`]; use [`Durability::from_level`] to refer to them.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Durability(DurabilityVal);
 
 impl std::fmt::Debug for Durability {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if f.alternate() {
-            match self.0 {
-                DurabilityVal::Low => f.write_str("Durability::LOW"),
-                DurabilityVal::Medium => f.write_str("Durability::MEDIUM"),
-                DurabilityVal::High => f.write_str("Durability::HIGH"),
+            match *self {
+                Durability::LOW => f.write_str("Durability::LOW"),
+                Durability::MEDIUM => f.write_str("Durability::MEDIUM"),
+                Durability::HIGH => f.write_str("Durability::HIGH"),
+                _ => write!(f, "Durability::from_level({})", self.index()),
             }
         } else {
             f.debug_tuple("Durability")
@@ -38,38 +43,55 @@ impl std::fmt::Debug for Durability {
 // We use an enum here instead of a u8 for niches.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum DurabilityVal {
-    Low = 0,
-    Medium = 1,
-    High = 2,
+    Level0 = 0,
+    Level1 = 1,
+    Level2 = 2,
+    Level3 = 3,
+    Level4 = 4,
+    Level5 = 5,
+    Level6 = 6,
+    Level7 = 7,
 }
 
-impl From<u8> for DurabilityVal {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => DurabilityVal::Low,
-            1 => DurabilityVal::Medium,
-            2 => DurabilityVal::High,
+impl DurabilityVal {
+    const fn from_level(level: usize) -> Self {
+        match level {
+            0 => DurabilityVal::Level0,
+            1 => DurabilityVal::Level1,
+            2 => DurabilityVal::Level2,
+            3 => DurabilityVal::Level3,
+            4 => DurabilityVal::Level4,
+            5 => DurabilityVal::Level5,
+            6 => DurabilityVal::Level6,
+            7 => DurabilityVal::Level7,
             _ => panic!("invalid durability"),
         }
     }
 }
 
 impl Durability {
+    /// Number of durability levels.
+    pub const LEVELS: usize = if cfg!(feature = "more_durabilities") {
+        8
+    } else {
+        3
+    };
+
     /// Low durability: things that change frequently.
     ///
     /// Example: part of the crate being edited
-    pub const LOW: Durability = Durability(DurabilityVal::Low);
+    pub const LOW: Durability = Durability(DurabilityVal::Level0);
 
     /// Medium durability: things that change sometimes, but rarely.
     ///
     /// Example: a Cargo.toml file
-    pub const MEDIUM: Durability = Durability(DurabilityVal::Medium);
+    pub const MEDIUM: Durability = Durability(DurabilityVal::Level1);
 
     /// High durability: things that are not expected to change under
     /// common usage.
     ///
     /// Example: the standard library or something from crates.io
-    pub const HIGH: Durability = Durability(DurabilityVal::High);
+    pub const HIGH: Durability = Durability::from_level(Self::LEVELS - 1);
 
     /// The minimum possible durability; equivalent to LOW but
     /// "conceptually" distinct (i.e., if we add more durability
@@ -81,12 +103,25 @@ impl Durability {
     /// levels, this could change).
     pub(crate) const MAX: Durability = Self::HIGH;
 
-    /// Number of durability levels.
-    pub(crate) const LEN: usize = Self::HIGH.0 as usize + 1;
+    /// Returns the durability at `level`, where level 0 is [`Durability::LOW`] and
+    /// level `Durability::LEVELS - 1` is [`Durability::HIGH`].
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not below [`Durability::LEVELS`].
+    pub const fn from_level(level: usize) -> Durability {
+        assert!(level < Self::LEVELS, "invalid durability");
+        Durability(DurabilityVal::from_level(level))
+    }
 
     pub(crate) fn index(self) -> usize {
         self.0 as usize
     }
+
+    /// Returns the durability with the given [`index`](`Self::index`), if any.
+    pub(crate) fn from_index(index: usize) -> Option<Durability> {
+        (index < Self::LEVELS).then(|| Durability::from_level(index))
+    }
 }
 
 impl Default for Durability {
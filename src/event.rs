@@ -1,4 +1,8 @@
+use std::ops::{BitOr, BitOrAssign};
+use std::time::Duration;
+
 use crate::cycle::IterationCount;
+use crate::durability::Durability;
 use crate::key::DatabaseKeyIndex;
 use crate::sync::thread::{self, ThreadId};
 use crate::Revision;
@@ -6,7 +10,7 @@ use crate::Revision;
 /// The `Event` struct identifies various notable things that can
 /// occur during salsa execution. Instances of this struct are given
 /// to `salsa_event`.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Event {
     /// The id of the thread that triggered the event.
     pub thread_id: ThreadId,
@@ -25,7 +29,7 @@ impl Event {
 }
 
 /// An enum identifying the various kinds of events that can occur.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum EventKind {
     /// Occurs when we found that all inputs to a memoized value are
     /// up-to-date and hence the value can be re-used without
@@ -51,6 +55,16 @@ pub enum EventKind {
         database_key: DatabaseKeyIndex,
     },
 
+    /// Indicates that this thread stopped blocking on another thread, after a
+    /// [`EventKind::WillBlockOn`] event.
+    DidBlockOn {
+        /// The id of the thread we blocked on.
+        other_thread_id: ThreadId,
+
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+    },
+
     /// Indicates that the function for this query will be executed.
     /// This is either because it has never executed before or because
     /// its inputs may be out of date.
@@ -59,6 +73,40 @@ pub enum EventKind {
         database_key: DatabaseKeyIndex,
     },
 
+    /// Indicates that the function for this query finished executing, after a
+    /// [`EventKind::WillExecute`] event, and that its new value has been memoized.
+    DidExecute {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// The time spent executing the function, including the functions it called.
+        duration: Duration,
+
+        /// Whether the new value was equal to the old one and has been backdated to
+        /// the revision in which the old value changed.
+        backdated: bool,
+    },
+
+    /// Indicates that the function for this query did not finish executing after a
+    /// [`EventKind::WillExecute`] event, because it panicked, failed, was cancelled or yielded,
+    /// and that no new value has been memoized.
+    DidUnwindExecute {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+    },
+
+    /// Indicates why the function for this query will be executed again.
+    ///
+    /// Only emitted if enabled with [`Database::set_explain_reexecutions`](`crate::Database::set_explain_reexecutions`),
+    /// immediately before the corresponding [`EventKind::WillExecute`].
+    WillReexecute {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// Why the previously memoized value could not be reused.
+        reason: ReexecuteReason,
+    },
+
     WillIterateCycle {
         /// The database-key for the cycle head. Implements `Debug`.
         database_key: DatabaseKeyIndex,
@@ -75,6 +123,12 @@ pub enum EventKind {
     /// and panic with a sentinel value of type [`Cancelled`](`crate::Cancelled`).
     DidSetCancellationFlag,
 
+    /// Indicates that a new revision has started, e.g. because an input was set.
+    DidStartNewRevision {
+        /// The new current revision.
+        revision: Revision,
+    },
+
     /// Discovered that a query used to output a given output but no longer does.
     WillDiscardStaleOutput {
         /// Key for the query that is executing and which no longer outputs the given value.
@@ -90,6 +144,25 @@ pub enum EventKind {
         key: DatabaseKeyIndex,
     },
 
+    /// The memoized value of this query was evicted by its LRU policy, the memory budget
+    /// or because it wasn't verified in the last `evict_after_revisions` revisions.
+    ///
+    /// The memo itself is kept, so that the value can still be backdated when it is recomputed.
+    DidEvictLru {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+    },
+
+    /// Indicates that the automatic eviction configured with `StorageBuilder::auto_evict`
+    /// ran as part of starting a new revision.
+    DidAutoEvict {
+        /// The revision that was started.
+        revision: Revision,
+
+        /// How long the eviction took.
+        duration: Duration,
+    },
+
     /// Discarded accumulated data from a given fn
     DidDiscardAccumulated {
         /// The key of the fn that accumulated results
@@ -99,6 +172,24 @@ pub enum EventKind {
         accumulator: DatabaseKeyIndex,
     },
 
+    /// Indicates that a field of an input was set.
+    DidSetInputField {
+        /// The key of the input field.
+        key: DatabaseKeyIndex,
+
+        /// The durability of the field after it was set.
+        durability: Durability,
+    },
+
+    /// Indicates that an interned value was garbage collected, so that its slot can be reused.
+    DidGarbageCollectInterned {
+        // The key of the collected value.
+        key: DatabaseKeyIndex,
+
+        // The revision the value was last interned in.
+        revision: Revision,
+    },
+
     /// Indicates that a value was newly interned.
     DidInternValue {
         // The key of the interned value.
@@ -126,3 +217,262 @@ pub enum EventKind {
         revision: Revision,
     },
 }
+
+/// The reason of a [`EventKind::WillReexecute`] event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReexecuteReason {
+    /// A dependency of the query changed.
+    ChangedDependency {
+        /// The chain of dependencies that failed verification, starting with a direct
+        /// dependency of the query and ending with the dependency that changed, e.g. an input field.
+        path: Vec<DatabaseKeyIndex>,
+
+        /// The revision in which the last dependency in `path` changed, if known.
+        changed_at: Option<Revision>,
+    },
+
+    /// The query read state that is not tracked by salsa.
+    UntrackedRead,
+
+    /// The value was specified by another query, which did not specify it again.
+    Assigned,
+
+    /// The memoized value was evicted, e.g. by LRU.
+    Evicted,
+
+    /// The memoized value is a provisional value of a cycle.
+    Provisional,
+
+    /// The memoized value is the initial value of a cycle head, which was not computed by
+    /// executing the query.
+    FixpointInitial,
+
+    /// The memoized value could not be verified, e.g. because verifying its dependencies
+    /// ran into a cycle.
+    Unverified,
+}
+
+/// A set of [`EventKind`]s, used by an [`EventSubscriber`] to declare which events it is interested in.
+///
+/// Sets can be combined with `|`, e.g. `EventKinds::WILL_EXECUTE | EventKinds::DID_EXECUTE`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct EventKinds(u32);
+
+impl EventKinds {
+    /// The empty set.
+    pub const NONE: EventKinds = EventKinds(0);
+
+    /// The set of all event kinds.
+    pub const ALL: EventKinds = EventKinds(u32::MAX);
+
+    /// The kind of [`EventKind::DidValidateMemoizedValue`] events.
+    pub const DID_VALIDATE_MEMOIZED_VALUE: EventKinds = EventKinds(1 << 0);
+    /// The kind of [`EventKind::WillBlockOn`] events.
+    pub const WILL_BLOCK_ON: EventKinds = EventKinds(1 << 1);
+    /// The kind of [`EventKind::DidBlockOn`] events.
+    pub const DID_BLOCK_ON: EventKinds = EventKinds(1 << 2);
+    /// The kind of [`EventKind::WillExecute`] events.
+    pub const WILL_EXECUTE: EventKinds = EventKinds(1 << 3);
+    /// The kind of [`EventKind::DidExecute`] events.
+    pub const DID_EXECUTE: EventKinds = EventKinds(1 << 4);
+    /// The kind of [`EventKind::WillReexecute`] events.
+    pub const WILL_REEXECUTE: EventKinds = EventKinds(1 << 5);
+    /// The kind of [`EventKind::WillIterateCycle`] events.
+    pub const WILL_ITERATE_CYCLE: EventKinds = EventKinds(1 << 6);
+    /// The kind of [`EventKind::WillCheckCancellation`] events.
+    pub const WILL_CHECK_CANCELLATION: EventKinds = EventKinds(1 << 7);
+    /// The kind of [`EventKind::DidSetCancellationFlag`] events.
+    pub const DID_SET_CANCELLATION_FLAG: EventKinds = EventKinds(1 << 8);
+    /// The kind of [`EventKind::DidStartNewRevision`] events.
+    pub const DID_START_NEW_REVISION: EventKinds = EventKinds(1 << 9);
+    /// The kind of [`EventKind::WillDiscardStaleOutput`] events.
+    pub const WILL_DISCARD_STALE_OUTPUT: EventKinds = EventKinds(1 << 10);
+    /// The kind of [`EventKind::DidDiscard`] events.
+    pub const DID_DISCARD: EventKinds = EventKinds(1 << 11);
+    /// The kind of [`EventKind::DidEvictLru`] events.
+    pub const DID_EVICT_LRU: EventKinds = EventKinds(1 << 12);
+    /// The kind of [`EventKind::DidDiscardAccumulated`] events.
+    pub const DID_DISCARD_ACCUMULATED: EventKinds = EventKinds(1 << 13);
+    /// The kind of [`EventKind::DidSetInputField`] events.
+    pub const DID_SET_INPUT_FIELD: EventKinds = EventKinds(1 << 14);
+    /// The kind of [`EventKind::DidGarbageCollectInterned`] events.
+    pub const DID_GARBAGE_COLLECT_INTERNED: EventKinds = EventKinds(1 << 15);
+    /// The kind of [`EventKind::DidInternValue`] events.
+    pub const DID_INTERN_VALUE: EventKinds = EventKinds(1 << 16);
+    /// The kind of [`EventKind::DidReuseInternedValue`] events.
+    pub const DID_REUSE_INTERNED_VALUE: EventKinds = EventKinds(1 << 17);
+    /// The kind of [`EventKind::DidValidateInternedValue`] events.
+    pub const DID_VALIDATE_INTERNED_VALUE: EventKinds = EventKinds(1 << 18);
+    /// The kind of [`EventKind::DidAutoEvict`] events.
+    pub const DID_AUTO_EVICT: EventKinds = EventKinds(1 << 19);
+    /// The kind of [`EventKind::DidUnwindExecute`] events.
+    pub const DID_UNWIND_EXECUTE: EventKinds = EventKinds(1 << 20);
+
+    /// Returns the set containing only the kind of `kind`.
+    pub const fn of(kind: &EventKind) -> EventKinds {
+        match kind {
+            EventKind::DidValidateMemoizedValue { .. } => Self::DID_VALIDATE_MEMOIZED_VALUE,
+            EventKind::WillBlockOn { .. } => Self::WILL_BLOCK_ON,
+            EventKind::DidBlockOn { .. } => Self::DID_BLOCK_ON,
+            EventKind::WillExecute { .. } => Self::WILL_EXECUTE,
+            EventKind::DidExecute { .. } => Self::DID_EXECUTE,
+            EventKind::DidUnwindExecute { .. } => Self::DID_UNWIND_EXECUTE,
+            EventKind::WillReexecute { .. } => Self::WILL_REEXECUTE,
+            EventKind::WillIterateCycle { .. } => Self::WILL_ITERATE_CYCLE,
+            EventKind::WillCheckCancellation => Self::WILL_CHECK_CANCELLATION,
+            EventKind::DidSetCancellationFlag => Self::DID_SET_CANCELLATION_FLAG,
+            EventKind::DidStartNewRevision { .. } => Self::DID_START_NEW_REVISION,
+            EventKind::WillDiscardStaleOutput { .. } => Self::WILL_DISCARD_STALE_OUTPUT,
+            EventKind::DidDiscard { .. } => Self::DID_DISCARD,
+            EventKind::DidEvictLru { .. } => Self::DID_EVICT_LRU,
+            EventKind::DidAutoEvict { .. } => Self::DID_AUTO_EVICT,
+            EventKind::DidDiscardAccumulated { .. } => Self::DID_DISCARD_ACCUMULATED,
+            EventKind::DidSetInputField { .. } => Self::DID_SET_INPUT_FIELD,
+            EventKind::DidGarbageCollectInterned { .. } => Self::DID_GARBAGE_COLLECT_INTERNED,
+            EventKind::DidInternValue { .. } => Self::DID_INTERN_VALUE,
+            EventKind::DidReuseInternedValue { .. } => Self::DID_REUSE_INTERNED_VALUE,
+            EventKind::DidValidateInternedValue { .. } => Self::DID_VALIDATE_INTERNED_VALUE,
+        }
+    }
+
+    /// Returns the union of `self` and `other`.
+    pub const fn union(self, other: EventKinds) -> EventKinds {
+        EventKinds(self.0 | other.0)
+    }
+
+    /// Returns `true` if `self` and `other` have a kind in common.
+    pub const fn intersects(self, other: EventKinds) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns `true` if `self` contains all kinds of `other`.
+    pub const fn contains(self, other: EventKinds) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for EventKinds {
+    type Output = EventKinds;
+
+    fn bitor(self, other: EventKinds) -> EventKinds {
+        self.union(other)
+    }
+}
+
+impl BitOrAssign for EventKinds {
+    fn bitor_assign(&mut self, other: EventKinds) {
+        *self = self.union(other);
+    }
+}
+
+/// A subscriber to salsa events, registered with `event_subscriber` on the
+/// [storage builder](crate::Storage::builder).
+///
+/// Salsa only constructs the events that at least one subscriber is interested in.
+pub trait EventSubscriber: Send + Sync + 'static {
+    /// Returns the kinds of events this subscriber is interested in.
+    ///
+    /// This is queried once, when the subscriber is registered.
+    fn interests(&self) -> EventKinds;
+
+    /// Invoked for every event whose kind is in [`Self::interests`].
+    fn on_event(&self, event: &Event);
+}
+
+/// The event subscribers of a database.
+#[derive(Default)]
+pub(crate) struct EventSubscribers {
+    /// The union of the interests of all subscribers.
+    interests: EventKinds,
+    subscribers: Vec<(EventKinds, Box<dyn EventSubscriber>)>,
+    /// The callbacks passed to [`crate::Storage::new`] or [`crate::StorageBuilder::event_callback`],
+    /// which are interested in all events and take them by value.
+    callbacks: Vec<Box<dyn Fn(Event) + Send + Sync + 'static>>,
+}
+
+impl EventSubscribers {
+    pub(crate) fn from_callback(
+        event_callback: Option<Box<dyn Fn(Event) + Send + Sync + 'static>>,
+    ) -> Self {
+        let mut subscribers = Self::default();
+        if let Some(event_callback) = event_callback {
+            subscribers.push_callback(event_callback);
+        }
+        subscribers
+    }
+
+    pub(crate) fn push_callback(
+        &mut self,
+        event_callback: Box<dyn Fn(Event) + Send + Sync + 'static>,
+    ) {
+        self.interests = EventKinds::ALL;
+        self.callbacks.push(event_callback);
+    }
+
+    pub(crate) fn push(&mut self, subscriber: Box<dyn EventSubscriber>) {
+        let interests = subscriber.interests();
+        self.interests |= interests;
+        self.subscribers.push((interests, subscriber));
+    }
+
+    /// Returns `true` if a subscriber is interested in any of `kinds`.
+    #[inline(always)]
+    pub(crate) fn is_subscribed(&self, kinds: EventKinds) -> bool {
+        self.interests.intersects(kinds)
+    }
+
+    /// Sends the event to all subscribers that are interested in `kind`.
+    ///
+    /// The event is only constructed if there is such a subscriber.
+    #[inline(always)]
+    pub(crate) fn publish(&self, kind: EventKinds, event: &dyn Fn() -> Event) {
+        if self.is_subscribed(kind) {
+            self.publish_cold(kind, event);
+        }
+    }
+
+    /// Sends the event to all subscribers that are interested in its kind.
+    ///
+    /// Unlike [`Self::publish`], the event is constructed if there is any subscriber.
+    #[inline(always)]
+    pub(crate) fn publish_any(&self, event: &dyn Fn() -> Event) {
+        if self.is_subscribed(EventKinds::ALL) {
+            self.publish_any_cold(event);
+        }
+    }
+
+    // Avoid inlining, as events are typically only enabled for debugging purposes.
+    #[cold]
+    #[inline(never)]
+    fn publish_cold(&self, kind: EventKinds, event: &dyn Fn() -> Event) {
+        let event = event();
+        debug_assert_eq!(EventKinds::of(&event.kind), kind);
+        self.dispatch(kind, event);
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn publish_any_cold(&self, event: &dyn Fn() -> Event) {
+        let event = event();
+        let kind = EventKinds::of(&event.kind);
+        if self.is_subscribed(kind) {
+            self.dispatch(kind, event);
+        }
+    }
+
+    fn dispatch(&self, kind: EventKinds, event: Event) {
+        for (interests, subscriber) in &self.subscribers {
+            if interests.contains(kind) {
+                subscriber.on_event(&event);
+            }
+        }
+
+        // The callbacks take the event by value, so it only has to be cloned for all but the last.
+        if let Some((last, rest)) = self.callbacks.split_last() {
+            for callback in rest {
+                callback(event.clone());
+            }
+            last(event);
+        }
+    }
+}
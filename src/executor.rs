@@ -0,0 +1,145 @@
+//! The executors that run the closures of [`crate::join`] and [`crate::par_map`].
+
+use std::panic::{self, AssertUnwindSafe};
+
+/// A closure executed by an [`Executor`].
+pub type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// Executes the closures passed to [`crate::join`] and [`crate::par_map`], potentially in
+/// parallel.
+///
+/// Set the executor of a database with `StorageBuilder::executor`. The default executor is
+/// [`RayonExecutor`] if the `rayon` feature is enabled, and [`CurrentThreadExecutor`]
+/// otherwise.
+///
+/// Salsa hands every closure a fork of the database that no other closure uses concurrently,
+/// so executors can run them on any thread. A closure that panics must not prevent the executor from returning; the
+/// panic is resumed once the other closures completed.
+pub trait Executor: Send + Sync + 'static {
+    /// Executes `a` and `b`, and returns once both completed.
+    fn join(&self, a: Task<'_>, b: Task<'_>);
+
+    /// Executes `op` with every index in `0..len`, and returns once all executions completed.
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync));
+}
+
+/// The executor of a database, see `StorageBuilder::executor`.
+///
+/// The [`RayonExecutor`] is kept apart from the other executors, so that `salsa::par_map`
+/// can hand the inputs to rayon as a parallel iterator instead of dispatching them by index.
+pub(crate) enum DatabaseExecutor {
+    #[cfg(feature = "rayon")]
+    Rayon(RayonExecutor),
+    Other(Box<dyn Executor>),
+}
+
+impl DatabaseExecutor {
+    pub(crate) fn new(executor: impl Executor) -> Self {
+        #[cfg(feature = "rayon")]
+        if let Some(executor) = (&executor as &dyn std::any::Any).downcast_ref::<RayonExecutor>() {
+            return Self::Rayon(executor.clone());
+        }
+
+        Self::Other(Box::new(executor))
+    }
+
+    #[inline]
+    pub(crate) fn as_dyn(&self) -> &dyn Executor {
+        match self {
+            #[cfg(feature = "rayon")]
+            Self::Rayon(executor) => executor,
+            Self::Other(executor) => &**executor,
+        }
+    }
+
+    /// Returns the executor if it is a [`RayonExecutor`].
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub(crate) fn as_rayon(&self) -> Option<&RayonExecutor> {
+        match self {
+            Self::Rayon(executor) => Some(executor),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl Default for DatabaseExecutor {
+    /// Returns the executor used if none is set on the storage.
+    fn default() -> Self {
+        #[cfg(feature = "rayon")]
+        return Self::Rayon(RayonExecutor::new());
+
+        #[cfg(not(feature = "rayon"))]
+        return Self::Other(Box::new(CurrentThreadExecutor));
+    }
+}
+
+/// An [`Executor`] that executes all closures on the calling thread, one after the other.
+///
+/// The order of execution is deterministic, which makes this executor useful in tests.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CurrentThreadExecutor;
+
+impl Executor for CurrentThreadExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        // Like `rayon::join`, execute `b` even if `a` panics, and resume the panic of `a` if
+        // both panic.
+        let result_a = crate::cancelled::catch_unwind(AssertUnwindSafe(a));
+        let result_b = crate::cancelled::catch_unwind(AssertUnwindSafe(b));
+        if let Err(payload) = result_a.and(result_b) {
+            panic::resume_unwind(payload);
+        }
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        let mut first_panic = None;
+        for index in 0..len {
+            if let Err(payload) = crate::cancelled::catch_unwind(AssertUnwindSafe(|| op(index))) {
+                first_panic.get_or_insert(payload);
+            }
+        }
+        if let Some(payload) = first_panic {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+/// An [`Executor`] that executes the closures on a rayon thread pool.
+#[cfg(feature = "rayon")]
+#[derive(Clone, Debug, Default)]
+pub struct RayonExecutor {
+    pool: Option<std::sync::Arc<rayon::ThreadPool>>,
+}
+
+#[cfg(feature = "rayon")]
+impl RayonExecutor {
+    /// Creates an executor that uses the global rayon thread pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an executor that uses `pool`.
+    pub fn with_pool(pool: std::sync::Arc<rayon::ThreadPool>) -> Self {
+        Self { pool: Some(pool) }
+    }
+
+    pub(crate) fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Executor for RayonExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        self.install(|| rayon::join(a, b));
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        self.install(|| (0..len).into_par_iter().for_each(op));
+    }
+}
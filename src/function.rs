@@ -11,31 +11,38 @@ use crate::cycle::{
     ProvisionalStatus,
 };
 use crate::database::RawDatabase;
+use crate::dependency_graph::DependencyInfo;
 use crate::function::delete::DeletedEntries;
 use crate::function::sync::{ClaimResult, SyncTable};
+use crate::hash::{Fingerprint, MemoFingerprint};
 use crate::ingredient::{Ingredient, WaitForResult};
 use crate::key::DatabaseKeyIndex;
 use crate::plumbing::MemoIngredientMap;
+use crate::profile::ProfileCounters;
+use crate::runtime::Runtime;
+use crate::salsa_error::SalsaError;
 use crate::salsa_struct::SalsaStructInDb;
 use crate::sync::Arc;
 use crate::table::memo::MemoTableTypes;
-use crate::table::Table;
 use crate::views::DatabaseDownCaster;
 use crate::zalsa::{IngredientIndex, MemoIngredientIndex, Zalsa};
 use crate::zalsa_local::QueryOriginRef;
-use crate::{Id, Revision};
+use crate::{Event, EventKind, EventKinds, Id, Revision};
 
 #[cfg(feature = "accumulator")]
 mod accumulated;
 mod backdate;
 mod delete;
 mod diff_outputs;
+mod evict_after;
 mod execute;
+mod explain;
 mod fetch;
 mod inputs;
 mod lru;
 mod maybe_changed_after;
 mod memo;
+mod persist;
 mod specify;
 mod sync;
 
@@ -79,6 +86,32 @@ pub trait Configuration: Any {
         0
     }
 
+    /// Whether memos of this function record a [`Fingerprint`] of their value, which is then
+    /// used for backdating instead of [`Self::values_equal`].
+    const FINGERPRINT: bool = false;
+
+    /// Where memos of this function store their [`Fingerprint`], `Option<Fingerprint>` if
+    /// [`Self::FINGERPRINT`] is `true` and `()` otherwise.
+    type Fingerprint: MemoFingerprint;
+
+    /// Computes the fingerprint of an output value; only invoked if [`Self::FINGERPRINT`] is `true`.
+    fn fingerprint(_value: &Self::Output<'_>) -> Fingerprint {
+        unreachable!("function is not fingerprinted")
+    }
+
+    /// Whether memos of this function are written by [`crate::Database::persist_memos`].
+    const PERSIST: bool = false;
+
+    /// Serializes an output value; only invoked if [`Self::PERSIST`] is `true`.
+    fn encode_value(_value: &Self::Output<'_>, _buf: &mut Vec<u8>) {
+        unreachable!("function is not persisted")
+    }
+
+    /// Deserializes an output value; only invoked if [`Self::PERSIST`] is `true`.
+    fn decode_value(_buf: &mut &[u8]) -> Result<Self::Output<'static>, crate::PersistenceError> {
+        unreachable!("function is not persisted")
+    }
+
     /// Invoked when we need to compute the value for the given key, either because we've never
     /// computed it before or because the old one relied on inputs that have changed.
     ///
@@ -125,6 +158,9 @@ pub struct IngredientImpl<C: Configuration> {
     /// Used to find memos to throw out when we have too many memoized values.
     lru: lru::Lru,
 
+    /// Used to find memos to throw out when they haven't been verified for a while.
+    evict_after: evict_after::EvictAfter,
+
     /// An downcaster to `C::DbView`.
     ///
     /// # Safety
@@ -148,6 +184,9 @@ pub struct IngredientImpl<C: Configuration> {
     /// we don't know that we can trust the database to give us the same runtime
     /// everytime and so forth.
     deleted_entries: DeletedEntries<C>,
+
+    /// The counters of the built-in profiler, see [`crate::Database::set_profiling`].
+    profile: ProfileCounters,
 }
 
 impl<C> IngredientImpl<C>
@@ -158,14 +197,17 @@ where
         index: IngredientIndex,
         memo_ingredient_indices: <C::SalsaStruct<'static> as SalsaStructInDb>::MemoIngredientMap,
         lru: usize,
+        evict_after_revisions: usize,
     ) -> Self {
         Self {
             index,
             memo_ingredient_indices,
             lru: lru::Lru::new(lru),
+            evict_after: evict_after::EvictAfter::new(evict_after_revisions),
             deleted_entries: Default::default(),
             view_caster: OnceLock::new(),
             sync_table: SyncTable::new(index),
+            profile: ProfileCounters::default(),
         }
     }
 
@@ -191,6 +233,10 @@ where
         self.lru.set_capacity(capacity);
     }
 
+    pub fn set_evict_after_revisions(&mut self, revisions: usize) {
+        self.evict_after.set_revisions(revisions);
+    }
+
     /// Returns a reference to the memo value that lives as long as self.
     /// This is UNSAFE: the caller is responsible for ensuring that the
     /// memo will not be released so long as the `&self` is valid.
@@ -236,6 +282,39 @@ where
         unsafe { self.extend_memo_lifetime(memo.as_ref()) }
     }
 
+    /// Evicts the memoized values that haven't been verified in the last
+    /// `evict_after_revisions` revisions.
+    fn evict_stale_values(&self, runtime: &mut Runtime) {
+        let Some(revisions) = self.evict_after.revisions() else {
+            return;
+        };
+        let Some(verified_before) = Revision::from_opt(
+            runtime
+                .current_revision()
+                .as_usize()
+                .saturating_sub(revisions.get()),
+        ) else {
+            return;
+        };
+        self.evict_after.retain(|&id| {
+            let table = runtime.table_mut();
+            let ingredient_index = table.ingredient_index(id);
+            let evicted = Self::evict_stale_value_from_memo_for(
+                table.memos_mut(id),
+                self.memo_ingredient_indices.get(ingredient_index),
+                verified_before,
+            );
+            if evicted == Some(true) {
+                runtime.event(EventKinds::DID_EVICT_LRU, &|| {
+                    Event::new(EventKind::DidEvictLru {
+                        database_key: DatabaseKeyIndex::new(self.index, id),
+                    })
+                });
+            }
+            evicted == Some(false)
+        });
+    }
+
     #[inline]
     fn memo_ingredient_index(&self, zalsa: &Zalsa, id: Id) -> MemoIngredientIndex {
         self.memo_ingredient_indices.get_zalsa_id(zalsa, id)
@@ -267,7 +346,7 @@ where
         input: Id,
         revision: Revision,
         cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         // SAFETY: The `db` belongs to the ingredient as per caller invariant
         let db = unsafe { self.view_caster().downcast_unchecked(db) };
         self.maybe_changed_after(db, input, revision, cycle_heads)
@@ -320,6 +399,25 @@ where
         self.origin(zalsa, key)
     }
 
+    fn dependency_info<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        key_index: Id,
+    ) -> Option<DependencyInfo<'db>> {
+        let memo_ingredient_index = self.memo_ingredient_index(zalsa, key_index);
+        let memo = self.get_memo_from_table_for(zalsa, key_index, memo_ingredient_index)?;
+        Some(DependencyInfo {
+            changed_at: memo.revisions.changed_at,
+            verified_at: Some(memo.verified_at.load()),
+            durability: memo.revisions.durability,
+            edges: memo.revisions.origin.as_ref().edges(),
+        })
+    }
+
+    fn profile_counters(&self) -> Option<&ProfileCounters> {
+        Some(&self.profile)
+    }
+
     fn mark_validated_output(
         &self,
         zalsa: &Zalsa,
@@ -344,18 +442,38 @@ where
         true
     }
 
-    fn reset_for_new_revision(&mut self, table: &mut Table) {
+    fn reset_for_new_revision(&mut self, runtime: &mut Runtime) {
         self.lru.for_each_evicted(|evict| {
-            let ingredient_index = table.ingredient_index(evict);
-            Self::evict_value_from_memo_for(
-                table.memos_mut(evict),
-                self.memo_ingredient_indices.get(ingredient_index),
-            )
+            self.evict_value(runtime, evict);
         });
+        self.evict_stale_values(runtime);
 
         self.deleted_entries.clear();
     }
 
+    fn evictable_memory(&self, zalsa: &Zalsa, key_index: Id) -> usize {
+        let memo_ingredient_index = self.memo_ingredient_index(zalsa, key_index);
+        self.get_memo_from_table_for(zalsa, key_index, memo_ingredient_index)
+            .map_or(0, |memo| memo.evictable_memory())
+    }
+
+    fn evict_value(&self, runtime: &mut Runtime, key_index: Id) -> bool {
+        let table = runtime.table_mut();
+        let ingredient_index = table.ingredient_index(key_index);
+        let evicted = Self::evict_value_from_memo_for(
+            table.memos_mut(key_index),
+            self.memo_ingredient_indices.get(ingredient_index),
+        );
+        if evicted {
+            runtime.event(EventKinds::DID_EVICT_LRU, &|| {
+                Event::new(EventKind::DidEvictLru {
+                    database_key: DatabaseKeyIndex::new(self.index, key_index),
+                })
+            });
+        }
+        evicted
+    }
+
     fn debug_name(&self) -> &'static str {
         C::DEBUG_NAME
     }
@@ -368,6 +486,11 @@ where
         unreachable!("function does not allocate pages")
     }
 
+    fn forget_released_slots(&mut self, released: &dyn Fn(Id) -> bool) {
+        self.lru.forget(released);
+        self.evict_after.forget(released);
+    }
+
     fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
         C::CYCLE_STRATEGY
     }
@@ -385,6 +508,19 @@ where
         let db = unsafe { self.view_caster().downcast_unchecked(db) };
         self.accumulated_map(db, key_index)
     }
+
+    fn persist_memo(&self, zalsa: &Zalsa, key_index: Id, buf: &mut Vec<u8>) -> bool {
+        self.persist_memo_for(zalsa, key_index, buf)
+    }
+
+    fn restore_memo<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        key_index: Id,
+        buf: &mut &[u8],
+    ) -> Result<Option<crate::persistence::PendingMemo<'db>>, crate::PersistenceError> {
+        self.restore_memo_for(zalsa, key_index, buf)
+    }
 }
 
 impl<C> std::fmt::Debug for IngredientImpl<C>
@@ -99,7 +99,9 @@ where
     ) -> (Option<&'db AccumulatedMap>, InputAccumulatedValues) {
         let (zalsa, zalsa_local) = db.zalsas();
         // NEXT STEP: stash and refactor `fetch` to return an `&Memo` so we can make this work
-        let memo = self.refresh_memo(db, zalsa, zalsa_local, key);
+        let memo = self
+            .refresh_memo(db, zalsa, zalsa_local, key)
+            .unwrap_or_else(|error| error.throw());
         (
             memo.revisions.accumulated(),
             memo.revisions.accumulated_inputs.load(),
@@ -1,5 +1,6 @@
 use crate::function::memo::Memo;
 use crate::function::{Configuration, IngredientImpl};
+use crate::hash::MemoFingerprint;
 use crate::zalsa_local::QueryRevisions;
 use crate::DatabaseKeyIndex;
 
@@ -10,20 +11,43 @@ where
     /// If the value/durability of this memo is equal to what is found in `revisions`/`value`,
     /// then update `revisions.changed_at` to match `self.revisions.changed_at`. This is invoked
     /// on an old memo when a new memo has been produced to check whether there have been changed.
+    ///
+    /// For fingerprinted functions, the fingerprints are compared instead of the values, so that
+    /// memos whose value has been evicted can still be backdated.
+    ///
+    /// Returns `true` if `revisions` was backdated.
     pub(super) fn backdate_if_appropriate<'db>(
         &self,
         old_memo: &Memo<'db, C>,
         index: DatabaseKeyIndex,
         revisions: &mut QueryRevisions,
         value: &C::Output<'db>,
-    ) {
+        fingerprint: C::Fingerprint,
+    ) -> bool {
         // We've seen issues where queries weren't re-validated when backdating provisional values
         // in ty. This is more of a bandaid because we're close to a release and don't have the time to prove
         // right now whether backdating could be made safe for queries participating in queries.
         // TODO: Write a test that demonstrates that backdating queries participating in a cycle isn't safe
         // OR write many tests showing that it is (and fixing the case where it didn't correctly account for today).
         if !revisions.cycle_heads().is_empty() {
-            return;
+            return false;
+        }
+
+        if C::FINGERPRINT {
+            if revisions.durability >= old_memo.revisions.durability
+                && old_memo.fingerprint.get().is_some()
+                && old_memo.fingerprint.get() == fingerprint.get()
+            {
+                crate::tracing::debug!(
+                    "{index:?} fingerprint is equal, back-dating to {:?}",
+                    old_memo.revisions.changed_at,
+                );
+
+                assert!(old_memo.revisions.changed_at <= revisions.changed_at);
+                revisions.changed_at = old_memo.revisions.changed_at;
+                return true;
+            }
+            return false;
         }
 
         if let Some(old_value) = &old_memo.value {
@@ -41,7 +65,10 @@ where
 
                 assert!(old_memo.revisions.changed_at <= revisions.changed_at);
                 revisions.changed_at = old_memo.revisions.changed_at;
+                return true;
             }
         }
+
+        false
     }
 }
@@ -3,7 +3,7 @@ use crate::function::{Configuration, IngredientImpl};
 use crate::hash::FxIndexSet;
 use crate::zalsa::Zalsa;
 use crate::zalsa_local::{output_edges, QueryOriginRef, QueryRevisions};
-use crate::{DatabaseKeyIndex, Event, EventKind, Id};
+use crate::{DatabaseKeyIndex, Event, EventKind, EventKinds, Id};
 
 impl<C> IngredientImpl<C>
 where
@@ -62,7 +62,7 @@ where
     }
 
     fn report_stale_output(zalsa: &Zalsa, key: DatabaseKeyIndex, output: DatabaseKeyIndex) {
-        zalsa.event(&|| {
+        zalsa.event_of_kind(EventKinds::WILL_DISCARD_STALE_OUTPUT, &|| {
             Event::new(EventKind::WillDiscardStaleOutput {
                 execute_key: key,
                 output_key: output,
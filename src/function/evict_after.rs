@@ -0,0 +1,54 @@
+use std::num::NonZeroUsize;
+
+use crate::hash::FxHashSet;
+use crate::sync::Mutex;
+use crate::Id;
+
+/// Tracks the memos to throw out once they haven't been verified for a number of revisions.
+pub(super) struct EvictAfter {
+    revisions: Option<NonZeroUsize>,
+    set: Mutex<FxHashSet<Id>>,
+}
+
+impl EvictAfter {
+    pub fn new(revisions: usize) -> Self {
+        Self {
+            revisions: NonZeroUsize::new(revisions),
+            set: Mutex::default(),
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn record_use(&self, index: Id) {
+        if self.revisions.is_some() {
+            self.insert(index);
+        }
+    }
+
+    #[inline(never)]
+    fn insert(&self, index: Id) {
+        let mut set = self.set.lock();
+        set.insert(index);
+    }
+
+    pub(super) fn revisions(&self) -> Option<NonZeroUsize> {
+        self.revisions
+    }
+
+    pub(super) fn set_revisions(&mut self, revisions: usize) {
+        self.revisions = NonZeroUsize::new(revisions);
+        if self.revisions.is_none() {
+            self.set.get_mut().clear();
+        }
+    }
+
+    /// Forgets the ids for which `released` returns `true`.
+    pub(super) fn forget(&mut self, released: &dyn Fn(Id) -> bool) {
+        self.set.get_mut().retain(|&id| !released(id));
+    }
+
+    /// Keeps only the memos for which `keep` returns `true`.
+    pub(super) fn retain(&self, keep: impl FnMut(&Id) -> bool) {
+        self.set.lock().retain(keep);
+    }
+}
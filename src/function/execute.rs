@@ -1,10 +1,14 @@
+use std::time::Instant;
+
 use crate::cycle::{CycleRecoveryStrategy, IterationCount};
 use crate::function::memo::Memo;
 use crate::function::{Configuration, IngredientImpl};
+use crate::hash::MemoFingerprint;
+use crate::salsa_error::SalsaError;
 use crate::sync::atomic::{AtomicBool, Ordering};
 use crate::zalsa::{MemoIngredientIndex, Zalsa, ZalsaDatabase};
 use crate::zalsa_local::{ActiveQueryGuard, QueryRevisions};
-use crate::{Event, EventKind, Id};
+use crate::{DatabaseKeyIndex, Event, EventKind, EventKinds, Id};
 
 impl<C> IngredientImpl<C>
 where
@@ -14,6 +18,8 @@ where
     /// a new memo with the result, backdated if possible. Once this completes,
     /// the query will have been popped off the active query stack.
     ///
+    /// Fails without storing a memo if the query did not converge or read a query that failed.
+    ///
     /// # Parameters
     ///
     /// * `db`, the database.
@@ -25,27 +31,36 @@ where
         db: &'db C::DbView,
         active_query: ActiveQueryGuard<'db>,
         opt_old_memo: Option<&Memo<'db, C>>,
-    ) -> &'db Memo<'db, C> {
+    ) -> Result<&'db Memo<'db, C>, SalsaError> {
         let database_key_index = active_query.database_key_index;
         let id = database_key_index.key_index();
 
         crate::tracing::info!("{:?}: executing query", database_key_index);
         let zalsa = db.zalsa();
 
-        zalsa.event(&|| {
+        zalsa.event_of_kind(EventKinds::WILL_EXECUTE, &|| {
             Event::new(EventKind::WillExecute {
                 database_key: database_key_index,
             })
         });
+        let events = ExecuteEvents {
+            zalsa,
+            database_key_index,
+            start: zalsa
+                .runtime()
+                .is_subscribed(EventKinds::DID_EXECUTE)
+                .then(Instant::now),
+        };
         let memo_ingredient_index = self.memo_ingredient_index(zalsa, id);
+        let _timer = self.profile.start_execution(zalsa, db.zalsa_local());
 
         let (new_value, mut revisions) = match C::CYCLE_STRATEGY {
             CycleRecoveryStrategy::Panic => {
-                Self::execute_query(db, zalsa, active_query, opt_old_memo, id)
+                Self::execute_query(db, zalsa, active_query, opt_old_memo, id)?
             }
             CycleRecoveryStrategy::FallbackImmediate => {
                 let (mut new_value, mut revisions) =
-                    Self::execute_query(db, zalsa, active_query, opt_old_memo, id);
+                    Self::execute_query(db, zalsa, active_query, opt_old_memo, id)?;
 
                 if let Some(cycle_heads) = revisions.cycle_heads_mut() {
                     // Did the new result we got depend on our own provisional value, in a cycle?
@@ -62,7 +77,8 @@ where
                         // We need to mark the memo as finalized so other cycle participants that have fallbacks
                         // will be verified (participants that don't have fallbacks will not be verified).
                         memo.revisions.verified_final.store(true, Ordering::Release);
-                        return memo;
+                        events.did_execute(false);
+                        return Ok(memo);
                     }
 
                     // If we're in the middle of a cycle and we have a fallback, use it instead.
@@ -89,26 +105,40 @@ where
                 zalsa,
                 id,
                 memo_ingredient_index,
-            ),
+            )?,
         };
 
+        let fingerprint = C::Fingerprint::new(|| C::fingerprint(&new_value));
+
+        let mut backdated = false;
         if let Some(old_memo) = opt_old_memo {
             // If the new value is equal to the old one, then it didn't
             // really change, even if some of its inputs have. So we can
             // "backdate" its `changed_at` revision to be the same as the
             // old value.
-            self.backdate_if_appropriate(old_memo, database_key_index, &mut revisions, &new_value);
+            backdated = self.backdate_if_appropriate(
+                old_memo,
+                database_key_index,
+                &mut revisions,
+                &new_value,
+                fingerprint,
+            );
 
             // Diff the new outputs with the old, to discard any no-longer-emitted
             // outputs and update the tracked struct IDs for seeding the next revision.
             self.diff_outputs(zalsa, database_key_index, old_memo, &mut revisions);
         }
-        self.insert_memo(
+        let memo = self.insert_memo(
             zalsa,
             id,
-            Memo::new(Some(new_value), zalsa.current_revision(), revisions),
+            Memo {
+                fingerprint,
+                ..Memo::new(Some(new_value), zalsa.current_revision(), revisions)
+            },
             memo_ingredient_index,
-        )
+        );
+        events.did_execute(backdated);
+        Ok(memo)
     }
 
     #[inline]
@@ -120,7 +150,7 @@ where
         zalsa: &'db Zalsa,
         id: Id,
         memo_ingredient_index: MemoIngredientIndex,
-    ) -> (C::Output<'db>, QueryRevisions) {
+    ) -> Result<(C::Output<'db>, QueryRevisions), SalsaError> {
         let database_key_index = active_query.database_key_index;
         let mut iteration_count = IterationCount::initial();
         let mut fell_back = false;
@@ -132,7 +162,7 @@ where
         loop {
             let previous_memo = opt_last_provisional.or(opt_old_memo);
             let (mut new_value, mut revisions) =
-                Self::execute_query(db, zalsa, active_query, previous_memo, id);
+                Self::execute_query(db, zalsa, active_query, previous_memo, id)?;
 
             // Did the new result we got depend on our own provisional value, in a cycle?
             if let Some(cycle_heads) = revisions
@@ -172,9 +202,12 @@ where
                         // (ignoring the request to fall back), or forcibly use the fallback and
                         // leave the cycle in an inconsistent state (we'll be using a value for
                         // this query that it doesn't evaluate to, given its inputs). Maybe we'll
-                        // have to go with the latter, but for now let's panic and see if real use
+                        // have to go with the latter, but for now let's fail and see if real use
                         // cases need non-converging fallbacks.
-                        panic!("{database_key_index:?}: execute: fallback did not converge");
+                        return Err(SalsaError::cycle(
+                            database_key_index,
+                            format!("{database_key_index:?}: execute: fallback did not converge"),
+                        ));
                     }
                     // We are in a cycle that hasn't converged; ask the user's
                     // cycle-recovery function what to do:
@@ -198,10 +231,14 @@ where
                     }
                     // `iteration_count` can't overflow as we check it against `MAX_ITERATIONS`
                     // which is less than `u32::MAX`.
-                    iteration_count = iteration_count.increment().unwrap_or_else(|| {
-                        panic!("{database_key_index:?}: execute: too many cycle iterations")
-                    });
-                    zalsa.event(&|| {
+                    iteration_count = iteration_count.increment().ok_or_else(|| {
+                        SalsaError::cycle(
+                            database_key_index,
+                            format!("{database_key_index:?}: execute: too many cycle iterations"),
+                        )
+                    })?;
+                    self.profile.record_cycle_iteration(zalsa);
+                    zalsa.event_of_kind(EventKinds::WILL_ITERATE_CYCLE, &|| {
                         Event::new(EventKind::WillIterateCycle {
                             database_key: database_key_index,
                             iteration_count,
@@ -241,7 +278,7 @@ where
                 "{database_key_index:?}: execute: result.revisions = {revisions:#?}"
             );
 
-            break (new_value, revisions);
+            break Ok((new_value, revisions));
         }
     }
 
@@ -252,7 +289,7 @@ where
         active_query: ActiveQueryGuard<'db>,
         opt_old_memo: Option<&Memo<'db, C>>,
         id: Id,
-    ) -> (C::Output<'db>, QueryRevisions) {
+    ) -> Result<(C::Output<'db>, QueryRevisions), SalsaError> {
         if let Some(old_memo) = opt_old_memo {
             // If we already executed this query once, then use the tracked-struct ids from the
             // previous execution as the starting point for the new one.
@@ -276,6 +313,48 @@ where
         // stale, or value is absent. Let's execute!
         let new_value = C::execute(db, C::id_to_input(zalsa, id));
 
-        (new_value, active_query.pop())
+        // The value may depend on the fallback the query used for a failed query.
+        if let Some(error) = active_query.take_failure() {
+            return Err(error);
+        }
+
+        Ok((new_value, active_query.pop()))
+    }
+}
+
+/// Reports the end of the execution of a query, which starts with a
+/// [`EventKind::WillExecute`] event.
+///
+/// Reports [`EventKind::DidUnwindExecute`] when dropped before the execution completed,
+/// e.g. because the query panicked or failed, so that every `WillExecute` event is followed
+/// by exactly one end event.
+struct ExecuteEvents<'a> {
+    zalsa: &'a Zalsa,
+    database_key_index: DatabaseKeyIndex,
+    /// The start of the execution, if `DidExecute` events are reported.
+    start: Option<Instant>,
+}
+
+impl ExecuteEvents<'_> {
+    fn did_execute(self, backdated: bool) {
+        let this = std::mem::ManuallyDrop::new(self);
+        this.zalsa.event_of_kind(EventKinds::DID_EXECUTE, &|| {
+            Event::new(EventKind::DidExecute {
+                database_key: this.database_key_index,
+                duration: this.start.map(|start| start.elapsed()).unwrap_or_default(),
+                backdated,
+            })
+        });
+    }
+}
+
+impl Drop for ExecuteEvents<'_> {
+    fn drop(&mut self) {
+        self.zalsa
+            .event_of_kind(EventKinds::DID_UNWIND_EXECUTE, &|| {
+                Event::new(EventKind::DidUnwindExecute {
+                    database_key: self.database_key_index,
+                })
+            });
     }
 }
@@ -0,0 +1,62 @@
+use crate::function::memo::Memo;
+use crate::function::{Configuration, IngredientImpl};
+use crate::zalsa::Zalsa;
+use crate::zalsa_local::{QueryOriginRef, ZalsaLocal};
+use crate::{DatabaseKeyIndex, Event, EventKind, EventKinds, ReexecuteReason};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Emits a [`EventKind::WillReexecute`] event explaining why `old_memo` is about to be
+    /// re-executed, if enabled.
+    ///
+    /// Returns the chain of changed dependencies recorded while verifying `old_memo`, so that
+    /// the caller can restore it if the re-executed query's value changed as well.
+    pub(super) fn explain_reexecution(
+        &self,
+        zalsa: &Zalsa,
+        zalsa_local: &ZalsaLocal,
+        database_key_index: DatabaseKeyIndex,
+        old_memo: Option<&Memo<'_, C>>,
+    ) -> Option<Vec<DatabaseKeyIndex>> {
+        if !zalsa.explains_reexecutions() {
+            return None;
+        }
+
+        let changed_dependencies = zalsa_local.take_changed_dependencies();
+        let old_memo = old_memo?;
+
+        let reason = if let Some(&changed) = changed_dependencies.first() {
+            let changed_at = zalsa
+                .lookup_ingredient(changed.ingredient_index())
+                .dependency_info(zalsa, changed.key_index())
+                .map(|info| info.changed_at);
+            ReexecuteReason::ChangedDependency {
+                path: changed_dependencies.iter().rev().copied().collect(),
+                changed_at,
+            }
+        } else if old_memo.value.is_none() {
+            ReexecuteReason::Evicted
+        } else {
+            match old_memo.revisions.origin.as_ref() {
+                QueryOriginRef::DerivedUntracked(_) => ReexecuteReason::UntrackedRead,
+                QueryOriginRef::Assigned(_) => ReexecuteReason::Assigned,
+                QueryOriginRef::FixpointInitial => ReexecuteReason::FixpointInitial,
+                QueryOriginRef::Derived(_) if old_memo.may_be_provisional() => {
+                    ReexecuteReason::Provisional
+                }
+                QueryOriginRef::Derived(_) => ReexecuteReason::Unverified,
+            }
+        };
+
+        zalsa.event_of_kind(EventKinds::WILL_REEXECUTE, &|| {
+            Event::new(EventKind::WillReexecute {
+                database_key: database_key_index,
+                reason: reason.clone(),
+            })
+        });
+
+        Some(changed_dependencies)
+    }
+}
@@ -2,6 +2,7 @@ use crate::cycle::{CycleHeadKeys, CycleHeads, CycleRecoveryStrategy, IterationCo
 use crate::function::memo::Memo;
 use crate::function::sync::ClaimResult;
 use crate::function::{Configuration, IngredientImpl, VerifyResult};
+use crate::salsa_error::SalsaError;
 use crate::zalsa::{MemoIngredientIndex, Zalsa};
 use crate::zalsa_local::{QueryRevisions, ZalsaLocal};
 use crate::Id;
@@ -17,19 +18,48 @@ where
         zalsa_local: &'db ZalsaLocal,
         id: Id,
     ) -> &'db C::Output<'db> {
-        zalsa.unwind_if_revision_cancelled(zalsa_local);
+        self.fetch_value(db, zalsa, zalsa_local, id)
+            .unwrap_or_else(|error| error.throw())
+    }
+
+    /// Like [`Self::fetch`], but returns an error instead of panicking if the query could not
+    /// produce a value.
+    ///
+    /// The error is also recorded for the active query, if any, which then fails as well.
+    pub fn try_fetch<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        zalsa: &'db Zalsa,
+        zalsa_local: &'db ZalsaLocal,
+        id: Id,
+    ) -> Result<&'db C::Output<'db>, SalsaError> {
+        self.fetch_value(db, zalsa, zalsa_local, id)
+            .inspect_err(|error| zalsa_local.report_failure(error))
+    }
+
+    #[inline(always)]
+    fn fetch_value<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        zalsa: &'db Zalsa,
+        zalsa_local: &'db ZalsaLocal,
+        id: Id,
+    ) -> Result<&'db C::Output<'db>, SalsaError> {
+        zalsa.check_revision_cancelled(zalsa_local)?;
 
         let database_key_index = self.database_key_index(id);
 
         #[cfg(debug_assertions)]
         let _span = crate::tracing::debug_span!("fetch", query = ?database_key_index).entered();
 
-        let memo = self.refresh_memo(db, zalsa, zalsa_local, id);
+        let memo = self.refresh_memo(db, zalsa, zalsa_local, id)?;
 
         // SAFETY: We just refreshed the memo so it is guaranteed to contain a value now.
         let memo_value = unsafe { memo.value.as_ref().unwrap_unchecked() };
 
         self.lru.record_use(id);
+        self.evict_after.record_use(id);
+        zalsa.record_memo_use(database_key_index);
 
         zalsa_local.report_tracked_read(
             database_key_index,
@@ -42,7 +72,7 @@ where
             &memo.revisions.accumulated_inputs,
         );
 
-        memo_value
+        Ok(memo_value)
     }
 
     #[inline(always)]
@@ -52,16 +82,16 @@ where
         zalsa: &'db Zalsa,
         zalsa_local: &'db ZalsaLocal,
         id: Id,
-    ) -> &'db Memo<'db, C> {
+    ) -> Result<&'db Memo<'db, C>, SalsaError> {
         let memo_ingredient_index = self.memo_ingredient_index(zalsa, id);
         loop {
-            if let Some(memo) = self
-                .fetch_hot(zalsa, id, memo_ingredient_index)
-                .or_else(|| {
-                    self.fetch_cold_with_retry(zalsa, zalsa_local, db, id, memo_ingredient_index)
-                })
+            if let Some(memo) = self.fetch_hot(zalsa, id, memo_ingredient_index) {
+                return Ok(memo);
+            }
+            if let Some(memo) =
+                self.fetch_cold_with_retry(zalsa, zalsa_local, db, id, memo_ingredient_index)?
             {
-                return memo;
+                return Ok(memo);
             }
         }
     }
@@ -83,6 +113,7 @@ where
 
         if can_shallow_update.yes() && !memo.may_be_provisional() {
             self.update_shallow(zalsa, database_key_index, memo, can_shallow_update);
+            self.profile.record_shallow_hit(zalsa);
 
             // SAFETY: memo is present in memo_map and we have verified that it is
             // still valid for the current revision.
@@ -100,8 +131,10 @@ where
         db: &'db C::DbView,
         id: Id,
         memo_ingredient_index: MemoIngredientIndex,
-    ) -> Option<&'db Memo<'db, C>> {
-        let memo = self.fetch_cold(zalsa, zalsa_local, db, id, memo_ingredient_index)?;
+    ) -> Result<Option<&'db Memo<'db, C>>, SalsaError> {
+        let Some(memo) = self.fetch_cold(zalsa, zalsa_local, db, id, memo_ingredient_index)? else {
+            return Ok(None);
+        };
 
         // If we get back a provisional cycle memo, and it's provisional on any cycle heads
         // that are claimed by a different thread, we can't propagate the provisional memo
@@ -111,11 +144,11 @@ where
         // That is only correct for fixpoint cycles, though: `FallbackImmediate` cycles
         // never have provisional entries.
         if C::CYCLE_STRATEGY == CycleRecoveryStrategy::FallbackImmediate
-            || !memo.provisional_retry(zalsa, zalsa_local, self.database_key_index(id))
+            || !memo.provisional_retry(zalsa, zalsa_local, self.database_key_index(id))?
         {
-            Some(memo)
+            Ok(Some(memo))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -126,12 +159,13 @@ where
         db: &'db C::DbView,
         id: Id,
         memo_ingredient_index: MemoIngredientIndex,
-    ) -> Option<&'db Memo<'db, C>> {
+    ) -> Result<Option<&'db Memo<'db, C>>, SalsaError> {
         let database_key_index = self.database_key_index(id);
         // Try to claim this query: if someone else has claimed it already, go back and start again.
         let claim_guard = match self.sync_table.try_claim(zalsa, id) {
             ClaimResult::Running(blocked_on) => {
-                blocked_on.block_on(zalsa);
+                self.profile
+                    .record_blocked(zalsa, zalsa_local, || blocked_on.block_on(zalsa))?;
 
                 let memo = self.get_memo_from_table_for(zalsa, id, memo_ingredient_index);
 
@@ -140,10 +174,10 @@ where
                     // await all outer cycle heads to give the thread driving it a chance to complete
                     // (we don't want multiple threads competing for the queries participating in the same cycle).
                     if memo.value.is_some() && memo.may_be_provisional() {
-                        memo.block_on_heads(zalsa, zalsa_local);
+                        memo.block_on_heads(zalsa, zalsa_local)?;
                     }
                 }
-                return None;
+                return Ok(None);
             }
             ClaimResult::Cycle { .. } => {
                 // check if there's a provisional value for this query
@@ -164,22 +198,26 @@ where
                                 can_shallow_update,
                             );
                             // SAFETY: memo is present in memo_map.
-                            return unsafe { Some(self.extend_memo_lifetime(memo)) };
+                            return unsafe { Ok(Some(self.extend_memo_lifetime(memo))) };
                         }
                     }
                 }
                 // no provisional value; create/insert/return initial provisional value
-                return match C::CYCLE_STRATEGY {
+                return Ok(match C::CYCLE_STRATEGY {
                     // SAFETY: We do not access the query stack reentrantly.
-                    CycleRecoveryStrategy::Panic => unsafe {
-                        zalsa_local.with_query_stack_unchecked(|stack| {
-                            panic!(
-                                "dependency graph cycle when querying {database_key_index:#?}, \
-                            set cycle_fn/cycle_initial to fixpoint iterate.\n\
-                            Query stack:\n{stack:#?}",
-                            );
-                        })
-                    },
+                    CycleRecoveryStrategy::Panic => {
+                        // SAFETY: We do not access the query stack reentrantly.
+                        let message = unsafe {
+                            zalsa_local.with_query_stack_unchecked(|stack| {
+                                format!(
+                                    "dependency graph cycle when querying {database_key_index:#?}, \
+                                set cycle_fn/cycle_initial to fixpoint iterate.\n\
+                                Query stack:\n{stack:#?}",
+                                )
+                            })
+                        };
+                        return Err(SalsaError::cycle(database_key_index, message));
+                    }
                     CycleRecoveryStrategy::Fixpoint => {
                         crate::tracing::debug!(
                             "hit cycle at {database_key_index:#?}, \
@@ -212,7 +250,7 @@ where
                             memo_ingredient_index,
                         ))
                     }
-                };
+                });
             }
             ClaimResult::Claimed(guard) => guard,
         };
@@ -223,13 +261,17 @@ where
         if let Some(old_memo) = opt_old_memo {
             if old_memo.value.is_some() {
                 let mut cycle_heads = CycleHeadKeys::new();
-                if let VerifyResult::Unchanged { .. } =
-                    self.deep_verify_memo(db, zalsa, old_memo, database_key_index, &mut cycle_heads)
-                {
+                if let VerifyResult::Unchanged { .. } = self.deep_verify_memo(
+                    db,
+                    zalsa,
+                    old_memo,
+                    database_key_index,
+                    &mut cycle_heads,
+                )? {
                     if cycle_heads.is_empty() {
                         // SAFETY: memo is present in memo_map and we have verified that it is
                         // still valid for the current revision.
-                        return unsafe { Some(self.extend_memo_lifetime(old_memo)) };
+                        return unsafe { Ok(Some(self.extend_memo_lifetime(old_memo))) };
                     }
                 }
 
@@ -257,19 +299,22 @@ where
                     // We will then block on the cycle head and retry once all cycle heads completed.
                     if !old_memo.try_claim_heads(zalsa, zalsa_local) {
                         drop(claim_guard);
-                        old_memo.block_on_heads(zalsa, zalsa_local);
-                        return None;
+                        // The memo is verified again once its cycle heads completed.
+                        zalsa_local.clear_changed_dependencies();
+                        old_memo.block_on_heads(zalsa, zalsa_local)?;
+                        return Ok(None);
                     }
                 }
             }
         }
 
+        self.explain_reexecution(zalsa, zalsa_local, database_key_index, opt_old_memo);
         let memo = self.execute(
             db,
             zalsa_local.push_query(database_key_index, IterationCount::initial()),
             opt_old_memo,
-        );
+        )?;
 
-        Some(memo)
+        Ok(Some(memo))
     }
 }
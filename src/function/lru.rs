@@ -37,11 +37,16 @@ impl Lru {
         }
     }
 
-    pub(super) fn for_each_evicted(&mut self, mut cb: impl FnMut(Id)) {
+    /// Forgets the ids for which `released` returns `true`.
+    pub(super) fn forget(&mut self, released: &dyn Fn(Id) -> bool) {
+        self.set.get_mut().retain(|&id| !released(id));
+    }
+
+    pub(super) fn for_each_evicted(&self, mut cb: impl FnMut(Id)) {
         let Some(cap) = self.capacity else {
             return;
         };
-        let set = self.set.get_mut();
+        let mut set = self.set.lock();
         while set.len() > cap.get() {
             if let Some(id) = set.pop_front() {
                 cb(id);
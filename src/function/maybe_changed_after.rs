@@ -1,13 +1,21 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::panic::AssertUnwindSafe;
+
 #[cfg(feature = "accumulator")]
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
 use crate::cycle::{CycleHeadKeys, CycleRecoveryStrategy, IterationCount, ProvisionalStatus};
 use crate::function::memo::Memo;
 use crate::function::sync::ClaimResult;
 use crate::function::{Configuration, IngredientImpl};
+use crate::hash::MemoFingerprint;
 use crate::key::DatabaseKeyIndex;
-use crate::sync::atomic::Ordering;
+use crate::run_async::Blocking;
+use crate::salsa_error::SalsaError;
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::Mutex;
 use crate::zalsa::{MemoIngredientIndex, Zalsa, ZalsaDatabase};
-use crate::zalsa_local::{QueryEdgeKind, QueryOriginRef, ZalsaLocal};
+use crate::zalsa_local::{QueryEdge, QueryEdgeKind, QueryOriginRef, ZalsaLocal};
 use crate::{Id, Revision};
 
 /// Result of memo validation.
@@ -52,10 +60,10 @@ where
         id: Id,
         revision: Revision,
         cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         let (zalsa, zalsa_local) = db.zalsas();
         let memo_ingredient_index = self.memo_ingredient_index(zalsa, id);
-        zalsa.unwind_if_revision_cancelled(zalsa_local);
+        zalsa.check_revision_cancelled(zalsa_local)?;
 
         loop {
             let database_key_index = self.database_key_index(id);
@@ -68,21 +76,22 @@ where
             let memo_guard = self.get_memo_from_table_for(zalsa, id, memo_ingredient_index);
             let Some(memo) = memo_guard else {
                 // No memo? Assume has changed.
-                return VerifyResult::Changed;
+                return Ok(VerifyResult::Changed);
             };
 
             let can_shallow_update = self.shallow_verify_memo(zalsa, database_key_index, memo);
             if can_shallow_update.yes() && !memo.may_be_provisional() {
                 self.update_shallow(zalsa, database_key_index, memo, can_shallow_update);
+                self.profile.record_shallow_hit(zalsa);
 
-                return if memo.revisions.changed_at > revision {
+                return Ok(if memo.revisions.changed_at > revision {
                     VerifyResult::Changed
                 } else {
                     VerifyResult::Unchanged {
                         #[cfg(feature = "accumulator")]
                         accumulated: memo.revisions.accumulated_inputs.load(),
                     }
-                };
+                });
             }
 
             if let Some(mcs) = self.maybe_changed_after_cold(
@@ -92,8 +101,8 @@ where
                 revision,
                 memo_ingredient_index,
                 cycle_heads,
-            ) {
-                return mcs;
+            )? {
+                return Ok(mcs);
             } else {
                 // We failed to claim, have to retry.
             }
@@ -109,34 +118,39 @@ where
         revision: Revision,
         memo_ingredient_index: MemoIngredientIndex,
         cycle_heads: &mut CycleHeadKeys,
-    ) -> Option<VerifyResult> {
+    ) -> Result<Option<VerifyResult>, SalsaError> {
         let database_key_index = self.database_key_index(key_index);
 
         let _claim_guard = match self.sync_table.try_claim(zalsa, key_index) {
             ClaimResult::Running(blocked_on) => {
-                blocked_on.block_on(zalsa);
-                return None;
+                self.profile
+                    .record_blocked(zalsa, db.zalsa_local(), || blocked_on.block_on(zalsa))?;
+                return Ok(None);
             }
             ClaimResult::Cycle { .. } => match C::CYCLE_STRATEGY {
                 // SAFETY: We do not access the query stack reentrantly.
-                CycleRecoveryStrategy::Panic => unsafe {
-                    db.zalsa_local().with_query_stack_unchecked(|stack| {
-                        panic!(
-                            "dependency graph cycle when validating {database_key_index:#?}, \
-                        set cycle_fn/cycle_initial to fixpoint iterate.\n\
-                        Query stack:\n{stack:#?}",
-                        );
-                    })
-                },
+                CycleRecoveryStrategy::Panic => {
+                    // SAFETY: We do not access the query stack reentrantly.
+                    let message = unsafe {
+                        db.zalsa_local().with_query_stack_unchecked(|stack| {
+                            format!(
+                                "dependency graph cycle when validating {database_key_index:#?}, \
+                            set cycle_fn/cycle_initial to fixpoint iterate.\n\
+                            Query stack:\n{stack:#?}",
+                            )
+                        })
+                    };
+                    return Err(SalsaError::cycle(database_key_index, message));
+                }
                 CycleRecoveryStrategy::FallbackImmediate => {
-                    return Some(VerifyResult::unchanged());
+                    return Ok(Some(VerifyResult::unchanged()));
                 }
                 CycleRecoveryStrategy::Fixpoint => {
                     crate::tracing::debug!(
                         "hit cycle at {database_key_index:?} in `maybe_changed_after`,  returning fixpoint initial value",
                     );
                     cycle_heads.insert(database_key_index);
-                    return Some(VerifyResult::unchanged());
+                    return Ok(Some(VerifyResult::unchanged()));
                 }
             },
             ClaimResult::Claimed(guard) => guard,
@@ -144,7 +158,7 @@ where
         // Load the current memo, if any.
         let Some(old_memo) = self.get_memo_from_table_for(zalsa, key_index, memo_ingredient_index)
         else {
-            return Some(VerifyResult::Changed);
+            return Ok(Some(VerifyResult::Changed));
         };
 
         crate::tracing::debug!(
@@ -155,20 +169,20 @@ where
 
         // Check if the inputs are still valid. We can just compare `changed_at`.
         let deep_verify =
-            self.deep_verify_memo(db, zalsa, old_memo, database_key_index, cycle_heads);
+            self.deep_verify_memo(db, zalsa, old_memo, database_key_index, cycle_heads)?;
         if let VerifyResult::Unchanged {
             #[cfg(feature = "accumulator")]
                 accumulated: accumulated_inputs,
         } = deep_verify
         {
-            return Some(if old_memo.revisions.changed_at > revision {
+            return Ok(Some(if old_memo.revisions.changed_at > revision {
                 VerifyResult::Changed
             } else {
                 VerifyResult::Unchanged {
                     #[cfg(feature = "accumulator")]
                     accumulated: accumulated_inputs,
                 }
-            });
+            }));
         }
 
         // If inputs have changed, but we have an old value, we can re-execute.
@@ -181,14 +195,26 @@ where
         // the cycle head returned *fixpoint initial* without validating its dependencies.
         // `in_cycle` tracks if the enclosing query is in a cycle. `deep_verify.cycle_heads` tracks
         // if **this query** encountered a cycle (which means there's some provisional value somewhere floating around).
-        if old_memo.value.is_some() && cycle_heads.is_empty() {
-            let active_query = db
-                .zalsa_local()
-                .push_query(database_key_index, IterationCount::initial());
-            let memo = self.execute(db, active_query, Some(old_memo));
+        // Fingerprinted memos can be backdated even if their value was evicted.
+        let can_backdate = old_memo.value.is_some() || old_memo.fingerprint.get().is_some();
+        if can_backdate && cycle_heads.is_empty() {
+            let zalsa_local = db.zalsa_local();
+            if zalsa_local.is_verify_only() {
+                crate::run_async::yield_now();
+            }
+            let changed_dependencies =
+                self.explain_reexecution(zalsa, zalsa_local, database_key_index, Some(old_memo));
+            let active_query =
+                zalsa_local.push_query(database_key_index, IterationCount::initial());
+            let memo = self.execute(db, active_query, Some(old_memo))?;
             let changed_at = memo.revisions.changed_at;
 
-            return Some(if changed_at > revision {
+            return Ok(Some(if changed_at > revision {
+                // Let the queries depending on this one explain their re-execution
+                // with the dependencies that changed this one.
+                if let Some(changed_dependencies) = changed_dependencies {
+                    zalsa_local.restore_changed_dependencies(changed_dependencies);
+                }
                 VerifyResult::Changed
             } else {
                 VerifyResult::Unchanged {
@@ -198,11 +224,11 @@ where
                         None => memo.revisions.accumulated_inputs.load(),
                     },
                 }
-            });
+            }));
         }
 
         // Otherwise, nothing for it: have to consider the value to have changed.
-        Some(VerifyResult::Changed)
+        Ok(Some(VerifyResult::Changed))
     }
 
     /// `Some` if the memo's value and `changed_at` time is still valid in this revision.
@@ -407,7 +433,7 @@ where
         old_memo: &Memo<'_, C>,
         database_key_index: DatabaseKeyIndex,
         cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         crate::tracing::debug!(
             "{database_key_index:?}: deep_verify_memo(old_memo = {old_memo:#?})",
             old_memo = old_memo.tracing_debug()
@@ -423,11 +449,12 @@ where
             )
         {
             self.update_shallow(zalsa, database_key_index, old_memo, can_shallow_update);
+            self.profile.record_shallow_hit(zalsa);
 
-            return VerifyResult::unchanged();
+            return Ok(VerifyResult::unchanged());
         }
 
-        match old_memo.revisions.origin.as_ref() {
+        Ok(match old_memo.revisions.origin.as_ref() {
             QueryOriginRef::Assigned(_) => {
                 // If the value was assigned by another query,
                 // and that query were up-to-date,
@@ -460,27 +487,59 @@ where
                 // If the value is from the same revision but is still provisional, consider it changed
                 // because we're now in a new iteration.
                 if can_shallow_update == ShallowUpdate::Verified && is_provisional {
-                    return VerifyResult::Changed;
+                    return Ok(VerifyResult::Changed);
                 }
 
                 #[cfg(feature = "accumulator")]
                 let mut inputs = InputAccumulatedValues::Empty;
+                // The results of the inputs verified in parallel that were not processed yet.
+                let mut verified_in_parallel = VecDeque::new();
+                // Whether the inputs up to the next output may still be verified in parallel.
+                let mut try_parallel = true;
                 // Fully tracked inputs? Iterate over the inputs and check them, one by one.
                 //
                 // NB: It's important here that we are iterating the inputs in the order that
                 // they executed. It's possible that if the value of some input I0 is no longer
                 // valid, then some later input I1 might never have executed at all, so verifying
-                // it is still up to date is meaningless.
-                for &edge in edges {
+                // it is still up to date is meaningless. Inputs verified in parallel are still
+                // processed in this order.
+                for (index, &edge) in edges.iter().enumerate() {
                     match edge.kind() {
                         QueryEdgeKind::Input(dependency_index) => {
-                            match dependency_index.maybe_changed_after(
-                                db.into(),
-                                zalsa,
-                                old_memo.verified_at.load(),
-                                cycle_heads,
-                            ) {
-                                VerifyResult::Changed => return VerifyResult::Changed,
+                            if try_parallel {
+                                try_parallel = false;
+                                if let Some(min_edges) = zalsa.parallel_verification() {
+                                    verified_in_parallel = Self::verify_inputs_in_parallel(
+                                        db,
+                                        zalsa,
+                                        &edges[index..],
+                                        old_memo.verified_at.load(),
+                                        min_edges,
+                                    );
+                                }
+                            }
+                            let result = match verified_in_parallel.pop_front() {
+                                Some(ParallelVerifyResult::Verified(result, heads)) => {
+                                    cycle_heads.extend(heads);
+                                    result
+                                }
+                                Some(ParallelVerifyResult::Skipped) | None => dependency_index
+                                    .maybe_changed_after(
+                                        db.into(),
+                                        zalsa,
+                                        old_memo.verified_at.load(),
+                                        cycle_heads,
+                                    )?,
+                            };
+                            match result {
+                                VerifyResult::Changed => {
+                                    if zalsa.explains_reexecutions() {
+                                        db.zalsa_local()
+                                            .record_changed_dependency(dependency_index);
+                                    }
+                                    self.profile.record_deep_verification(zalsa, false);
+                                    return Ok(VerifyResult::Changed);
+                                }
                                 #[cfg(feature = "accumulator")]
                                 VerifyResult::Unchanged { accumulated } => {
                                     inputs |= accumulated;
@@ -507,6 +566,7 @@ where
                             // so even if we mark them as valid here, the function will re-execute
                             // and overwrite the contents.
                             dependency_index.mark_validated_output(zalsa, database_key_index);
+                            try_parallel = true;
                         }
                     }
                 }
@@ -537,6 +597,8 @@ where
                 //    future iteration; first the outer cycle head needs to verify itself.
 
                 cycle_heads.remove(&database_key_index);
+                self.profile
+                    .record_deep_verification(zalsa, cycle_heads.is_empty());
 
                 // 1 and 3
                 if cycle_heads.is_empty() {
@@ -557,10 +619,88 @@ where
                     accumulated: inputs,
                 }
             }
+        })
+    }
+
+    /// Verifies the inputs at the start of `edges` in parallel, if there are at least
+    /// `min_edges` of them before the first output, see `Database::set_parallel_verification`.
+    ///
+    /// Returns the results of the inputs in order, or nothing if they were not verified.
+    fn verify_inputs_in_parallel(
+        db: &C::DbView,
+        zalsa: &Zalsa,
+        edges: &[QueryEdge],
+        last_verified_at: Revision,
+        min_edges: usize,
+    ) -> VecDeque<ParallelVerifyResult> {
+        // The forks must not block, which the forks of the forks would not know about.
+        if crate::run_async::is_non_blocking() {
+            return VecDeque::new();
         }
+
+        let dependencies = edges
+            .iter()
+            .map_while(|edge| match edge.kind() {
+                QueryEdgeKind::Input(dependency_index) => Some(dependency_index),
+                QueryEdgeKind::Output(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if dependencies.len() < min_edges {
+            return VecDeque::new();
+        }
+
+        let first_changed = AtomicUsize::new(usize::MAX);
+        let mut results = dependencies
+            .iter()
+            .map(|_| Mutex::new(ParallelVerifyResult::Skipped))
+            .collect::<Vec<_>>();
+        crate::parallel::for_each_fork(db, dependencies.len(), |db, index| {
+            // An earlier input changed, so this one is never processed.
+            if index > first_changed.load(Ordering::Relaxed) {
+                return;
+            }
+            // The inputs are verified deeply, but verifying an input out of order must not
+            // execute queries that would not be executed if an earlier input changed.
+            db.zalsa_local().set_verify_only();
+            let mut cycle_heads = CycleHeadKeys::new();
+            let result = crate::cancelled::catch_unwind(AssertUnwindSafe(|| {
+                crate::run_async::try_without_blocking(Blocking::Never, || {
+                    dependencies[index].maybe_changed_after(
+                        db.into(),
+                        zalsa,
+                        last_verified_at,
+                        &mut cycle_heads,
+                    )
+                })
+            }));
+            // If verifying the input requires executing a query or a query claimed by another
+            // execution, it is verified again in order, which may execute the query or block on
+            // that execution. The same applies to failures and panics, which may be caused by
+            // verifying the input out of order.
+            if let Ok(Some(Ok(result))) = result {
+                if let VerifyResult::Changed = result {
+                    first_changed.fetch_min(index, Ordering::Relaxed);
+                }
+                *results[index].lock() = ParallelVerifyResult::Verified(result, cycle_heads);
+            }
+        });
+
+        results
+            .iter_mut()
+            .map(|result| mem::replace(result.get_mut(), ParallelVerifyResult::Skipped))
+            .collect()
     }
 }
 
+/// Result of verifying an input of a memo in parallel, see
+/// [`IngredientImpl::verify_inputs_in_parallel`].
+enum ParallelVerifyResult {
+    Verified(VerifyResult, CycleHeadKeys),
+
+    /// The input was not verified, and needs to be verified again.
+    Skipped,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub(super) enum ShallowUpdate {
     /// The memo is from this revision and has already been verified
@@ -10,11 +10,12 @@ use crate::ingredient::{Ingredient, WaitForResult};
 use crate::key::DatabaseKeyIndex;
 use crate::revision::AtomicRevision;
 use crate::runtime::Running;
+use crate::salsa_error::SalsaError;
 use crate::sync::atomic::Ordering;
 use crate::table::memo::MemoTableWithTypesMut;
 use crate::zalsa::{MemoIngredientIndex, Zalsa};
 use crate::zalsa_local::{QueryOriginRef, QueryRevisions, ZalsaLocal};
-use crate::{Event, EventKind, Id, Revision};
+use crate::{Event, EventKind, EventKinds, Id, Revision};
 
 impl<C: Configuration> IngredientImpl<C> {
     /// Inserts the memo for the given key; (atomically) overwrites and returns any previously existing memo
@@ -59,10 +60,13 @@ impl<C: Configuration> IngredientImpl<C> {
     /// Evicts the existing memo for the given key, replacing it
     /// with an equivalent memo that has no value. If the memo is untracked, FixpointInitial,
     /// or has values assigned as output of another query, this has no effect.
+    ///
+    /// Returns `true` if a value was evicted.
     pub(super) fn evict_value_from_memo_for(
         table: MemoTableWithTypesMut<'_>,
         memo_ingredient_index: MemoIngredientIndex,
-    ) {
+    ) -> bool {
+        let mut evicted = false;
         let map = |memo: &mut Memo<'static, C>| {
             match memo.revisions.origin.as_ref() {
                 QueryOriginRef::Assigned(_)
@@ -75,12 +79,56 @@ impl<C: Configuration> IngredientImpl<C> {
                 }
                 QueryOriginRef::Derived(_) => {
                     // Set the memo value to `None`.
+                    evicted = memo.value.take().is_some();
+                }
+            }
+        };
+
+        table.map_memo(memo_ingredient_index, map);
+        evicted
+    }
+
+    /// Like [`Self::evict_value_from_memo_for`], but only evicts the value if the memo
+    /// was last verified before `verified_before`.
+    ///
+    /// Returns `None` if there is no value that can be evicted, and otherwise
+    /// whether the value was evicted.
+    pub(super) fn evict_stale_value_from_memo_for(
+        table: MemoTableWithTypesMut<'_>,
+        memo_ingredient_index: MemoIngredientIndex,
+        verified_before: Revision,
+    ) -> Option<bool> {
+        let mut evicted = None;
+        let map = |memo: &mut Memo<'static, C>| {
+            if memo.has_evictable_value() {
+                let stale = memo.verified_at.load() < verified_before;
+                if stale {
                     memo.value = None;
                 }
+                evicted = Some(stale);
             }
         };
 
-        table.map_memo(memo_ingredient_index, map)
+        table.map_memo(memo_ingredient_index, map);
+        evicted
+    }
+}
+
+impl<C: Configuration> Memo<'_, C> {
+    /// Returns the size of the value in bytes, if it can be evicted by
+    /// [`IngredientImpl::evict_value_from_memo_for`], or 0 otherwise.
+    pub(super) fn evictable_memory(&self) -> usize {
+        match &self.value {
+            Some(value) if self.has_evictable_value() => {
+                std::mem::size_of::<C::Output<'static>>() + C::heap_size(value)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if the memo has a value that can be reconstructed after evicting it.
+    fn has_evictable_value(&self) -> bool {
+        self.value.is_some() && matches!(self.revisions.origin.as_ref(), QueryOriginRef::Derived(_))
     }
 }
 
@@ -95,6 +143,11 @@ pub struct Memo<'db, C: Configuration> {
 
     /// Revision information
     pub(super) revisions: QueryRevisions,
+
+    /// The fingerprint of the value, for functions declared with the `fingerprint` option.
+    /// It outlives the value itself if the value is evicted, so that re-executions can still
+    /// be backdated.
+    pub(super) fingerprint: C::Fingerprint,
 }
 
 impl<'db, C: Configuration> Memo<'db, C> {
@@ -111,6 +164,7 @@ impl<'db, C: Configuration> Memo<'db, C> {
             value,
             verified_at: AtomicRevision::from(revision_now),
             revisions,
+            fingerprint: Default::default(),
         }
     }
 
@@ -129,23 +183,23 @@ impl<'db, C: Configuration> Memo<'db, C> {
     /// other thread to complete the fixpoint iteration, and then retry fetching our own memo.
     ///
     /// Return `true` if the caller should retry, `false` if the caller should go ahead and return
-    /// this memo to the caller.
+    /// this memo to the caller. Fails if a cycle head panicked on another thread.
     #[inline(always)]
     pub(super) fn provisional_retry(
         &self,
         zalsa: &Zalsa,
         zalsa_local: &ZalsaLocal,
         database_key_index: DatabaseKeyIndex,
-    ) -> bool {
+    ) -> Result<bool, SalsaError> {
         if self.revisions.cycle_heads().is_empty() {
-            return false;
+            return Ok(false);
         }
 
         if !self.may_be_provisional() {
-            return false;
+            return Ok(false);
         };
 
-        if self.block_on_heads(zalsa, zalsa_local) {
+        Ok(if self.block_on_heads(zalsa, zalsa_local)? {
             // If we get here, we are a provisional value of
             // the cycle head (either initial value, or from a later iteration) and should be
             // returned to caller to allow fixpoint iteration to proceed.
@@ -157,29 +211,33 @@ impl<'db, C: Configuration> Memo<'db, C> {
                 "Retrying provisional memo {database_key_index:?} after awaiting cycle heads."
             );
             true
-        }
+        })
     }
 
     /// Blocks on all cycle heads (recursively) that this memo depends on.
     ///
     /// Returns `true` if awaiting all cycle heads results in a cycle. This means, they're all waiting
-    /// for us to make progress.
+    /// for us to make progress. Fails if a cycle head panicked on another thread.
     #[inline(always)]
-    pub(super) fn block_on_heads(&self, zalsa: &Zalsa, zalsa_local: &ZalsaLocal) -> bool {
+    pub(super) fn block_on_heads(
+        &self,
+        zalsa: &Zalsa,
+        zalsa_local: &ZalsaLocal,
+    ) -> Result<bool, SalsaError> {
         // IMPORTANT: If you make changes to this function, make sure to run `cycle_nested_deep` with
         // shuttle with at least 10k iterations.
 
         // The most common case is that the entire cycle is running in the same thread.
         // If that's the case, short circuit and return `true` immediately.
         if self.all_cycles_on_stack(zalsa_local) {
-            return true;
+            return Ok(true);
         }
 
         // Otherwise, await all cycle heads, recursively.
         return block_on_heads_cold(zalsa, self.cycle_heads());
 
         #[inline(never)]
-        fn block_on_heads_cold(zalsa: &Zalsa, heads: &CycleHeads) -> bool {
+        fn block_on_heads_cold(zalsa: &Zalsa, heads: &CycleHeads) -> Result<bool, SalsaError> {
             let _entered = crate::tracing::debug_span!("block_on_heads").entered();
             let mut cycle_heads = TryClaimCycleHeadsIter::new(zalsa, heads);
             let mut all_cycles = true;
@@ -195,12 +253,12 @@ impl<'db, C: Configuration> Memo<'db, C> {
                     }
                     TryClaimHeadsResult::Running(running) => {
                         all_cycles = false;
-                        running.block_on(&mut cycle_heads);
+                        running.block_on(&mut cycle_heads)?;
                     }
                 }
             }
 
-            all_cycles
+            Ok(all_cycles)
         }
     }
 
@@ -265,7 +323,7 @@ impl<'db, C: Configuration> Memo<'db, C> {
     /// values have changed since.
     #[inline]
     pub(super) fn mark_as_verified(&self, zalsa: &Zalsa, database_key_index: DatabaseKeyIndex) {
-        zalsa.event(&|| {
+        zalsa.event_of_kind(EventKinds::DID_VALIDATE_MEMOIZED_VALUE, &|| {
             Event::new(EventKind::DidValidateMemoizedValue {
                 database_key: database_key_index,
             })
@@ -318,6 +376,10 @@ where
         self.revisions.origin.as_ref()
     }
 
+    fn verified_at(&self) -> Revision {
+        self.verified_at.load()
+    }
+
     #[cfg(feature = "salsa_unstable")]
     fn memory_usage(&self) -> crate::database::MemoInfo {
         let size_of = std::mem::size_of::<Memo<C>>() + self.revisions.allocation_size();
@@ -355,11 +417,12 @@ pub(super) struct RunningCycleHead<'me> {
 }
 
 impl<'a> RunningCycleHead<'a> {
-    fn block_on(self, cycle_heads: &mut TryClaimCycleHeadsIter<'a>) {
+    fn block_on(self, cycle_heads: &mut TryClaimCycleHeadsIter<'a>) -> Result<(), SalsaError> {
         let key_index = self.inner.database_key().key_index();
-        self.inner.block_on(cycle_heads.zalsa);
+        self.inner.block_on(cycle_heads.zalsa)?;
 
         cycle_heads.queue_ingredient_heads(self.ingredient, key_index);
+        Ok(())
     }
 }
 
@@ -494,6 +557,7 @@ mod _memory_usage {
         type SalsaStruct<'db> = DummyStruct;
         type Input<'db> = ();
         type Output<'db> = NonZeroUsize;
+        type Fingerprint = ();
         const CYCLE_STRATEGY: CycleRecoveryStrategy = CycleRecoveryStrategy::Panic;
 
         fn values_equal<'db>(_: &Self::Output<'db>, _: &Self::Output<'db>) -> bool {
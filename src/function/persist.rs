@@ -0,0 +1,103 @@
+use std::mem::transmute;
+use std::ptr::NonNull;
+
+use crate::function::memo::Memo;
+use crate::function::{Configuration, IngredientImpl};
+use crate::hash::MemoFingerprint;
+use crate::persistence::{self, PendingMemo, Persist};
+use crate::zalsa::Zalsa;
+use crate::zalsa_local::QueryRevisions;
+use crate::{Durability, Id, PersistenceError};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Writes the memo for `id` to `buf` if it is up-to-date and only depends on
+    /// persistable inputs. See [`crate::persistence`] for the format.
+    pub(super) fn persist_memo_for(&self, zalsa: &Zalsa, id: Id, buf: &mut Vec<u8>) -> bool {
+        if !C::PERSIST {
+            return false;
+        }
+
+        let memo_ingredient_index = self.memo_ingredient_index(zalsa, id);
+        let Some(memo) = self.get_memo_from_table_for(zalsa, id, memo_ingredient_index) else {
+            return false;
+        };
+        let Some(value) = &memo.value else {
+            return false;
+        };
+        if memo.verified_at.load() != zalsa.current_revision()
+            || memo.may_be_provisional()
+            || !memo.revisions.is_persistable()
+        {
+            return false;
+        }
+
+        memo.revisions.durability.encode(buf);
+        if !persistence::encode_edges(zalsa, memo.revisions.origin.as_ref().edges(), buf) {
+            return false;
+        }
+        C::encode_value(value, buf);
+        true
+    }
+
+    /// Decodes a memo written by [`Self::persist_memo_for`], returning a closure that inserts
+    /// it into the memo table for `id`.
+    pub(super) fn restore_memo_for<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+        buf: &mut &[u8],
+    ) -> Result<Option<PendingMemo<'db>>, PersistenceError> {
+        if !C::PERSIST {
+            return Err(PersistenceError::Corrupted);
+        }
+
+        let durability = Durability::decode(buf)?;
+        let edges = persistence::decode_edges(zalsa, buf)?;
+        let value = C::decode_value(buf)?;
+
+        let memo_ingredient_index = self.memo_ingredient_index(zalsa, id);
+        let Some(edges) = edges else {
+            return Ok(None);
+        };
+        // Restored memos can be verified before this function has been called, so the view
+        // caster has to be initialized here. It is only available once the database view has
+        // been registered, which happens on the first call of any function taking that view.
+        if self.view_caster.get().is_none() {
+            let Some(view_caster) = zalsa.views().try_downcaster_for::<C::DbView>() else {
+                return Ok(None);
+            };
+            self.view_caster.get_or_init(|| *view_caster);
+        }
+        if self
+            .get_memo_from_table_for(zalsa, id, memo_ingredient_index)
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(Box::new(move |zalsa: &'db Zalsa| {
+            let revision_now = zalsa.current_revision();
+            let revisions = QueryRevisions::restored(revision_now, durability, edges);
+            let memo = Memo::<'static, C> {
+                fingerprint: C::Fingerprint::new(|| C::fingerprint(&value)),
+                ..Memo::new(Some(value), revision_now, revisions)
+            };
+            let memo = NonNull::from(Box::leak(Box::new(memo)));
+            // SAFETY: The decoded value does not borrow from the database, so it is valid
+            // for any `'db`.
+            let memo =
+                unsafe { transmute::<NonNull<Memo<'static, C>>, NonNull<Memo<'db, C>>>(memo) };
+
+            if let Some(old_value) =
+                self.insert_memo_into_table_for(zalsa, id, memo, memo_ingredient_index)
+            {
+                // SAFETY: Once the revision starts, there will be no outstanding borrows to the
+                // memo contents, and so it will be safe to free.
+                unsafe { self.deleted_entries.push(old_value) };
+            }
+        })))
+    }
+}
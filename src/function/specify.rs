@@ -2,6 +2,7 @@
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
 use crate::function::memo::Memo;
 use crate::function::{Configuration, IngredientImpl};
+use crate::hash::MemoFingerprint;
 use crate::revision::AtomicRevision;
 use crate::sync::atomic::AtomicBool;
 use crate::tracked_struct::TrackedStructInDb;
@@ -73,9 +74,17 @@ where
             extra: QueryRevisionsExtra::default(),
         };
 
+        let fingerprint = C::Fingerprint::new(|| C::fingerprint(&value));
+
         let memo_ingredient_index = self.memo_ingredient_index(zalsa, key);
         if let Some(old_memo) = self.get_memo_from_table_for(zalsa, key, memo_ingredient_index) {
-            self.backdate_if_appropriate(old_memo, database_key_index, &mut revisions, &value);
+            self.backdate_if_appropriate(
+                old_memo,
+                database_key_index,
+                &mut revisions,
+                &value,
+                fingerprint,
+            );
             self.diff_outputs(zalsa, database_key_index, old_memo, &mut revisions);
         }
 
@@ -83,6 +92,7 @@ where
             value: Some(value),
             verified_at: AtomicRevision::from(revision),
             revisions,
+            fingerprint,
         };
 
         crate::tracing::debug!(
@@ -5,7 +5,7 @@ use crate::runtime::{BlockResult, Running, WaitResult};
 use crate::sync::thread::{self, ThreadId};
 use crate::sync::Mutex;
 use crate::zalsa::Zalsa;
-use crate::{Id, IngredientIndex};
+use crate::{Cancelled, Id, IngredientIndex};
 
 pub(crate) type SyncGuard<'me> = crate::sync::MutexGuard<'me, FxHashMap<Id, SyncState>>;
 
@@ -45,6 +45,10 @@ impl SyncTable {
         let mut write = self.syncs.lock();
         match write.entry(key_index) {
             std::collections::hash_map::Entry::Occupied(occupied_entry) => {
+                if !crate::run_async::claim_may_block() {
+                    drop(write);
+                    crate::run_async::yield_now();
+                }
                 let &mut SyncState {
                     id,
                     ref mut anyone_waiting,
@@ -70,6 +74,7 @@ impl SyncTable {
                     id: thread::current().id(),
                     anyone_waiting: false,
                 });
+                crate::run_async::claimed();
                 ClaimResult::Claimed(ClaimGuard {
                     key_index,
                     zalsa,
@@ -99,7 +104,9 @@ impl ClaimGuard<'_> {
         if anyone_waiting {
             self.zalsa.runtime().unblock_queries_blocked_on(
                 DatabaseKeyIndex::new(self.sync_table.ingredient, self.key_index),
-                if thread::panicking() {
+                if Cancelled::is_unwinding_handle() {
+                    WaitResult::Cancelled
+                } else if thread::panicking() {
                     WaitResult::Panicked
                 } else {
                     WaitResult::Completed
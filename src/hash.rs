@@ -1,3 +1,4 @@
+use std::fmt;
 use std::hash::{BuildHasher, Hash, Hasher};
 
 pub(crate) type FxHasher = std::hash::BuildHasherDefault<rustc_hash::FxHasher>;
@@ -29,3 +30,139 @@ impl Hasher for TypeIdHasher {
         self.0
     }
 }
+
+/// Computes a hash of `value` with [`StableHasher`].
+pub fn stable_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = StableHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A 64-bit FNV-1a hasher.
+///
+/// Unlike the hashers used elsewhere in salsa, its output is stable across processes and
+/// platforms, which is required for anything that is persisted. Integers are written in
+/// little-endian order, and `usize`/`isize` are written as 64-bit integers.
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// A 128-bit hash of a tracked function's return value, used for early cutoff when the
+/// function is declared with the `fingerprint` option.
+///
+/// It is computed with 128-bit FNV-1a, so it is stable across processes.
+// Stored as two `u64`s rather than a `u128` to keep the alignment of memos low.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint([u64; 2]);
+
+impl Fingerprint {
+    /// Computes the fingerprint of `value`.
+    pub fn of<T: Hash + ?Sized>(value: &T) -> Self {
+        let mut hasher = FingerprintHasher(0x6c62_272e_07bb_0142_62b8_2175_6295_c58d);
+        value.hash(&mut hasher);
+        Fingerprint([(hasher.0 >> 64) as u64, hasher.0 as u64])
+    }
+}
+
+/// Where a memo stores the [`Fingerprint`] of its value: `Option<Fingerprint>` for functions
+/// declared with the `fingerprint` option, and `()` for all others so that their memos don't
+/// grow.
+pub trait MemoFingerprint: Copy + Default + fmt::Debug + Send + Sync + 'static {
+    /// Returns the stored fingerprint, if any.
+    fn get(&self) -> Option<Fingerprint>;
+
+    /// Stores the fingerprint computed by `fingerprint`, which is only invoked if it is kept.
+    fn new(fingerprint: impl FnOnce() -> Fingerprint) -> Self;
+}
+
+impl MemoFingerprint for () {
+    fn get(&self) -> Option<Fingerprint> {
+        None
+    }
+
+    fn new(_fingerprint: impl FnOnce() -> Fingerprint) -> Self {}
+}
+
+impl MemoFingerprint for Option<Fingerprint> {
+    fn get(&self) -> Option<Fingerprint> {
+        *self
+    }
+
+    fn new(fingerprint: impl FnOnce() -> Fingerprint) -> Self {
+        Some(fingerprint())
+    }
+}
+
+struct FingerprintHasher(u128);
+
+impl Hasher for FingerprintHasher {
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self
+                .0
+                .wrapping_mul(0x0000_0000_0100_0000_0000_0000_0000_013b);
+        }
+    }
+}
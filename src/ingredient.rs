@@ -7,10 +7,10 @@ use crate::cycle::{
 };
 use crate::database::RawDatabase;
 use crate::function::VerifyResult;
-use crate::runtime::Running;
+use crate::runtime::{Running, Runtime};
+use crate::salsa_error::SalsaError;
 use crate::sync::Arc;
 use crate::table::memo::MemoTableTypes;
-use crate::table::Table;
 use crate::zalsa::{transmute_data_mut_ptr, transmute_data_ptr, IngredientIndex, Zalsa};
 use crate::zalsa_local::QueryOriginRef;
 use crate::{DatabaseKeyIndex, Id, Revision};
@@ -43,6 +43,8 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
 
     /// Has the value for `input` in this ingredient changed after `revision`?
     ///
+    /// Fails if verifying `input` requires executing a query that fails.
+    ///
     /// # Safety
     ///
     /// The passed in database needs to be the same one that the ingredient was created with.
@@ -53,7 +55,7 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
         input: Id,
         revision: Revision,
         cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult;
+    ) -> Result<VerifyResult, SalsaError>;
 
     /// Returns information about the current provisional status of `input`.
     ///
@@ -128,8 +130,8 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     ///
     /// **Important:** to actually receive resets, the ingredient must set
     /// [`IngredientRequiresReset::RESET_ON_NEW_REVISION`] to true.
-    fn reset_for_new_revision(&mut self, table: &mut Table) {
-        _ = table;
+    fn reset_for_new_revision(&mut self, runtime: &mut Runtime) {
+        _ = runtime;
         panic!(
             "Ingredient `{}` set `Ingredient::requires_reset_for_new_revision` to true but does \
             not overwrite `Ingredient::reset_for_new_revision`",
@@ -153,6 +155,63 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
         unreachable!("only function ingredients can be part of a cycle")
     }
 
+    /// Returns the size of the memoized value at `key_index` in bytes, or 0 if there
+    /// is no value or it cannot be evicted. See [`crate::Database::set_memory_budget`].
+    fn evictable_memory(&self, zalsa: &Zalsa, key_index: Id) -> usize {
+        let _ = (zalsa, key_index);
+        unreachable!("only function ingredients have memoized values")
+    }
+
+    /// Evicts the memoized value at `key_index`, returning `true` if a value was evicted.
+    fn evict_value(&self, runtime: &mut Runtime, key_index: Id) -> bool {
+        let _ = (runtime, key_index);
+        unreachable!("only function ingredients have memoized values")
+    }
+
+    /// Frees the values that were not read in the last `revisions` revisions, returning the
+    /// number of freed values. See [`crate::Database::collect_interned_garbage`].
+    ///
+    /// (Only relevant to interned ingredients.)
+    ///
+    /// # Safety
+    ///
+    /// The caller must have `&mut` access to the database the ingredient belongs to.
+    unsafe fn collect_garbage(&self, zalsa: &Zalsa, revisions: usize) -> usize {
+        let _ = (zalsa, revisions);
+        0
+    }
+
+    /// Invoked before the pages containing the slots for which `released` returns `true` are
+    /// released by [`crate::Database::release_empty_pages`]. The ingredient must not access
+    /// these slots afterwards, e.g. it has to drop them from its list of freed slots.
+    fn forget_released_slots(&mut self, released: &dyn Fn(Id) -> bool) {
+        let _ = released;
+    }
+
+    /// Returns `true` if slots were quarantined after the generation of their ids overflowed,
+    /// and wait for [`Ingredient::release_quarantined_slots`].
+    ///
+    /// (Only relevant to tracked structs.)
+    fn has_quarantined_slots(&mut self) -> bool {
+        false
+    }
+
+    /// Releases the quarantined slots that were freed before `verified_at` for recycling,
+    /// where every memo in the database was last verified in `verified_at` or later.
+    ///
+    /// (Only relevant to tracked structs.)
+    fn release_quarantined_slots(&mut self, verified_at: Revision) {
+        let _ = verified_at;
+    }
+
+    /// Returns the number of slots that were recycled after the generation of their ids
+    /// overflowed, see `dyn Database::recycled_tracked_struct_slots`.
+    ///
+    /// (Only relevant to tracked structs.)
+    fn recycled_slots(&self) -> usize {
+        0
+    }
+
     /// What were the inputs (if any) that were used to create the value at `key_index`.
     fn origin<'db>(&self, zalsa: &'db Zalsa, key_index: Id) -> Option<QueryOriginRef<'db>> {
         let _ = (zalsa, key_index);
@@ -187,6 +246,76 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     fn memory_usage(&self, _db: &dyn crate::Database) -> Option<Vec<crate::database::SlotInfo>> {
         None
     }
+
+    /// Returns the revision information and dependencies of `key_index` for
+    /// [`crate::QueryGraph`], or `None` if the ingredient has nothing to report.
+    fn dependency_info<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        key_index: Id,
+    ) -> Option<crate::dependency_graph::DependencyInfo<'db>> {
+        let _ = (zalsa, key_index);
+        None
+    }
+
+    /// Returns the profiling counters of this ingredient, if it is profiled.
+    fn profile_counters(&self) -> Option<&crate::profile::ProfileCounters> {
+        None
+    }
+
+    /// Returns the id of the value currently stored in the slot of `slot_id`, which can differ
+    /// from `slot_id` in its generation if the slot was reused, or `None` if the slot is free.
+    ///
+    /// (Only relevant to salsa structs.)
+    fn current_id(&self, zalsa: &Zalsa, slot_id: Id) -> Option<Id> {
+        let _ = zalsa;
+        Some(slot_id)
+    }
+
+    /// Returns the key identifying `key_index` and its current value in persisted data,
+    /// or `None` if memos depending on it cannot be persisted.
+    ///
+    /// (Only relevant to salsa structs.)
+    fn persisted_key(
+        &self,
+        zalsa: &Zalsa,
+        key_index: Id,
+    ) -> Option<crate::persistence::PersistedKey> {
+        let _ = (zalsa, key_index);
+        None
+    }
+
+    /// Returns the id identified by a key returned by [`Ingredient::persisted_key`], or
+    /// `None` if there is no such id or if its value changed.
+    ///
+    /// (Only relevant to salsa structs.)
+    fn restore_key(&self, zalsa: &Zalsa, key: crate::persistence::PersistedKey) -> Option<Id> {
+        let _ = (zalsa, key);
+        None
+    }
+
+    /// Appends the persisted representation of the memo for `key_index` to `buf`.
+    ///
+    /// Returns `false` if there is no memo for `key_index` or if it cannot be persisted.
+    /// (Only relevant to [`crate::function::FunctionIngredient`].)
+    fn persist_memo(&self, zalsa: &Zalsa, key_index: Id, buf: &mut Vec<u8>) -> bool {
+        let _ = (zalsa, key_index, buf);
+        false
+    }
+
+    /// Decodes a memo for `key_index` that was written by [`Ingredient::persist_memo`].
+    ///
+    /// Returns `Ok(None)` if the memo should not be restored, e.g. because a memo for
+    /// `key_index` already exists.
+    fn restore_memo<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        key_index: Id,
+        buf: &mut &[u8],
+    ) -> Result<Option<crate::persistence::PendingMemo<'db>>, crate::PersistenceError> {
+        let _ = (zalsa, key_index, buf);
+        unreachable!("only function ingredients can restore memos")
+    }
 }
 
 impl dyn Ingredient {
@@ -14,12 +14,17 @@ use crate::id::{AsId, FromId, FromIdWithDb};
 use crate::ingredient::Ingredient;
 use crate::input::singleton::{Singleton, SingletonChoice};
 use crate::key::DatabaseKeyIndex;
+use crate::persistence::PersistedKey;
 use crate::plumbing::Jar;
+use crate::salsa_error::SalsaError;
+use crate::stable_id::StableIdMap;
 use crate::sync::Arc;
 use crate::table::memo::{MemoTable, MemoTableTypes};
 use crate::table::{Slot, Table};
 use crate::zalsa::{IngredientIndex, Zalsa};
-use crate::{zalsa_local, Durability, Id, Revision, Runtime};
+use crate::{
+    zalsa_local, Durability, Event, EventKind, EventKinds, Id, Revision, Runtime, StableId,
+};
 
 pub trait Configuration: Any {
     const DEBUG_NAME: &'static str;
@@ -40,6 +45,18 @@ pub trait Configuration: Any {
 
     /// A array of [`Durability`], one per each of the value fields.
     type Durabilities: Send + Sync + fmt::Debug + IndexMut<usize, Output = Durability>;
+
+    /// Computes the [`StableId`] of an input created with the given fields, if the input
+    /// tracks stable identities.
+    fn stable_id(_fields: &Self::Fields) -> Option<StableId> {
+        None
+    }
+
+    /// Computes a stable hash of the given field values, if the input tracks stable
+    /// identities.
+    fn value_hash(_fields: &Self::Fields) -> Option<u64> {
+        None
+    }
 }
 
 pub struct JarImpl<C: Configuration> {
@@ -77,6 +94,7 @@ pub struct IngredientImpl<C: Configuration> {
     ingredient_index: IngredientIndex,
     singleton: C::Singleton,
     memo_table_types: Arc<MemoTableTypes>,
+    stable_ids: StableIdMap,
     _phantom: std::marker::PhantomData<C::Struct>,
 }
 
@@ -86,6 +104,7 @@ impl<C: Configuration> IngredientImpl<C> {
             ingredient_index: index,
             singleton: Default::default(),
             memo_table_types: Arc::new(MemoTableTypes::default()),
+            stable_ids: StableIdMap::default(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -110,6 +129,7 @@ impl<C: Configuration> IngredientImpl<C> {
         revisions: C::Revisions,
         durabilities: C::Durabilities,
     ) -> C::Struct {
+        let stable_id = C::stable_id(&fields);
         let id = self.singleton.with_scope(|| {
             let (id, _) = zalsa_local.allocate(zalsa, self.ingredient_index, |_| Value::<C> {
                 fields,
@@ -123,9 +143,25 @@ impl<C: Configuration> IngredientImpl<C> {
             id
         });
 
+        if let Some(stable_id) = stable_id {
+            self.stable_ids.insert(stable_id, id);
+        }
+
         FromIdWithDb::from_id(id, zalsa)
     }
 
+    /// Returns the [`StableId`] of the given input, if it tracks stable identities.
+    pub fn stable_id(&self, id: C::Struct) -> Option<StableId> {
+        self.stable_ids.stable_id(id.as_id())
+    }
+
+    /// Returns the input with the given [`StableId`], if any.
+    pub fn lookup_stable_id(&self, zalsa: &Zalsa, stable_id: StableId) -> Option<C::Struct> {
+        self.stable_ids
+            .id(stable_id)
+            .map(|id| FromIdWithDb::from_id(id, zalsa))
+    }
+
     /// Change the value of the field `field_index` to a new value.
     ///
     /// # Parameters
@@ -158,8 +194,16 @@ impl<C: Configuration> IngredientImpl<C> {
             runtime.report_tracked_write(*field_durability);
         }
         *field_durability = durability.unwrap_or(*field_durability);
-
-        setter(&mut data.fields)
+        let durability = *field_durability;
+
+        let result = setter(&mut data.fields);
+        runtime.event(EventKinds::DID_SET_INPUT_FIELD, &|| {
+            Event::new(EventKind::DidSetInputField {
+                key: DatabaseKeyIndex::new(self.ingredient_index.successor(field_index), id),
+                durability,
+            })
+        });
+        result
     }
 
     /// Get the singleton input previously created (if any).
@@ -227,10 +271,10 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
         _input: Id,
         _revision: Revision,
         _cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         // Input ingredients are just a counter, they store no data, they are immortal.
         // Their *fields* are stored in function ingredients elsewhere.
-        VerifyResult::unchanged()
+        Ok(VerifyResult::unchanged())
     }
 
     fn debug_name(&self) -> &'static str {
@@ -245,6 +289,18 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
         &mut self.memo_table_types
     }
 
+    fn persisted_key(&self, zalsa: &Zalsa, key_index: Id) -> Option<PersistedKey> {
+        Some(PersistedKey {
+            stable_id: self.stable_ids.stable_id(key_index)?,
+            value_hash: C::value_hash(&Self::data(zalsa, key_index).fields)?,
+        })
+    }
+
+    fn restore_key(&self, zalsa: &Zalsa, key: PersistedKey) -> Option<Id> {
+        let id = self.stable_ids.id(key.stable_id)?;
+        (C::value_hash(&Self::data(zalsa, id).fields) == Some(key.value_hash)).then_some(id)
+    }
+
     /// Returns memory usage information about any inputs.
     #[cfg(feature = "salsa_unstable")]
     fn memory_usage(&self, db: &dyn crate::Database) -> Option<Vec<crate::database::SlotInfo>> {
@@ -2,9 +2,11 @@ use std::fmt;
 use std::marker::PhantomData;
 
 use crate::cycle::CycleHeadKeys;
+use crate::dependency_graph::DependencyInfo;
 use crate::function::VerifyResult;
 use crate::ingredient::Ingredient;
 use crate::input::{Configuration, IngredientImpl, Value};
+use crate::salsa_error::SalsaError;
 use crate::sync::Arc;
 use crate::table::memo::MemoTableTypes;
 use crate::zalsa::IngredientIndex;
@@ -57,9 +59,25 @@ where
         input: Id,
         revision: Revision,
         _cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         let value = <IngredientImpl<C>>::data(zalsa, input);
-        VerifyResult::changed_if(value.revisions[self.field_index] > revision)
+        Ok(VerifyResult::changed_if(
+            value.revisions[self.field_index] > revision,
+        ))
+    }
+
+    fn dependency_info<'db>(
+        &'db self,
+        zalsa: &'db crate::zalsa::Zalsa,
+        key_index: Id,
+    ) -> Option<DependencyInfo<'db>> {
+        let value = <IngredientImpl<C>>::data(zalsa, key_index);
+        Some(DependencyInfo {
+            changed_at: value.revisions[self.field_index],
+            verified_at: None,
+            durability: value.durabilities[self.field_index],
+            edges: &[],
+        })
     }
 
     fn fmt_index(&self, index: crate::Id, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
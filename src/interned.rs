@@ -17,11 +17,13 @@ use crate::id::{AsId, FromId};
 use crate::ingredient::Ingredient;
 use crate::plumbing::{Jar, ZalsaLocal};
 use crate::revision::AtomicRevision;
+use crate::salsa_error::SalsaError;
+use crate::stable_id::StableIdMap;
 use crate::sync::{Arc, Mutex, OnceLock};
 use crate::table::memo::{MemoTable, MemoTableTypes, MemoTableWithTypesMut};
 use crate::table::Slot;
 use crate::zalsa::{IngredientIndex, Zalsa};
-use crate::{DatabaseKeyIndex, Event, EventKind, Id, Revision};
+use crate::{DatabaseKeyIndex, Event, EventKind, EventKinds, Id, Revision, StableId};
 
 /// Trait that defines the key properties of an interned struct.
 ///
@@ -39,6 +41,12 @@ pub trait Configuration: Sized + 'static {
     #[cfg(not(test))] // More aggressive garbage collection by default when testing.
     const REVISIONS: NonZeroUsize = NonZeroUsize::new(1).unwrap();
 
+    /// Computes the [`StableId`] of a value with the given fields, if the struct tracks
+    /// stable identities.
+    fn stable_id(_fields: &Self::Fields<'_>) -> Option<StableId> {
+        None
+    }
+
     /// The fields of the struct being interned.
     type Fields<'db>: InternedData;
 
@@ -75,6 +83,9 @@ pub struct IngredientImpl<C: Configuration> {
 
     memo_table_types: Arc<MemoTableTypes>,
 
+    /// The stable identities of the interned values, if the struct tracks them.
+    stable_ids: StableIdMap,
+
     _marker: PhantomData<fn() -> C>,
 }
 
@@ -87,19 +98,25 @@ struct IngredientShard<C: Configuration> {
 
     /// An intrusive linked list for LRU.
     lru: LinkedList<ValueAdapter<C>>,
+
+    /// An intrusive linked list of the values freed by [`IngredientImpl::collect_garbage`],
+    /// whose slots are reused before allocating new ones.
+    free: LinkedList<ValueAdapter<C>>,
 }
 
 impl<C: Configuration> Default for IngredientShard<C> {
     fn default() -> Self {
         Self {
             lru: LinkedList::default(),
+            free: LinkedList::default(),
             key_map: hashbrown::HashTable::new(),
         }
     }
 }
 
 // SAFETY: `LinkedListLink` is `!Sync`, however, the linked list is only accessed through the
-// ingredient lock, and values are only ever linked to a single list on the ingredient.
+// ingredient lock, and values are only ever linked to a single list on the ingredient, i.e.
+// either the LRU or the free list of their shard.
 unsafe impl<C: Configuration> Sync for Value<C> {}
 
 intrusive_adapter!(ValueAdapter<C> = UnsafeRef<Value<C>>: Value<C> { link: LinkedListLink } where C: Configuration);
@@ -118,8 +135,9 @@ where
     /// The interned fields for this value.
     ///
     /// These are valid for read-only access as long as the lock is held
-    /// or the value has been validated in the current revision.
-    fields: UnsafeCell<C::Fields<'static>>,
+    /// or the value has been validated in the current revision. They are
+    /// `None` once the value is freed by [`IngredientImpl::collect_garbage`].
+    fields: UnsafeCell<Option<C::Fields<'static>>>,
 
     /// Memos attached to this interned value.
     ///
@@ -188,7 +206,31 @@ where
         // SAFETY: The fact that this function is safe is technically unsound. However, interned
         // values are only exposed if they have been validated in the current revision, which
         // ensures that they are not reused while being accessed.
-        unsafe { &*self.fields.get() }
+        unsafe { self.live_fields() }
+    }
+
+    /// Returns the fields of this value, panicking if it was freed.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held for the shard containing the value, or the value must have been
+    /// validated in the current revision.
+    unsafe fn live_fields(&self) -> &C::Fields<'static> {
+        // SAFETY: Guaranteed by the caller.
+        match unsafe { &*self.fields.get() } {
+            Some(fields) => fields,
+            None => panic!("attempted to read an interned value that was garbage collected"),
+        }
+    }
+
+    /// Returns `true` if the value was freed by [`IngredientImpl::collect_garbage`].
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held for the shard containing the value.
+    unsafe fn is_freed(&self) -> bool {
+        // SAFETY: Guaranteed by the caller.
+        unsafe { (*self.fields.get()).is_none() }
     }
 
     /// Returns memory usage information about the interned value.
@@ -256,6 +298,7 @@ where
             revision_queue: RevisionQueue::default(),
             shift: usize::BITS - shards.trailing_zeros(),
             shards: (0..shards).map(|_| Default::default()).collect(),
+            stable_ids: StableIdMap::default(),
             _marker: PhantomData,
         }
     }
@@ -363,7 +406,7 @@ where
             if { value_shared.last_interned_at } < current_revision {
                 value_shared.last_interned_at = current_revision;
 
-                zalsa.event(&|| {
+                zalsa.event_of_kind(EventKinds::DID_VALIDATE_INTERNED_VALUE, &|| {
                     Event::new(EventKind::DidValidateInternedValue {
                         key: index,
                         revision: current_revision,
@@ -468,6 +511,13 @@ where
                 continue;
             };
 
+            zalsa.event_of_kind(EventKinds::DID_GARBAGE_COLLECT_INTERNED, &|| {
+                Event::new(EventKind::DidGarbageCollectInterned {
+                    key: self.database_key_index(old_id),
+                    revision: value_shared.last_interned_at,
+                })
+            });
+
             // Mark the slot as reused.
             *value_shared = ValueShared {
                 id: new_id,
@@ -486,7 +536,7 @@ where
                 current_revision,
             );
 
-            zalsa.event(&|| {
+            zalsa.event_of_kind(EventKinds::DID_REUSE_INTERNED_VALUE, &|| {
                 Event::new(EventKind::DidReuseInternedValue {
                     key: index,
                     revision: current_revision,
@@ -498,11 +548,6 @@ where
             // SAFETY: The value pointer is valid for the lifetime of the database.
             let value = unsafe { &*UnsafeRef::into_raw(cursor.remove().unwrap()) };
 
-            // SAFETY: We hold the lock for the shard containing the value, and the
-            // value has not been interned in the current revision, so no references to
-            // it can exist.
-            let old_fields = unsafe { &mut *value.fields.get() };
-
             // Remove the previous value from the ID map.
             //
             // Note that while the ID stays the same when a slot is reused, the fields,
@@ -510,17 +555,30 @@ where
             // map. Crucially, we know that the hashes for the old and new fields both map
             // to the same shard, because we determined the initial shard based on the new
             // fields and only accessed the LRU list for that shard.
-            let old_hash = self.hasher.hash_one(&*old_fields);
+            //
+            // SAFETY: We hold the lock for the shard containing the value.
+            let old_hash = self.hasher.hash_one(unsafe { value.live_fields() });
             shard
                 .key_map
                 .find_entry(old_hash, |found_id: &Id| *found_id == old_id)
                 .expect("interned value in LRU so must be in key_map")
                 .remove();
 
+            // SAFETY: We hold the lock for the shard containing the value, and the
+            // value has not been interned in the current revision, so no references to
+            // it can exist.
+            let old_fields = unsafe { &mut *value.fields.get() };
+
             // Update the fields.
             //
             // SAFETY: We call `from_internal_data` to restore the correct lifetime before access.
-            *old_fields = unsafe { self.to_internal_data(assemble(new_id, key)) };
+            let new_fields =
+                old_fields.insert(unsafe { self.to_internal_data(assemble(new_id, key)) });
+
+            if let Some(stable_id) = C::stable_id(new_fields) {
+                self.stable_ids.remove(old_id);
+                self.stable_ids.insert(stable_id, new_id);
+            }
 
             // SAFETY: We hold the lock for the shard containing the value.
             let hasher = |id: &_| unsafe { self.value_hash(*id, zalsa) };
@@ -554,7 +612,7 @@ where
         self.intern_id_cold(key, zalsa, zalsa_local, assemble, shard, shard_index, hash)
     }
 
-    /// The cold path for interning a value, allocating a new slot.
+    /// The cold path for interning a value, allocating a new slot or reusing a freed one.
     ///
     /// Returns `true` if the current thread interned the value.
     #[allow(clippy::too_many_arguments)]
@@ -582,25 +640,57 @@ where
             // `last_interned_at` needs to be `Revision::MAX`, see the `intern_access_in_different_revision` test.
             .unwrap_or((Durability::MAX, Revision::max()));
 
-        // Allocate the value slot.
-        let (id, value) = zalsa_local.allocate(zalsa, self.ingredient_index, |id| Value::<C> {
-            shard: shard_index as u16,
-            link: LinkedListLink::new(),
-            // SAFETY: We only ever access the memos of a value that we allocated through
-            // our `MemoTableTypes`.
-            memos: UnsafeCell::new(unsafe { MemoTable::new(self.memo_table_types()) }),
-            // SAFETY: We call `from_internal_data` to restore the correct lifetime before access.
-            fields: UnsafeCell::new(unsafe { self.to_internal_data(assemble(id, key)) }),
-            shared: UnsafeCell::new(ValueShared {
+        let (id, value) = if let Some(value) = shard.free.pop_front() {
+            // Reuse the slot of a value freed by garbage collection.
+            //
+            // SAFETY: The value pointer is valid for the lifetime of the database.
+            let value = unsafe { &*UnsafeRef::into_raw(value) };
+
+            // SAFETY: We hold the lock for the shard containing the value.
+            let value_shared = unsafe { &mut *value.shared.get() };
+
+            // The generation of the ID was already incremented when the value was freed.
+            let id = value_shared.id;
+            zalsa.table().record_reused_slot(id);
+
+            // SAFETY: We hold the lock for the shard containing the value, and the value was
+            // freed, so no references to its fields can exist. We call `from_internal_data` to
+            // restore the correct lifetime before access.
+            unsafe { *value.fields.get() = Some(self.to_internal_data(assemble(id, key))) };
+
+            *value_shared = ValueShared {
                 id,
                 durability,
                 last_interned_at,
-            }),
-        });
+            };
+
+            (id, value)
+        } else {
+            // Allocate the value slot.
+            zalsa_local.allocate(zalsa, self.ingredient_index, |id| Value::<C> {
+                shard: shard_index as u16,
+                link: LinkedListLink::new(),
+                // SAFETY: We only ever access the memos of a value that we allocated through
+                // our `MemoTableTypes`.
+                memos: UnsafeCell::new(unsafe { MemoTable::new(self.memo_table_types()) }),
+                // SAFETY: We call `from_internal_data` to restore the correct lifetime before access.
+                fields: UnsafeCell::new(Some(unsafe { self.to_internal_data(assemble(id, key)) })),
+                shared: UnsafeCell::new(ValueShared {
+                    id,
+                    durability,
+                    last_interned_at,
+                }),
+            })
+        };
 
         // SAFETY: We hold the lock for the shard containing the value.
         let value_shared = unsafe { &mut *value.shared.get() };
 
+        // SAFETY: We hold the lock for the shard containing the value.
+        if let Some(stable_id) = C::stable_id(unsafe { value.live_fields() }) {
+            self.stable_ids.insert(stable_id, id);
+        }
+
         if value_shared.is_reusable::<C>() {
             // Add the value to the front of the LRU list.
             //
@@ -619,7 +709,7 @@ where
             let value = zalsa.table().get::<Value<C>>(id);
 
             // SAFETY: We hold the lock for the shard containing the value.
-            unsafe { self.hasher.hash_one(&*value.fields.get()) }
+            unsafe { self.hasher.hash_one(value.live_fields()) }
         });
 
         let index = self.database_key_index(id);
@@ -634,7 +724,7 @@ where
         // across revisions.
         zalsa_local.report_tracked_read_simple(index, durability, current_revision);
 
-        zalsa.event(&|| {
+        zalsa.event_of_kind(EventKinds::DID_INTERN_VALUE, &|| {
             Event::new(EventKind::DidInternValue {
                 key: index,
                 revision: current_revision,
@@ -676,7 +766,9 @@ where
 
                 let executor = DatabaseKeyIndex::new(ingredient_index, id);
 
-                zalsa.event(&|| Event::new(EventKind::DidDiscard { key: executor }));
+                zalsa.event_of_kind(EventKinds::DID_DISCARD, &|| {
+                    Event::new(EventKind::DidDiscard { key: executor })
+                });
 
                 for stale_output in memo.origin().outputs() {
                     stale_output.remove_stale_output(zalsa, executor);
@@ -701,7 +793,7 @@ where
         let value = zalsa.table().get::<Value<C>>(id);
 
         // SAFETY: We hold the lock for the shard containing the value.
-        unsafe { self.hasher.hash_one(&*value.fields.get()) }
+        unsafe { self.hasher.hash_one(value.live_fields()) }
     }
 
     // Compares the value by its fields to the given key.
@@ -722,7 +814,7 @@ where
         found_value.set(Some(value));
 
         // SAFETY: We hold the lock for the shard containing the value.
-        let fields = unsafe { &*value.fields.get() };
+        let fields = unsafe { value.live_fields() };
 
         HashEqLike::eq(Self::from_internal_data(fields), key)
     }
@@ -737,6 +829,12 @@ where
     pub fn data<'db>(&'db self, zalsa: &'db Zalsa, id: Id) -> &'db C::Fields<'db> {
         let value = zalsa.table().get::<Value<C>>(id);
 
+        // SAFETY: Interned values are only exposed if they have been validated in the
+        // current revision, as checked by the assertion below, which ensures that they
+        // are not reused while being accessed. Reading a value that was freed by garbage
+        // collection panics.
+        let fields = unsafe { value.live_fields() };
+
         debug_assert!(
             {
                 let _shard = self.shards[value.shard as usize].lock();
@@ -750,10 +848,7 @@ where
             "Data was not interned in the latest revision for its durability."
         );
 
-        // SAFETY: Interned values are only exposed if they have been validated in the
-        // current revision, as checked by the assertion above, which ensures that they
-        // are not reused while being accessed.
-        unsafe { Self::from_internal_data(&*value.fields.get()) }
+        Self::from_internal_data(fields)
     }
 
     /// Lookup the fields from an interned struct.
@@ -770,12 +865,55 @@ where
             // We can clear the key maps now that we have cancelled all other handles.
             shard.lock().key_map.clear();
         }
+
+        self.stable_ids.clear();
+    }
+
+    /// Returns the [`StableId`] of the interned value with the given id, if the struct tracks
+    /// stable identities.
+    pub fn stable_id(&self, id: Id) -> Option<StableId> {
+        self.stable_ids.stable_id(id)
+    }
+
+    /// Returns the interned value with the given [`StableId`], if any.
+    ///
+    /// The value is re-interned, i.e. validated in the current revision, before it is returned.
+    pub fn lookup_stable_id<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        zalsa_local: &'db ZalsaLocal,
+        stable_id: StableId,
+    ) -> Option<C::Struct<'db>> {
+        let id = self.stable_ids.id(stable_id)?;
+        let value = zalsa.table().get::<Value<C>>(id);
+
+        let fields = {
+            let _shard = self.shards[value.shard as usize].lock();
+
+            // SAFETY: We hold the lock for the shard containing the value.
+            let value_shared = unsafe { &*value.shared.get() };
+
+            // The slot may have been reused since we looked up the id.
+            if { value_shared.id } != id {
+                return None;
+            }
+
+            // SAFETY: We hold the lock for the shard containing the value.
+            unsafe { Self::from_internal_data(value.live_fields()) }.clone()
+        };
+
+        Some(self.intern(zalsa, zalsa_local, fields, |_, fields| fields))
     }
 
     #[cfg(feature = "salsa_unstable")]
     /// Returns all data corresponding to the interned struct.
     pub fn entries<'db>(&'db self, zalsa: &'db Zalsa) -> impl Iterator<Item = &'db Value<C>> {
-        zalsa.table().slots_of::<Value<C>>()
+        zalsa.table().slots_of::<Value<C>>().filter(|value| {
+            // SAFETY: The fact that this read is not synchronized is technically unsound, as
+            // freed slots may be reused concurrently. However, values are only freed with
+            // `&mut` access to the database, so values that are not freed stay initialized.
+            !unsafe { value.is_freed() }
+        })
     }
 }
 
@@ -798,7 +936,7 @@ where
         input: Id,
         _revision: Revision,
         _cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         // Record the current revision as active.
         let current_revision = zalsa.current_revision();
         self.revision_queue.record(current_revision);
@@ -811,15 +949,17 @@ where
         // SAFETY: We hold the lock for the shard containing the value.
         let value_shared = unsafe { &mut *value.shared.get() };
 
-        // The slot was reused.
-        if value_shared.id.generation() > input.generation() {
-            return VerifyResult::Changed;
+        // The slot was freed or reused.
+        //
+        // SAFETY: We hold the lock for the shard containing the value.
+        if unsafe { value.is_freed() } || value_shared.id.generation() > input.generation() {
+            return Ok(VerifyResult::Changed);
         }
 
         // Validate the value for the current revision to avoid reuse.
         value_shared.last_interned_at = current_revision;
 
-        zalsa.event(&|| {
+        zalsa.event_of_kind(EventKinds::DID_VALIDATE_INTERNED_VALUE, &|| {
             let index = self.database_key_index(input);
 
             Event::new(EventKind::DidValidateInternedValue {
@@ -829,13 +969,25 @@ where
         });
 
         // Any change to an interned value results in a new ID generation.
-        VerifyResult::unchanged()
+        Ok(VerifyResult::unchanged())
     }
 
     fn debug_name(&self) -> &'static str {
         C::DEBUG_NAME
     }
 
+    fn current_id(&self, zalsa: &Zalsa, slot_id: Id) -> Option<Id> {
+        let value = zalsa.table().get::<Value<C>>(slot_id);
+
+        // SAFETY: `value.shard` is guaranteed to be in-bounds for `self.shards`.
+        let _shard = unsafe { self.shards.get_unchecked(value.shard as usize) }.lock();
+
+        // SAFETY: We hold the lock for the shard containing the value.
+        let value_shared = unsafe { &*value.shared.get() };
+        // SAFETY: We hold the lock for the shard containing the value.
+        (!unsafe { value.is_freed() }).then_some(value_shared.id)
+    }
+
     fn memo_table_types(&self) -> &Arc<MemoTableTypes> {
         &self.memo_table_types
     }
@@ -844,6 +996,123 @@ where
         &mut self.memo_table_types
     }
 
+    fn forget_released_slots(&mut self, released: &dyn Fn(Id) -> bool) {
+        for shard in self.shards.iter_mut() {
+            let shard = shard.get_mut();
+
+            let mut cursor = shard.free.front_mut();
+            while let Some(value) = cursor.get() {
+                // SAFETY: We have `&mut` access to the shard containing the value.
+                if released(unsafe { (*value.shared.get()).id }) {
+                    cursor.remove();
+                } else {
+                    cursor.move_next();
+                }
+            }
+        }
+    }
+
+    /// Frees the values that were not read in the last `revisions` revisions.
+    ///
+    /// Only values that are eligible for reuse by the lazy garbage collection, i.e. values
+    /// with [`Durability::LOW`], are freed. Their fields and memos are dropped, the ID
+    /// generation of their slot is incremented, and the slot is reused by the next value
+    /// interned into the same shard.
+    unsafe fn collect_garbage(&self, zalsa: &Zalsa, revisions: usize) -> usize {
+        // Values read in the current revision must never be freed.
+        let Some(read_before) = Revision::from_opt(
+            zalsa
+                .current_revision()
+                .as_usize()
+                .saturating_sub(revisions),
+        ) else {
+            return 0;
+        };
+
+        let mut freed = 0;
+        for shard in self.shards.iter() {
+            let shard = &mut *shard.lock();
+
+            let mut cursor = shard.lru.front_mut();
+            while let Some(value) = cursor.get() {
+                // SAFETY: We hold the lock for the shard containing the value.
+                let value_shared = unsafe { &mut *value.shared.get() };
+
+                // Note that the list is not strictly sorted by `last_interned_at`, as
+                // `maybe_changed_after` validates values without moving them, so we have to
+                // check every value.
+                if { value_shared.last_interned_at } >= read_before {
+                    cursor.move_next();
+                    continue;
+                }
+
+                let old_id = value_shared.id;
+
+                zalsa.event_of_kind(EventKinds::DID_GARBAGE_COLLECT_INTERNED, &|| {
+                    Event::new(EventKind::DidGarbageCollectInterned {
+                        key: self.database_key_index(old_id),
+                        revision: value_shared.last_interned_at,
+                    })
+                });
+
+                // Remove the value from the LRU list.
+                //
+                // SAFETY: The value pointer is valid for the lifetime of the database.
+                let value = unsafe { &*UnsafeRef::into_raw(cursor.remove().unwrap()) };
+
+                // Remove the value from the ID map.
+                //
+                // SAFETY: We hold the lock for the shard containing the value.
+                let hash = self.hasher.hash_one(unsafe { value.live_fields() });
+                shard
+                    .key_map
+                    .find_entry(hash, |found_id: &Id| *found_id == old_id)
+                    .expect("interned value in LRU so must be in key_map")
+                    .remove();
+
+                self.stable_ids.remove(old_id);
+
+                // SAFETY: The caller has `&mut` access to the database, so no references to
+                // the memos can exist.
+                let memo_table = unsafe { &mut *value.memos.get() };
+
+                // SAFETY: The memo table belongs to a value that we allocated, so it has the
+                // correct type.
+                unsafe { self.clear_memos(zalsa, memo_table, old_id) };
+
+                // Drop the fields. Reading them through a stale ID now panics instead of
+                // observing dropped data.
+                //
+                // SAFETY: The caller has `&mut` access to the database, so no references to
+                // the fields can exist.
+                unsafe { *value.fields.get() = None };
+                zalsa.table().record_freed_slot(old_id);
+
+                // Increment the generation of the ID, so that any memos depending on the
+                // value observe it as changed.
+                //
+                // If the ID is at its maximum generation, we are forced to leak the slot.
+                if let Some(new_id) = old_id.next_generation() {
+                    value_shared.id = new_id;
+
+                    // SAFETY: The value pointer is valid for the lifetime of the database
+                    // and never accessed mutably directly.
+                    shard.free.push_back(unsafe { UnsafeRef::from_raw(value) });
+                }
+
+                freed += 1;
+            }
+
+            // SAFETY: We hold the lock for the shard containing the values.
+            let hasher = |id: &_| unsafe { self.value_hash(*id, zalsa) };
+
+            // Release the memory of the removed entries.
+            shard.key_map.shrink_to_fit(hasher);
+        }
+
+        freed
+    }
+
     /// Returns memory usage information about any interned values.
     #[cfg(all(not(feature = "shuttle"), feature = "salsa_unstable"))]
     fn memory_usage(&self, db: &dyn crate::Database) -> Option<Vec<crate::database::SlotInfo>> {
@@ -2,6 +2,7 @@ use core::fmt;
 
 use crate::cycle::CycleHeadKeys;
 use crate::function::VerifyResult;
+use crate::salsa_error::SalsaError;
 use crate::zalsa::{IngredientIndex, Zalsa};
 use crate::Id;
 
@@ -40,7 +41,16 @@ impl DatabaseKeyIndex {
         zalsa: &Zalsa,
         last_verified_at: crate::Revision,
         cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
+        // The slot was released with its page, see `Database::release_empty_pages`.
+        if zalsa
+            .table()
+            .try_ingredient_index(self.key_index())
+            .is_none()
+        {
+            return Ok(VerifyResult::Changed);
+        }
+
         // SAFETY: The `db` belongs to the ingredient
         unsafe {
             // here, `db` has to be either the correct type already, or a subtype (as far as trait
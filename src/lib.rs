@@ -6,11 +6,14 @@ mod accumulator;
 mod active_query;
 mod attach;
 mod cancelled;
+mod chrome_trace;
 mod cycle;
 mod database;
 mod database_impl;
+mod dependency_graph;
 mod durability;
 mod event;
+mod executor;
 mod function;
 mod hash;
 mod id;
@@ -20,10 +23,18 @@ mod input;
 mod interned;
 mod key;
 mod memo_ingredient_indices;
+mod memory_lru;
+mod parallel;
+mod persistence;
+mod prefetch;
+mod profile;
 mod return_mode;
 mod revision;
+mod run_async;
 mod runtime;
+mod salsa_error;
 mod salsa_struct;
+mod stable_id;
 mod storage;
 mod sync;
 mod table;
@@ -31,17 +42,17 @@ mod tracing;
 mod tracked_struct;
 mod update;
 mod views;
+mod wait_graph;
 mod zalsa;
 mod zalsa_local;
 
 #[cfg(not(feature = "inventory"))]
 mod nonce;
 
-#[cfg(feature = "rayon")]
-mod parallel;
+// Lets the unit tests use the macros, which refer to the `salsa` crate.
+#[cfg(all(test, feature = "macros"))]
+extern crate self as salsa;
 
-#[cfg(feature = "rayon")]
-pub use parallel::{join, par_map};
 #[cfg(feature = "macros")]
 pub use salsa_macros::{accumulator, db, input, interned, tracked, Supertype, Update};
 
@@ -51,21 +62,34 @@ pub use self::database::IngredientInfo;
 #[cfg(feature = "accumulator")]
 pub use self::accumulator::Accumulator;
 pub use self::active_query::Backtrace;
-pub use self::cancelled::Cancelled;
+pub use self::cancelled::{CancellationToken, Cancelled};
+pub use self::chrome_trace::ChromeTraceRecorder;
 pub use self::cycle::CycleRecoveryAction;
 pub use self::database::Database;
 pub use self::database_impl::DatabaseImpl;
+pub use self::dependency_graph::{DependencyEdge, DependencyEdgeKind, DependencyNode, QueryGraph};
 pub use self::durability::Durability;
-pub use self::event::{Event, EventKind};
+pub use self::event::{Event, EventKind, EventKinds, EventSubscriber, ReexecuteReason};
+#[cfg(feature = "rayon")]
+pub use self::executor::RayonExecutor;
+pub use self::executor::{CurrentThreadExecutor, Executor, Task};
 pub use self::id::Id;
 pub use self::input::setter::Setter;
 pub use self::key::DatabaseKeyIndex;
+pub use self::parallel::{join, par_map};
+pub use self::persistence::{Persist, PersistenceError};
+pub use self::prefetch::Prefetched;
+pub use self::profile::QueryProfile;
 pub use self::return_mode::SalsaAsDeref;
 pub use self::return_mode::SalsaAsRef;
 pub use self::revision::Revision;
+pub use self::run_async::RunAsync;
 pub use self::runtime::Runtime;
-pub use self::storage::{Storage, StorageHandle};
+pub use self::salsa_error::SalsaError;
+pub use self::stable_id::StableId;
+pub use self::storage::{Storage, StorageHandle, WouldBlock};
 pub use self::update::Update;
+pub use self::wait_graph::{BlockedThread, WaitGraph};
 pub use self::zalsa::IngredientIndex;
 pub use crate::attach::{attach, with_attached_database};
 
@@ -91,7 +115,7 @@ pub mod plumbing {
         gate_accumulated, macro_if, maybe_backdate, maybe_default, maybe_default_tt,
         return_mode_expression, return_mode_ty, setup_input_struct, setup_interned_struct,
         setup_tracked_assoc_fn_body, setup_tracked_fn, setup_tracked_method_body,
-        setup_tracked_struct, unexpected_cycle_initial, unexpected_cycle_recovery,
+        setup_tracked_struct, stable_id_of, unexpected_cycle_initial, unexpected_cycle_recovery,
     };
 
     #[cfg(feature = "accumulator")]
@@ -100,6 +124,7 @@ pub mod plumbing {
     pub use crate::cycle::{CycleRecoveryAction, CycleRecoveryStrategy};
     pub use crate::database::{current_revision, Database};
     pub use crate::durability::Durability;
+    pub use crate::hash::{stable_hash, Fingerprint, MemoFingerprint};
     pub use crate::id::{AsId, FromId, FromIdWithDb, Id};
     pub use crate::ingredient::{Ingredient, Jar, Location};
     pub use crate::ingredient_cache::IngredientCache;
@@ -108,9 +133,11 @@ pub mod plumbing {
         IngredientIndices, MemoIngredientIndices, MemoIngredientMap, MemoIngredientSingletonIndex,
         NewMemoIngredientIndices,
     };
+    pub use crate::persistence::{Persist, PersistenceError};
     pub use crate::revision::Revision;
     pub use crate::runtime::{stamp, Runtime, Stamp};
     pub use crate::salsa_struct::SalsaStructInDb;
+    pub use crate::stable_id::StableId;
     pub use crate::storage::{HasStorage, Storage};
     pub use crate::table::memo::MemoTableWithTypes;
     pub use crate::tracked_struct::TrackedStructInDb;
@@ -0,0 +1,92 @@
+use std::hash::BuildHasher;
+use std::sync::OnceLock;
+
+use crossbeam_utils::CachePadded;
+use rustc_hash::{FxBuildHasher, FxHashMap};
+
+use crate::key::DatabaseKeyIndex;
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::Mutex;
+
+/// Records when the memos of all tracked functions were last used, to evict the least
+/// recently used ones if there is a memory budget, see [`crate::Database::set_memory_budget`].
+///
+/// Uses are stamped with a clock and recorded in shards, so that fetching memos on
+/// different threads doesn't contend on a single lock. The order across the shards is only
+/// established when evicting.
+pub(crate) struct MemoryLru {
+    clock: AtomicU64,
+    shift: u32,
+    shards: Box<[CachePadded<Mutex<Shard>>]>,
+}
+
+/// The memos of a shard, with the clock value of their last use.
+type Shard = FxHashMap<DatabaseKeyIndex, u64>;
+
+impl Default for MemoryLru {
+    fn default() -> Self {
+        static SHARDS: OnceLock<usize> = OnceLock::new();
+        let shards = *SHARDS.get_or_init(|| {
+            let num_cpus = std::thread::available_parallelism()
+                .map(usize::from)
+                .unwrap_or(1);
+
+            (num_cpus * 4).next_power_of_two()
+        });
+
+        Self {
+            clock: AtomicU64::new(0),
+            shift: usize::BITS - shards.trailing_zeros(),
+            shards: (0..shards).map(|_| Default::default()).collect(),
+        }
+    }
+}
+
+impl MemoryLru {
+    /// Returns the shard for `key`, which is guaranteed to be in-bounds for `self.shards`.
+    #[inline]
+    fn shard(&self, key: DatabaseKeyIndex) -> usize {
+        let hash = FxBuildHasher.hash_one(key) as usize;
+        // https://github.com/xacrimon/dashmap/blob/366ce7e7872866a06de66eb95002fa6cf2c117a7/src/lib.rs#L421
+        (hash << 7) >> self.shift
+    }
+
+    /// Records that the memo of `key` was used.
+    pub(crate) fn record_use(&self, key: DatabaseKeyIndex) {
+        let used_at = self.clock.fetch_add(1, Ordering::Relaxed);
+        self.shards[self.shard(key)].lock().insert(key, used_at);
+    }
+
+    /// Removes all memos, least recently used first.
+    pub(crate) fn take(&mut self) -> Vec<DatabaseKeyIndex> {
+        let mut memos = self
+            .shards
+            .iter_mut()
+            .flat_map(|shard| shard.get_mut().drain())
+            .collect::<Vec<_>>();
+        memos.sort_unstable_by_key(|&(_, used_at)| used_at);
+        memos.into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// Records the uses of `keys`, in order.
+    pub(crate) fn extend(&mut self, keys: impl IntoIterator<Item = DatabaseKeyIndex>) {
+        for key in keys {
+            let used_at = self.clock.fetch_add(1, Ordering::Relaxed);
+            let shard = self.shard(key);
+            self.shards[shard].get_mut().insert(key, used_at);
+        }
+    }
+
+    /// Keeps only the memos for which `keep` returns `true`.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(DatabaseKeyIndex) -> bool) {
+        for shard in self.shards.iter_mut() {
+            shard.get_mut().retain(|&key, _| keep(key));
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for shard in self.shards.iter_mut() {
+            shard.get_mut().clear();
+        }
+    }
+}
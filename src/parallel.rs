@@ -1,7 +1,13 @@
+#[cfg(feature = "rayon")]
 use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
 
+use crate::attach;
+use crate::sync::Mutex;
 use crate::{database::RawDatabase, views::DatabaseDownCaster, Database};
 
+/// Maps `inputs` with `op` on the executor of the database, passing a fork of `db` to
+/// every execution.
+#[cfg(feature = "rayon")]
 pub fn par_map<Db, F, T, R, C>(db: &Db, inputs: impl IntoParallelIterator<Item = T>, op: F) -> C
 where
     Db: Database + ?Sized + Send,
@@ -10,15 +16,94 @@ where
     R: Send + Sync,
     C: FromParallelIterator<R>,
 {
-    let views = db.zalsa().views();
-    let caster = &views.downcaster_for::<Db>();
-    let db_caster = &views.downcaster_for::<dyn Database>();
-    inputs
+    use rayon::iter::IndexedParallelIterator;
+
+    let inputs = inputs.into_par_iter().collect::<Vec<_>>();
+    let Some(executor) = db.zalsa().rayon_executor() else {
+        return map_forked(db, inputs, op).into_par_iter().collect();
+    };
+
+    let forks = forks(db, inputs.len());
+    executor
+        .install(|| {
+            inputs
+                .into_par_iter()
+                .zip(forks)
+                .map(|(input, db)| op(db.as_view(), input))
+                .collect::<Vec<_>>()
+        })
         .into_par_iter()
-        .map_with(
-            DbForkOnClone(db.fork_db(), caster, db_caster),
-            |db, element| op(db.as_view(), element),
-        )
+        .collect()
+}
+
+/// Maps `inputs` with `op` on the executor of the database, passing a fork of `db` to
+/// every execution.
+#[cfg(not(feature = "rayon"))]
+pub fn par_map<Db, F, T, R, C>(db: &Db, inputs: impl IntoIterator<Item = T>, op: F) -> C
+where
+    Db: Database + ?Sized + Send,
+    F: Fn(&Db, T) -> R + Sync + Send,
+    T: Send,
+    R: Send + Sync,
+    C: FromIterator<R>,
+{
+    let inputs = inputs.into_iter().collect::<Vec<_>>();
+    map_forked(db, inputs, op).into_iter().collect()
+}
+
+fn map_forked<Db, F, T, R>(db: &Db, inputs: Vec<T>, op: F) -> Vec<R>
+where
+    Db: Database + ?Sized + Send,
+    F: Fn(&Db, T) -> R + Sync + Send,
+    T: Send,
+    R: Send + Sync,
+{
+    let inputs = inputs
+        .into_iter()
+        .map(|input| Mutex::new(Some(input)))
+        .collect::<Vec<_>>();
+    let mut outputs = inputs.iter().map(|_| Mutex::new(None)).collect::<Vec<_>>();
+    for_each_fork(db, inputs.len(), |db, index| {
+        let input = inputs[index].lock().take().unwrap();
+        *outputs[index].lock() = Some(op(db, input));
+    });
+
+    outputs
+        .iter_mut()
+        .map(|output| output.get_mut().take().unwrap())
+        .collect()
+}
+
+/// Executes `op` with every index in `0..len` on the executor of the database, passing
+/// a fork of `db` to every execution.
+pub(crate) fn for_each_fork<Db>(db: &Db, len: usize, op: impl Fn(&Db, usize) + Sync)
+where
+    Db: Database + ?Sized + Send,
+{
+    let forks = forks(db, len)
+        .into_iter()
+        .map(|fork| Mutex::new(Some(fork)))
+        .collect::<Vec<_>>();
+    db.zalsa().executor().for_each(len, &|index| {
+        let db = forks[index].lock().take().unwrap();
+        let view = db.as_view();
+        attach::detach(|| attach::attach(view, || op(view, index)));
+    });
+}
+
+/// Forks `db` `len` times.
+///
+/// We need to fork eagerly, before dispatching any execution: forking on the workers would
+/// race with the changes the database makes after the executions started.
+fn forks<Db>(db: &Db, len: usize) -> Vec<DbForkOnClone<'_, Db>>
+where
+    Db: Database + ?Sized + Send,
+{
+    let views = db.zalsa().views();
+    let caster = views.downcaster_for::<Db>();
+    let db_caster = views.downcaster_for::<dyn Database>();
+    (0..len)
+        .map(|_| DbForkOnClone(db.fork_db(), caster, db_caster))
         .collect()
 }
 
@@ -65,27 +150,19 @@ where
     RA: Send,
     RB: Send,
 {
-    #[derive(Copy, Clone)]
-    struct AssertSend<T>(T);
-    // SAFETY: We send owning pointers over, which are Send, given the `Db` type parameter above is Send
-    unsafe impl<T> Send for AssertSend<T> {}
-
-    let caster = &db.zalsa().views().downcaster_for::<Db>();
-    // we need to fork eagerly, as `rayon::join_context` gives us no option to tell whether we get
+    let views = db.zalsa().views();
+    let caster = &views.downcaster_for::<Db>();
+    let db_caster = &views.downcaster_for::<dyn Database>();
+    // we need to fork eagerly, as the executor gives us no option to tell whether we get
     // moved to another thread before the closure is executed
-    let db_a = AssertSend(db.fork_db());
-    let db_b = AssertSend(db.fork_db());
-    let res = rayon::join(
-        // SAFETY: `caster` is derived from a `db` fitting for our database clone
-        move || a(unsafe { caster.downcast_unchecked({ db_a }.0) }),
-        // SAFETY: `caster` is derived from a `db` fitting for our database clone
-        move || b(unsafe { caster.downcast_unchecked({ db_b }.0) }),
-    );
+    let db_a = DbForkOnClone(db.fork_db(), caster, db_caster);
+    let db_b = DbForkOnClone(db.fork_db(), caster, db_caster);
 
-    // SAFETY: `db` has been box allocated and leaked by `fork_db`
-    // FIXME: Clean this mess up, RAII
-    _ = unsafe { Box::from_raw(caster.downcast_mut_unchecked(db_a.0)) };
-    // SAFETY: `db` has been box allocated and leaked by `fork_db`
-    _ = unsafe { Box::from_raw(caster.downcast_mut_unchecked(db_b.0)) };
-    res
+    let (mut ra, mut rb) = (None, None);
+    let (slot_a, slot_b) = (&mut ra, &mut rb);
+    db.zalsa().executor().join(
+        Box::new(move || *slot_a = Some(attach::detach(|| a(db_a.as_view())))),
+        Box::new(move || *slot_b = Some(attach::detach(|| b(db_b.as_view())))),
+    );
+    (ra.unwrap(), rb.unwrap())
 }
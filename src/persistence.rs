@@ -0,0 +1,552 @@
+//! Opt-in persistence of memoized values and their dependency edges.
+//!
+//! Tracked functions declared with `#[salsa::tracked(persist)]` have their memos written by
+//! [`Database::persist_memos`](`crate::Database::persist_memos`). The resulting bytes can be
+//! loaded into a fresh database with [`Database::restore_memos`](`crate::Database::restore_memos`),
+//! after which the restored memos are deep-verified against their recorded inputs on first use
+//! instead of being re-executed.
+//!
+//! Memos and their dependencies are keyed by the [`StableId`] of their input structs, so only
+//! memos that solely depend on inputs declared with the `stable_id` option are persisted.
+//! Restoring therefore requires that the fresh database recreates these inputs before calling
+//! `restore_memos`, in any order. Every key also records a hash of the field values of the
+//! input when the memo was persisted, and memos depending on an input whose current values
+//! hash differently are not restored.
+//!
+//! The serialized data carries a fingerprint of the registered ingredients (their debug names
+//! and source locations) and of the number of durability levels. Data written by a database
+//! with a different set of ingredients is rejected with [`PersistenceError::SchemaMismatch`]
+//! instead of being misapplied.
+
+use std::fmt;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use crate::hash::StableHasher;
+use crate::zalsa::{IngredientIndex, Zalsa};
+use crate::{Durability, StableId};
+
+const MAGIC: &[u8; 8] = b"salsa\0mm";
+
+/// Version of the serialized format; bump this whenever the layout changes.
+const FORMAT_VERSION: u32 = 3;
+
+/// Error returned when restoring persisted memos fails.
+///
+/// No memos are restored if an error is returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PersistenceError {
+    /// The data does not start with the expected header or uses an unsupported format version.
+    UnsupportedFormat,
+
+    /// The data was written by a database with a different set of ingredients.
+    SchemaMismatch,
+
+    /// The data ended unexpectedly or contains an invalid value.
+    Corrupted,
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let why = match self {
+            PersistenceError::UnsupportedFormat => "unsupported format",
+            PersistenceError::SchemaMismatch => "schema mismatch",
+            PersistenceError::Corrupted => "corrupted data",
+        };
+        f.write_str("failed to restore memos: ")?;
+        f.write_str(why)
+    }
+}
+
+impl std::error::Error for PersistenceError {}
+
+/// A value that can be written to and read back from persisted memo data.
+///
+/// The return type of a `#[salsa::tracked(persist)]` function must implement this trait.
+pub trait Persist: Sized {
+    /// Appends the serialized representation of `self` to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Reads a value from the front of `buf`, advancing it past the consumed bytes.
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError>;
+}
+
+pub(crate) fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], PersistenceError> {
+    if buf.len() < len {
+        return Err(PersistenceError::Corrupted);
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+fn encode_len(len: usize, buf: &mut Vec<u8>) {
+    (len as u64).encode(buf);
+}
+
+fn decode_len(buf: &mut &[u8]) -> Result<usize, PersistenceError> {
+    let len = usize::try_from(u64::decode(buf)?).map_err(|_| PersistenceError::Corrupted)?;
+    // Every element occupies at least one byte, except for zero-sized ones which
+    // we do not bother to optimize for.
+    if len > buf.len() {
+        return Err(PersistenceError::Corrupted);
+    }
+    Ok(len)
+}
+
+macro_rules! persist_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Persist for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+                    let bytes = take(buf, std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+persist_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl Persist for usize {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as u64).encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        usize::try_from(u64::decode(buf)?).map_err(|_| PersistenceError::Corrupted)
+    }
+}
+
+impl Persist for isize {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as i64).encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        isize::try_from(i64::decode(buf)?).map_err(|_| PersistenceError::Corrupted)
+    }
+}
+
+impl Persist for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        match u8::decode(buf)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PersistenceError::Corrupted),
+        }
+    }
+}
+
+impl Persist for char {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as u32).encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        char::from_u32(u32::decode(buf)?).ok_or(PersistenceError::Corrupted)
+    }
+}
+
+impl Persist for () {
+    fn encode(&self, _buf: &mut Vec<u8>) {}
+
+    fn decode(_buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        Ok(())
+    }
+}
+
+impl Persist for String {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_len(self.len(), buf);
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        let len = decode_len(buf)?;
+        let bytes = take(buf, len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| PersistenceError::Corrupted)
+    }
+}
+
+impl Persist for Box<str> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_len(self.len(), buf);
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        String::decode(buf).map(String::into_boxed_str)
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_len(self.len(), buf);
+        for element in self {
+            element.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        let len = decode_len(buf)?;
+        (0..len).map(|_| T::decode(buf)).collect()
+    }
+}
+
+impl<T: Persist> Persist for Box<[T]> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_len(self.len(), buf);
+        for element in self.iter() {
+            element.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        Vec::decode(buf).map(Vec::into_boxed_slice)
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                value.encode(buf);
+            }
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        match u8::decode(buf)? {
+            0 => Ok(None),
+            1 => T::decode(buf).map(Some),
+            _ => Err(PersistenceError::Corrupted),
+        }
+    }
+}
+
+impl<T: Persist, E: Persist> Persist for Result<T, E> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Ok(value) => {
+                buf.push(0);
+                value.encode(buf);
+            }
+            Err(error) => {
+                buf.push(1);
+                error.encode(buf);
+            }
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        match u8::decode(buf)? {
+            0 => T::decode(buf).map(Ok),
+            1 => E::decode(buf).map(Err),
+            _ => Err(PersistenceError::Corrupted),
+        }
+    }
+}
+
+impl<T: Persist> Persist for Box<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        T::encode(self, buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        T::decode(buf).map(Box::new)
+    }
+}
+
+impl<T: Persist> Persist for Arc<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        T::encode(self, buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        T::decode(buf).map(Arc::new)
+    }
+}
+
+macro_rules! persist_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Persist),+> Persist for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self, buf: &mut Vec<u8>) {
+                let ($($name,)+) = self;
+                $($name.encode(buf);)+
+            }
+
+            fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+                Ok(($($name::decode(buf)?,)+))
+            }
+        }
+    };
+}
+
+persist_tuple!(A);
+persist_tuple!(A, B);
+persist_tuple!(A, B, C);
+persist_tuple!(A, B, C, D);
+persist_tuple!(A, B, C, D, E);
+persist_tuple!(A, B, C, D, E, F);
+
+impl Persist for Durability {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (self.index() as u8).encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        Durability::from_index(u8::decode(buf)? as usize).ok_or(PersistenceError::Corrupted)
+    }
+}
+
+impl Persist for IngredientIndex {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_u32().encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        let index = u32::decode(buf)?;
+        if index > IngredientIndex::MAX_INDEX {
+            return Err(PersistenceError::Corrupted);
+        }
+        Ok(IngredientIndex::new(index))
+    }
+}
+
+/// The key of a salsa struct in persisted data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PersistedKey {
+    /// The stable identity of the struct.
+    pub(crate) stable_id: StableId,
+
+    /// A stable hash of the field values of the struct.
+    pub(crate) value_hash: u64,
+}
+
+impl Persist for PersistedKey {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.stable_id.encode(buf);
+        self.value_hash.encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        Ok(PersistedKey {
+            stable_id: StableId::decode(buf)?,
+            value_hash: u64::decode(buf)?,
+        })
+    }
+}
+
+/// A memo that was decoded from persisted data but has not been inserted yet.
+///
+/// Restoring happens in two phases so that a corrupted entry does not leave the
+/// database with only part of the persisted memos applied.
+pub(crate) type PendingMemo<'db> = Box<dyn FnOnce(&'db Zalsa) + 'db>;
+
+/// Computes a fingerprint of the ingredients registered in `zalsa` and the number of
+/// durability levels.
+fn schema_fingerprint(zalsa: &Zalsa) -> u64 {
+    let mut hasher = StableHasher::default();
+    hasher.write_u32(Durability::LEVELS as u32);
+    for ingredient in zalsa.ingredients() {
+        let location = ingredient.location();
+        hasher.write_u32(ingredient.ingredient_index().as_u32());
+        hasher.write(ingredient.debug_name().as_bytes());
+        hasher.write_u8(0);
+        hasher.write(location.file.as_bytes());
+        hasher.write_u8(0);
+        hasher.write_u32(location.line);
+    }
+    hasher.finish()
+}
+
+/// Serializes the persistable memos of all tracked functions registered in `zalsa`.
+pub(crate) fn persist_memos(zalsa: &Zalsa) -> Vec<u8> {
+    let _span = crate::tracing::debug_span!("persist_memos").entered();
+
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    FORMAT_VERSION.encode(&mut buf);
+    schema_fingerprint(zalsa).encode(&mut buf);
+
+    let mut entry = Vec::new();
+    for (struct_index, function_index) in zalsa.memo_ingredients() {
+        let salsa_struct = zalsa.lookup_ingredient(struct_index);
+        let function = zalsa.lookup_ingredient(function_index);
+
+        let mut entries = Vec::new();
+        let mut count = 0usize;
+        for id in zalsa.table().ids_of(struct_index) {
+            let Some(key) = salsa_struct.persisted_key(zalsa, id) else {
+                continue;
+            };
+            entry.clear();
+            if function.persist_memo(zalsa, id, &mut entry) {
+                key.encode(&mut entries);
+                entry.encode(&mut entries);
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            continue;
+        }
+
+        function_index.encode(&mut buf);
+        struct_index.encode(&mut buf);
+        count.encode(&mut buf);
+        buf.extend_from_slice(&entries);
+    }
+
+    buf
+}
+
+/// Restores the memos serialized by [`persist_memos`] into `zalsa`.
+///
+/// Returns the number of restored memos.
+pub(crate) fn restore_memos(zalsa: &mut Zalsa, mut buf: &[u8]) -> Result<usize, PersistenceError> {
+    let _span = crate::tracing::debug_span!("restore_memos").entered();
+    let buf = &mut buf;
+
+    if buf.len() < MAGIC.len() || take(buf, MAGIC.len())? != MAGIC {
+        return Err(PersistenceError::UnsupportedFormat);
+    }
+    if u32::decode(buf)? != FORMAT_VERSION {
+        return Err(PersistenceError::UnsupportedFormat);
+    }
+    if u64::decode(buf)? != schema_fingerprint(zalsa) {
+        return Err(PersistenceError::SchemaMismatch);
+    }
+
+    let restored = {
+        let zalsa = &*zalsa;
+        let mut pending = Vec::new();
+
+        while !buf.is_empty() {
+            let function_index = IngredientIndex::decode(buf)?;
+            let struct_index = IngredientIndex::decode(buf)?;
+            if !zalsa
+                .memo_ingredients()
+                .any(|indices| indices == (struct_index, function_index))
+            {
+                return Err(PersistenceError::Corrupted);
+            }
+            let salsa_struct = zalsa.lookup_ingredient(struct_index);
+            let function = zalsa.lookup_ingredient(function_index);
+
+            let count = usize::decode(buf)?;
+            for _ in 0..count {
+                let key = PersistedKey::decode(buf)?;
+                let len = decode_len(buf)?;
+                let mut entry = take(buf, len)?;
+
+                // Skip entries whose key does not exist in this database, e.g. because fewer
+                // inputs were created than in the database the memos were persisted from, or
+                // whose key has a different value.
+                let Some(id) = salsa_struct.restore_key(zalsa, key) else {
+                    crate::tracing::debug!("skipping persisted memo for unknown key {key:?}");
+                    continue;
+                };
+
+                if let Some(memo) = function.restore_memo(zalsa, id, &mut entry)? {
+                    pending.push(memo);
+                }
+                if !entry.is_empty() {
+                    return Err(PersistenceError::Corrupted);
+                }
+            }
+        }
+
+        let restored = pending.len();
+        for memo in pending {
+            memo(zalsa);
+        }
+        restored
+    };
+
+    // Start a new revision in which every memo has to be deep verified, so that the restored
+    // memos are validated against their recorded dependencies before being reused.
+    zalsa.new_revision();
+    zalsa.runtime_mut().report_tracked_write(Durability::MAX);
+
+    Ok(restored)
+}
+
+/// Reads the dependency edges of a persisted memo, validating that all their keys exist.
+///
+/// Returns `Ok(None)` if one of the keys does not exist in this database.
+pub(crate) fn decode_edges(
+    zalsa: &Zalsa,
+    buf: &mut &[u8],
+) -> Result<Option<Vec<crate::zalsa_local::QueryEdge>>, PersistenceError> {
+    let len = decode_len(buf)?;
+    let mut edges = Vec::with_capacity(len);
+    let mut valid = true;
+    for _ in 0..len {
+        let ingredient_index = IngredientIndex::decode(buf)?;
+        let key_ingredient_index = IngredientIndex::decode(buf)?;
+        let key = PersistedKey::decode(buf)?;
+
+        let ingredients = zalsa.ingredients().len();
+        if ingredient_index.as_u32() as usize >= ingredients
+            || key_ingredient_index.as_u32() as usize >= ingredients
+        {
+            return Err(PersistenceError::Corrupted);
+        }
+        let Some(key) = zalsa
+            .lookup_ingredient(key_ingredient_index)
+            .restore_key(zalsa, key)
+        else {
+            valid = false;
+            continue;
+        };
+
+        edges.push(crate::zalsa_local::QueryEdge::input(
+            crate::DatabaseKeyIndex::new(ingredient_index, key),
+        ));
+    }
+    Ok(valid.then_some(edges))
+}
+
+/// Writes the dependency edges of a memo.
+///
+/// Returns `false`, leaving `buf` in an unspecified state, if one of the edges cannot be
+/// persisted.
+pub(crate) fn encode_edges(
+    zalsa: &Zalsa,
+    edges: &[crate::zalsa_local::QueryEdge],
+    buf: &mut Vec<u8>,
+) -> bool {
+    encode_len(edges.len(), buf);
+    for edge in edges {
+        let crate::zalsa_local::QueryEdgeKind::Input(key) = edge.kind() else {
+            return false;
+        };
+        let key_ingredient_index = zalsa.ingredient_index(key.key_index());
+        let Some(persisted_key) = zalsa
+            .lookup_ingredient(key_ingredient_index)
+            .persisted_key(zalsa, key.key_index())
+        else {
+            return false;
+        };
+
+        key.ingredient_index().encode(buf);
+        key_ingredient_index.encode(buf);
+        persisted_key.encode(buf);
+    }
+    true
+}
@@ -0,0 +1,78 @@
+//! Warming many queries in parallel, see [`Database::prefetch`](crate::Database::prefetch).
+
+use std::panic::AssertUnwindSafe;
+
+use crate::run_async::{try_without_blocking, Blocking};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::Mutex;
+use crate::{Cancelled, Database};
+
+/// The number of operations of [`Database::prefetch`](crate::Database::prefetch) by outcome.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Prefetched {
+    completed: usize,
+    skipped: usize,
+    cancelled: usize,
+}
+
+impl Prefetched {
+    /// Returns how many operations completed.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// Returns how many operations were skipped because the first query they claimed was
+    /// already executing with another handle.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Returns how many operations were cancelled, e.g. because of a pending write or the
+    /// [`CancellationToken`](crate::CancellationToken) of the handle.
+    pub fn cancelled(&self) -> usize {
+        self.cancelled
+    }
+}
+
+pub(crate) fn prefetch<Db, T>(
+    db: &Db,
+    keys: impl IntoIterator<Item = T>,
+    op: impl Fn(&Db, T) + Sync,
+) -> Prefetched
+where
+    Db: Database,
+    T: Send,
+{
+    // The forks are cast to the concrete type of the database, which has no view otherwise.
+    db.zalsa().views().add::<Db, Db>(|db| db);
+
+    let keys = keys
+        .into_iter()
+        .map(|key| Mutex::new(Some(key)))
+        .collect::<Vec<_>>();
+    let completed = AtomicUsize::new(0);
+    let skipped = AtomicUsize::new(0);
+    let cancelled = AtomicUsize::new(0);
+    crate::parallel::for_each_fork(db, keys.len(), |db, index| {
+        let key = keys[index].lock().take().unwrap();
+        let result = Cancelled::catch(AssertUnwindSafe(|| {
+            try_without_blocking(Blocking::AfterFirstClaim, || {
+                // Operations started after the cancellation do not execute any query.
+                db.unwind_if_revision_cancelled();
+                op(db, key);
+            })
+        }));
+        let outcome = match result {
+            Ok(Some(())) => &completed,
+            Ok(None) => &skipped,
+            Err(_) => &cancelled,
+        };
+        outcome.fetch_add(1, Ordering::Relaxed);
+    });
+
+    Prefetched {
+        completed: completed.load(Ordering::Relaxed),
+        skipped: skipped.load(Ordering::Relaxed),
+        cancelled: cancelled.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,251 @@
+//! Built-in profiling of tracked functions, see [`Database::set_profiling`](crate::Database::set_profiling).
+
+use std::time::{Duration, Instant};
+
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::zalsa::Zalsa;
+use crate::zalsa_local::ZalsaLocal;
+use crate::Database;
+
+impl dyn Database {
+    /// Returns the profiles of all tracked functions that were used since profiling was
+    /// enabled or the profiles were [reset](`Self::reset_query_profiles`).
+    pub fn query_profiles(&self) -> Vec<QueryProfile> {
+        self.zalsa()
+            .ingredients()
+            .filter_map(|ingredient| {
+                let profile = ingredient.profile_counters()?.load(ingredient.debug_name());
+                (profile.executions + profile.shallow_hits + profile.deep_verifications > 0)
+                    .then_some(profile)
+            })
+            .collect()
+    }
+
+    /// Resets the profiles of all tracked functions.
+    pub fn reset_query_profiles(&self) {
+        for ingredient in self.zalsa().ingredients() {
+            if let Some(counters) = ingredient.profile_counters() {
+                counters.reset();
+            }
+        }
+    }
+}
+
+/// The profile of a tracked function, see `dyn Database::query_profiles`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryProfile {
+    debug_name: &'static str,
+    executions: u64,
+    total_time: Duration,
+    self_time: Duration,
+    shallow_hits: u64,
+    deep_verifications: u64,
+    deep_hits: u64,
+    cycle_iterations: u64,
+    blocked: u64,
+    blocked_time: Duration,
+}
+
+impl QueryProfile {
+    /// Returns the debug name of the function.
+    pub fn debug_name(&self) -> &'static str {
+        self.debug_name
+    }
+
+    /// Returns how often the function was executed.
+    pub fn executions(&self) -> u64 {
+        self.executions
+    }
+
+    /// Returns the time spent executing the function, including the functions it called.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Returns the time spent executing the function, excluding the functions it executed
+    /// and the time it was blocked on other threads.
+    pub fn self_time(&self) -> Duration {
+        self.self_time
+    }
+
+    /// Returns how often a memoized value was reused without walking its dependencies.
+    pub fn shallow_hits(&self) -> u64 {
+        self.shallow_hits
+    }
+
+    /// Returns how often the dependencies of a memoized value were walked to verify it.
+    pub fn deep_verifications(&self) -> u64 {
+        self.deep_verifications
+    }
+
+    /// Returns how often a memoized value was reused after walking its dependencies.
+    pub fn deep_hits(&self) -> u64 {
+        self.deep_hits
+    }
+
+    /// Returns how often a memoized value was reused, i.e. the shallow and deep hits.
+    pub fn hits(&self) -> u64 {
+        self.shallow_hits + self.deep_hits
+    }
+
+    /// Returns how many additional fixpoint iterations were run with the function as cycle head.
+    pub fn cycle_iterations(&self) -> u64 {
+        self.cycle_iterations
+    }
+
+    /// Returns how often the function blocked on another thread computing the same key.
+    pub fn blocked(&self) -> u64 {
+        self.blocked
+    }
+
+    /// Returns the time spent blocked on other threads computing the same key.
+    pub fn blocked_time(&self) -> Duration {
+        self.blocked_time
+    }
+}
+
+/// The profiling counters of a tracked function.
+///
+/// The counters are only updated while profiling is enabled.
+#[derive(Default)]
+pub struct ProfileCounters {
+    executions: AtomicU64,
+    total_nanos: AtomicU64,
+    self_nanos: AtomicU64,
+    shallow_hits: AtomicU64,
+    deep_verifications: AtomicU64,
+    deep_hits: AtomicU64,
+    cycle_iterations: AtomicU64,
+    blocked: AtomicU64,
+    blocked_nanos: AtomicU64,
+}
+
+impl ProfileCounters {
+    #[inline]
+    pub(crate) fn record_shallow_hit(&self, zalsa: &Zalsa) {
+        if zalsa.is_profiling() {
+            self.shallow_hits.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn record_deep_verification(&self, zalsa: &Zalsa, hit: bool) {
+        if zalsa.is_profiling() {
+            self.deep_verifications.fetch_add(1, Ordering::Relaxed);
+            if hit {
+                self.deep_hits.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn record_cycle_iteration(&self, zalsa: &Zalsa) {
+        if zalsa.is_profiling() {
+            self.cycle_iterations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Runs `block_on`, recording the time spent in it as blocked.
+    ///
+    /// The time is excluded from the self time of the executing query.
+    #[inline]
+    pub(crate) fn record_blocked<R>(
+        &self,
+        zalsa: &Zalsa,
+        zalsa_local: &ZalsaLocal,
+        block_on: impl FnOnce() -> R,
+    ) -> R {
+        if !zalsa.is_profiling() {
+            return block_on();
+        }
+
+        let start = Instant::now();
+        let result = block_on();
+        let elapsed = start.elapsed();
+        self.blocked.fetch_add(1, Ordering::Relaxed);
+        self.blocked_nanos
+            .fetch_add(as_nanos(elapsed), Ordering::Relaxed);
+        zalsa_local.report_profiled_child_time(elapsed);
+        result
+    }
+
+    /// Starts timing an execution, which ends when the returned timer is dropped.
+    #[inline]
+    pub(crate) fn start_execution<'a>(
+        &'a self,
+        zalsa: &Zalsa,
+        zalsa_local: &'a ZalsaLocal,
+    ) -> Option<ExecutionTimer<'a>> {
+        if !zalsa.is_profiling() {
+            return None;
+        }
+
+        zalsa_local.push_profile_frame();
+        Some(ExecutionTimer {
+            counters: self,
+            zalsa_local,
+            start: Instant::now(),
+        })
+    }
+
+    fn load(&self, debug_name: &'static str) -> QueryProfile {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        QueryProfile {
+            debug_name,
+            executions: load(&self.executions),
+            total_time: Duration::from_nanos(load(&self.total_nanos)),
+            self_time: Duration::from_nanos(load(&self.self_nanos)),
+            shallow_hits: load(&self.shallow_hits),
+            deep_verifications: load(&self.deep_verifications),
+            deep_hits: load(&self.deep_hits),
+            cycle_iterations: load(&self.cycle_iterations),
+            blocked: load(&self.blocked),
+            blocked_time: Duration::from_nanos(load(&self.blocked_nanos)),
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.executions,
+            &self.total_nanos,
+            &self.self_nanos,
+            &self.shallow_hits,
+            &self.deep_verifications,
+            &self.deep_hits,
+            &self.cycle_iterations,
+            &self.blocked,
+            &self.blocked_nanos,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Times an execution of a tracked function, see [`ProfileCounters::start_execution`].
+pub(crate) struct ExecutionTimer<'a> {
+    counters: &'a ProfileCounters,
+    zalsa_local: &'a ZalsaLocal,
+    start: Instant,
+}
+
+impl Drop for ExecutionTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let child_time = self.zalsa_local.pop_profile_frame();
+        self.zalsa_local.report_profiled_child_time(elapsed);
+
+        let counters = self.counters;
+        counters.executions.fetch_add(1, Ordering::Relaxed);
+        counters
+            .total_nanos
+            .fetch_add(as_nanos(elapsed), Ordering::Relaxed);
+        counters.self_nanos.fetch_add(
+            as_nanos(elapsed.saturating_sub(child_time)),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,179 @@
+//! Running queries as futures, see [`Database::run_async`](crate::Database::run_async).
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use crate::{Cancelled, Database, SalsaError};
+
+crate::sync::thread_local! {
+    /// The waker of the [`RunAsync`] future that is being polled on this thread, if any.
+    static WAKER: RefCell<Option<Waker>> = const { RefCell::new(None) };
+
+    /// Whether the queries claimed by this thread may block on other executions.
+    static BLOCKING: Cell<Blocking> = const { Cell::new(Blocking::Allowed) };
+}
+
+/// The number of threads executing [`try_without_blocking`] with
+/// [`Blocking::AfterFirstClaim`], which are the only ones for which [`claimed`] has to update
+/// [`BLOCKING`]. This keeps the thread-local out of the claims of all other threads.
+static AWAITING_FIRST_CLAIM: AtomicUsize = AtomicUsize::new(0);
+
+/// The panic payload used to unwind the queries of a [`RunAsync`] future that has to wait
+/// for a query executing on another thread.
+struct Yield;
+
+/// Returns the waker of the [`RunAsync`] future that is being polled on this thread.
+///
+/// If this returns `Some`, the caller must not block the thread. Instead, it arranges for
+/// the waker to be woken and calls [`yield_now`].
+pub(crate) fn current_waker() -> Option<Waker> {
+    WAKER.with(|waker| waker.borrow().clone())
+}
+
+/// Unwinds the queries of the [`RunAsync`] future that is being polled on this thread,
+/// which then returns [`Poll::Pending`].
+///
+/// The queries are unwound like by a cancellation that only applies to the handle of the
+/// future, so threads blocked on them execute them themselves.
+pub(crate) fn yield_now() -> ! {
+    Cancelled::unwind_handle(Box::new(Yield))
+}
+
+/// Whether a query claimed by another execution blocks the thread, see
+/// [`try_without_blocking`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Blocking {
+    /// The thread blocks until the other execution completes.
+    Allowed,
+
+    /// The first query claimed by the thread must not block, the later ones may.
+    AfterFirstClaim,
+
+    /// No query claimed by the thread may block.
+    Never,
+}
+
+/// Executes `op` without blocking the thread as specified by `blocking`, returning `None`
+/// if a query it fetches or verifies is claimed by another execution, including an
+/// execution on this thread.
+///
+/// Like for a [`RunAsync`] future, `op` is unwound in that case.
+pub(crate) fn try_without_blocking<R>(blocking: Blocking, op: impl FnOnce() -> R) -> Option<R> {
+    let awaits_first_claim = blocking == Blocking::AfterFirstClaim;
+    if awaits_first_claim {
+        AWAITING_FIRST_CLAIM.fetch_add(1, Ordering::Relaxed);
+    }
+    let previous = BLOCKING.with(|cell| cell.replace(blocking));
+    let result = crate::cancelled::catch_unwind(AssertUnwindSafe(op));
+    BLOCKING.with(|cell| cell.set(previous));
+    if awaits_first_claim {
+        AWAITING_FIRST_CLAIM.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    match result {
+        Ok(result) => Some(result),
+        Err(payload) if payload.is::<Yield>() => None,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+/// Returns `true` if this thread executes [`try_without_blocking`] and may still give up
+/// on a query claimed by another execution.
+pub(crate) fn is_non_blocking() -> bool {
+    BLOCKING.with(Cell::get) != Blocking::Allowed
+}
+
+/// Called when a query this thread tries to claim is claimed by another execution, returning
+/// `false` if it must call [`yield_now`] instead of blocking.
+pub(crate) fn claim_may_block() -> bool {
+    BLOCKING.with(|cell| match cell.get() {
+        Blocking::Allowed => true,
+        Blocking::AfterFirstClaim => {
+            cell.set(Blocking::Allowed);
+            false
+        }
+        Blocking::Never => false,
+    })
+}
+
+/// Called when this thread claimed a query, after which later claims may block if only the
+/// first one must not.
+#[inline]
+pub(crate) fn claimed() {
+    // A thread only awaits its first claim within `try_without_blocking`, which incremented
+    // the counter before, so its own increment is always visible here.
+    if AWAITING_FIRST_CLAIM.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    BLOCKING.with(|cell| {
+        if cell.get() == Blocking::AfterFirstClaim {
+            cell.set(Blocking::Allowed);
+        }
+    });
+}
+
+/// Future returned by [`Database::run_async`](crate::Database::run_async).
+///
+/// The future retries the operation on contention, it does not suspend it: polling the future
+/// executes the operation synchronously. If a query it fetches is executing on another thread,
+/// the operation is unwound and the future returns [`Poll::Pending`] instead of blocking the
+/// thread. The future is woken once the other thread completes the query or the
+/// [`CancellationToken`](crate::CancellationToken) of the handle is cancelled, and the next
+/// poll executes the operation again from the start.
+///
+/// Only the queries that completed before the operation was unwound are memoized and not
+/// executed again, everything else the operation did is lost. A query that is unwound this
+/// way behaves like a query cancelled by the cancellation token of the handle, i.e. threads
+/// blocked on it execute it themselves.
+///
+/// Dropping the future in between polls cancels the operation without further cleanup,
+/// as it never holds on to a query while it is pending.
+#[must_use = "futures do nothing unless polled"]
+pub struct RunAsync<Db, F> {
+    db: Db,
+    op: F,
+}
+
+impl<Db, F> RunAsync<Db, F> {
+    pub(crate) fn new(db: Db, op: F) -> Self {
+        Self { db, op }
+    }
+}
+
+// The fields are never pinned.
+impl<Db, F> Unpin for RunAsync<Db, F> {}
+
+impl<Db, F, R> Future for RunAsync<Db, F>
+where
+    Db: Database,
+    F: FnMut(&Db) -> R,
+{
+    type Output = Result<R, SalsaError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Self { db, op } = self.get_mut();
+
+        let previous = WAKER.with(|waker| waker.replace(Some(cx.waker().clone())));
+        // This also resets the flag set by `yield_now`, so that later panics of this thread
+        // are not mistaken for a cancellation of its handle.
+        let result =
+            crate::cancelled::catch_unwind(AssertUnwindSafe(|| SalsaError::catch(|| op(db))));
+        WAKER.with(|waker| *waker.borrow_mut() = previous);
+
+        match result {
+            Ok(result) => Poll::Ready(result),
+            Err(payload) if payload.is::<Yield>() => {
+                if let Some(token) = db.zalsa_local().cancellation_token() {
+                    token.register_waker(cx.waker());
+                }
+                Poll::Pending
+            }
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
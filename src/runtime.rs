@@ -1,21 +1,29 @@
-use self::dependency_graph::DependencyGraph;
+use std::task::Waker;
+
+use self::dependency_graph::{DependencyGraph, Watchdog};
 use crate::durability::Durability;
+use crate::event::EventSubscribers;
 use crate::function::SyncGuard;
 use crate::key::DatabaseKeyIndex;
-use crate::sync::atomic::{AtomicBool, Ordering};
+use crate::sync::atomic::{AtomicUsize, Ordering};
 use crate::sync::thread::{self, ThreadId};
 use crate::sync::Mutex;
 use crate::table::Table;
+use crate::wait_graph::WaitGraph;
 use crate::zalsa::Zalsa;
-use crate::{Cancelled, Event, EventKind, Revision};
+use crate::{Event, EventKind, EventKinds, Revision, SalsaError};
 
 mod dependency_graph;
 
 pub struct Runtime {
-    /// Set to true when the current revision has been canceled.
+    /// Non-zero when the current revision has been canceled.
     /// This is done when we an input is being changed. The flag
-    /// is set back to false once the input has been changed.
-    revision_canceled: AtomicBool,
+    /// is set back to zero once the input has been changed.
+    ///
+    /// Counts the writers waiting for the other handles, so that a writer
+    /// that gives up (see [`Self::unset_cancellation_flag`]) doesn't clear
+    /// the flag while other writers are still waiting.
+    revision_canceled: AtomicUsize,
 
     /// Stores the "last change" revision for values of each duration.
     /// This vector is always of length at least 1 (for Durability 0)
@@ -26,7 +34,7 @@ pub struct Runtime {
     /// revisions[i + 1]`, for all `i`. This is because when you
     /// modify a value with durability D, that implies that values
     /// with durability less than D may have changed too.
-    revisions: [Revision; Durability::LEN],
+    revisions: [Revision; Durability::LEVELS],
 
     /// The dependency graph tracks which runtimes are blocked on one
     /// another, waiting for queries to terminate.
@@ -34,12 +42,18 @@ pub struct Runtime {
 
     /// Data for instances
     table: Table,
+
+    /// The subscribers to [`Event`]s, see `StorageBuilder::event_subscriber`.
+    event_subscribers: EventSubscribers,
 }
 
 #[derive(Copy, Clone, Debug)]
 pub(super) enum WaitResult {
     Completed,
     Panicked,
+    /// The query was unwound by a cancellation that only applies to the handle that
+    /// executed it, so the blocked thread can execute the query itself.
+    Cancelled,
 }
 
 #[derive(Debug)]
@@ -70,7 +84,9 @@ impl Running<'_> {
     }
 
     /// Blocks on the other thread to complete the computation.
-    pub(crate) fn block_on(self, zalsa: &Zalsa) {
+    ///
+    /// Fails with [`SalsaError::PropagatedPanic`] if the other thread panicked.
+    pub(crate) fn block_on(self, zalsa: &Zalsa) -> Result<(), SalsaError> {
         let BlockedOnInner {
             dg,
             query_mutex_guard,
@@ -79,7 +95,21 @@ impl Running<'_> {
             thread_id,
         } = *self.0;
 
-        zalsa.event(&|| {
+        if let Some(waker) = crate::run_async::current_waker() {
+            // Register the waker before releasing the lock on the query's state,
+            // so that it cannot complete without waking the future.
+            let mut dg = dg;
+            dg.add_waker(database_key, waker);
+            drop(query_mutex_guard);
+            drop(dg);
+
+            crate::tracing::debug!(
+                "block_on: thread {thread_id:?} yields on {database_key:?} in thread {other_id:?}",
+            );
+            crate::run_async::yield_now();
+        }
+
+        zalsa.event_of_kind(EventKinds::WILL_BLOCK_ON, &|| {
             Event::new(EventKind::WillBlockOn {
                 other_thread_id: other_id,
                 database_key,
@@ -90,17 +120,48 @@ impl Running<'_> {
             "block_on: thread {thread_id:?} is blocking on {database_key:?} in thread {other_id:?}",
         );
 
-        let result =
-            DependencyGraph::block_on(dg, thread_id, database_key, other_id, query_mutex_guard);
+        // The query stack is only captured for the watchdog, as blocking is common and
+        // its diagnostics are opt-in. The stack is only reachable through the attached
+        // database, as callers blocking on cycle heads don't have access to the local state.
+        let watchdog = zalsa.blocking_watchdog().map(|timeout| Watchdog {
+            query_stack: crate::attach::with_attached_database(|db| {
+                db.zalsa_local().try_with_query_stack(|stack| {
+                    stack
+                        .iter()
+                        .rev()
+                        .map(|query| query.database_key_index)
+                        .collect()
+                })
+            })
+            .flatten()
+            .unwrap_or_default(),
+            timeout,
+        });
+
+        let result = DependencyGraph::block_on(
+            dg,
+            thread_id,
+            database_key,
+            other_id,
+            query_mutex_guard,
+            watchdog,
+        );
+
+        zalsa.event_of_kind(EventKinds::DID_BLOCK_ON, &|| {
+            Event::new(EventKind::DidBlockOn {
+                other_thread_id: other_id,
+                database_key,
+            })
+        });
 
         match result {
             WaitResult::Panicked => {
                 // If the other thread panicked, then we consider this thread
                 // cancelled. The assumption is that the panic will be detected
                 // by the other thread and responded to appropriately.
-                Cancelled::PropagatedPanic.throw()
+                Err(SalsaError::PropagatedPanic)
             }
-            WaitResult::Completed => {}
+            WaitResult::Completed | WaitResult::Cancelled => Ok(()),
         }
     }
 }
@@ -130,12 +191,7 @@ pub fn stamp(revision: Revision, durability: Durability) -> Stamp {
 
 impl Default for Runtime {
     fn default() -> Self {
-        Runtime {
-            revisions: [Revision::start(); Durability::LEN],
-            revision_canceled: Default::default(),
-            dependency_graph: Default::default(),
-            table: Default::default(),
-        }
+        Self::new(EventSubscribers::default())
     }
 }
 
@@ -150,6 +206,40 @@ impl std::fmt::Debug for Runtime {
 }
 
 impl Runtime {
+    pub(crate) fn new(event_subscribers: EventSubscribers) -> Self {
+        Runtime {
+            revisions: [Revision::start(); Durability::LEVELS],
+            revision_canceled: Default::default(),
+            dependency_graph: Default::default(),
+            table: Default::default(),
+            event_subscribers,
+        }
+    }
+
+    /// Reports an event of the given kind, which is only constructed if there
+    /// is a subscriber interested in it.
+    #[inline(always)]
+    pub(crate) fn event(&self, kind: EventKinds, event: &dyn Fn() -> Event) {
+        self.event_subscribers.publish(kind, event);
+    }
+
+    /// Reports an event whose kind is only known once it is constructed.
+    #[inline(always)]
+    pub(crate) fn event_any(&self, event: &dyn Fn() -> Event) {
+        self.event_subscribers.publish_any(event);
+    }
+
+    /// Returns a snapshot of the threads blocked on other threads, see `dyn Database::wait_graph`.
+    pub(crate) fn wait_graph(&self) -> WaitGraph {
+        self.dependency_graph.lock().wait_graph()
+    }
+
+    /// Returns `true` if there is a subscriber interested in any of `kinds`.
+    #[inline(always)]
+    pub(crate) fn is_subscribed(&self, kinds: EventKinds) -> bool {
+        self.event_subscribers.is_subscribed(kinds)
+    }
+
     #[inline]
     pub(crate) fn current_revision(&self) -> Revision {
         self.revisions[0]
@@ -176,16 +266,23 @@ impl Runtime {
     }
 
     pub(crate) fn load_cancellation_flag(&self) -> bool {
-        self.revision_canceled.load(Ordering::Acquire)
+        self.revision_canceled.load(Ordering::Acquire) != 0
     }
 
     pub(crate) fn set_cancellation_flag(&self) {
         crate::tracing::trace!("set_cancellation_flag");
-        self.revision_canceled.store(true, Ordering::Release);
+        self.revision_canceled.fetch_add(1, Ordering::Release);
     }
 
     pub(crate) fn reset_cancellation_flag(&mut self) {
-        *self.revision_canceled.get_mut() = false;
+        *self.revision_canceled.get_mut() = 0;
+    }
+
+    /// Undoes a [`Self::set_cancellation_flag`] of a writer that gave up while other handles
+    /// are still alive. The flag stays set if other writers are still waiting.
+    pub(crate) fn unset_cancellation_flag(&self) {
+        crate::tracing::trace!("unset_cancellation_flag");
+        self.revision_canceled.fetch_sub(1, Ordering::Release);
     }
 
     /// Returns the [`Table`] used to store the value of salsa structs
@@ -259,8 +356,11 @@ impl Runtime {
         database_key: DatabaseKeyIndex,
         wait_result: WaitResult,
     ) {
-        self.dependency_graph
+        let wakers = self
+            .dependency_graph
             .lock()
             .unblock_runtimes_blocked_on(database_key, wait_result);
+        // Wake the futures outside of the lock, in case the executor polls them right away.
+        wakers.into_iter().for_each(Waker::wake);
     }
 }
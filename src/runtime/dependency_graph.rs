@@ -1,4 +1,6 @@
 use std::pin::Pin;
+use std::task::Waker;
+use std::time::{Duration, Instant};
 
 use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
@@ -8,6 +10,7 @@ use crate::runtime::dependency_graph::edge::EdgeCondvar;
 use crate::runtime::WaitResult;
 use crate::sync::thread::ThreadId;
 use crate::sync::MutexGuard;
+use crate::wait_graph::{BlockedThread, WaitGraph};
 
 #[derive(Debug, Default)]
 pub(super) struct DependencyGraph {
@@ -25,6 +28,20 @@ pub(super) struct DependencyGraph {
     /// it stores its `WaitResult` here. As they wake up, each query Q in Qs will
     /// come here to fetch their results.
     wait_results: FxHashMap<ThreadId, WaitResult>,
+
+    /// The wakers of the [`RunAsync`](crate::RunAsync) futures that yielded because a given
+    /// query was executing, woken once it completes.
+    wakers: FxHashMap<DatabaseKeyIndex, SmallVec<[Waker; 1]>>,
+}
+
+/// The blocking watchdog of a thread that blocks on another thread,
+/// see [`crate::Database::set_blocking_watchdog`].
+pub(super) struct Watchdog {
+    /// The query stack of the blocked thread, which is reported in the [wait graph](DependencyGraph::wait_graph).
+    pub(super) query_stack: Box<[DatabaseKeyIndex]>,
+
+    /// How long the thread may be blocked before the wait graph is logged.
+    pub(super) timeout: Duration,
 }
 
 impl DependencyGraph {
@@ -56,19 +73,42 @@ impl DependencyGraph {
     /// * No path from `to_id` to `from_id`
     ///   (i.e., `me.depends_on(to_id, from_id)` is false)
     /// * `held_mutex` is a read lock (or stronger) on `database_key`
+    ///
+    /// If `watchdog` is set, the wait graph is logged once `from_id` has been blocked
+    /// for longer than the watchdog's timeout.
     pub(super) fn block_on<QueryMutexGuard>(
         mut me: MutexGuard<'_, Self>,
         from_id: ThreadId,
         database_key: DatabaseKeyIndex,
         to_id: ThreadId,
         query_mutex_guard: QueryMutexGuard,
+        watchdog: Option<Watchdog>,
     ) -> WaitResult {
         let cvar = std::pin::pin!(EdgeCondvar::default());
         let cvar = cvar.as_ref();
+        let (query_stack, blocked_since, mut deadline) = match watchdog {
+            Some(Watchdog {
+                query_stack,
+                timeout,
+            }) => {
+                let now = Instant::now();
+                (query_stack, Some(now), Some((now + timeout, timeout)))
+            }
+            None => (Box::default(), None, None),
+        };
         // SAFETY: We are blocking until the result is removed from `DependencyGraph::wait_results`
         // at which point the `edge` won't signal the condvar anymore.
         // As such we are keeping the cond var alive until the reference in the edge drops.
-        unsafe { me.add_edge(from_id, database_key, to_id, cvar) };
+        unsafe {
+            me.add_edge(
+                from_id,
+                database_key,
+                to_id,
+                query_stack,
+                blocked_since,
+                cvar,
+            )
+        };
 
         // Release the mutex that prevents `database_key`
         // from completing, now that the edge has been added.
@@ -79,10 +119,42 @@ impl DependencyGraph {
                 debug_assert!(!me.edges.contains_key(&from_id));
                 return result;
             }
-            me = cvar.wait(me);
+            match deadline {
+                Some((instant, timeout)) => {
+                    let timed_out;
+                    (me, timed_out) = cvar.wait_until(me, instant);
+                    if timed_out && !me.wait_results.contains_key(&from_id) {
+                        crate::tracing::event!(
+                            WARN,
+                            "{from_id:?} has been blocked on {database_key:?} for more than {timeout:?}, \
+                            this may be a deadlock:\n{}",
+                            me.wait_graph()
+                        );
+                        deadline = None;
+                    }
+                }
+                None => me = cvar.wait(me),
+            }
         }
     }
 
+    /// Returns a snapshot of the blocked threads, longest blocked first.
+    pub(super) fn wait_graph(&self) -> WaitGraph {
+        let mut blocked_threads = self
+            .edges
+            .iter()
+            .map(|(&thread_id, edge)| BlockedThread {
+                thread_id,
+                other_thread_id: edge.blocked_on_id,
+                database_key: edge.database_key,
+                query_stack: edge.query_stack.clone(),
+                blocked_for: edge.blocked_since.map(|since| since.elapsed()),
+            })
+            .collect::<Vec<_>>();
+        blocked_threads.sort_by_key(|thread| std::cmp::Reverse(thread.blocked_for));
+        WaitGraph { blocked_threads }
+    }
+
     /// Helper for `block_on`: performs actual graph modification
     /// to add a dependency edge from `from_id` to `to_id`, which is
     /// computing `database_key`.
@@ -96,13 +168,16 @@ impl DependencyGraph {
         from_id: ThreadId,
         database_key: DatabaseKeyIndex,
         to_id: ThreadId,
+        query_stack: Box<[DatabaseKeyIndex]>,
+        blocked_since: Option<Instant>,
         cvar: Pin<&EdgeCondvar>,
     ) {
         assert_ne!(from_id, to_id);
         debug_assert!(!self.edges.contains_key(&from_id));
         debug_assert!(!self.depends_on(to_id, from_id));
         // SAFETY: The caller is responsible for ensuring that the `EdgeGuard` outlives the `Edge`.
-        let edge = unsafe { edge::Edge::new(to_id, cvar) };
+        let edge =
+            unsafe { edge::Edge::new(to_id, database_key, query_stack, blocked_since, cvar) };
         self.edges.insert(from_id, edge);
         self.query_dependents
             .entry(database_key)
@@ -110,13 +185,28 @@ impl DependencyGraph {
             .push(from_id);
     }
 
+    /// Registers `waker` to be woken once `database_key` completes.
+    ///
+    /// Like for [`Self::block_on`], the lock on the results table for `database_key`
+    /// must be held.
+    pub(super) fn add_waker(&mut self, database_key: DatabaseKeyIndex, waker: Waker) {
+        let wakers = self.wakers.entry(database_key).or_default();
+        if !wakers.iter().any(|registered| registered.will_wake(&waker)) {
+            wakers.push(waker);
+        }
+    }
+
     /// Invoked when runtime `to_id` completes executing
     /// `database_key`.
+    ///
+    /// Returns the wakers of the futures waiting for `database_key`, which the caller
+    /// wakes once it released the lock.
+    #[must_use]
     pub(super) fn unblock_runtimes_blocked_on(
         &mut self,
         database_key: DatabaseKeyIndex,
         wait_result: WaitResult,
-    ) {
+    ) -> SmallVec<[Waker; 1]> {
         let dependents = self
             .query_dependents
             .remove(&database_key)
@@ -125,6 +215,8 @@ impl DependencyGraph {
         for from_id in dependents {
             self.unblock_runtime(from_id, wait_result);
         }
+
+        self.wakers.remove(&database_key).unwrap_or_default()
     }
 
     /// Unblock the runtime with the given id with the given wait-result.
@@ -141,10 +233,12 @@ impl DependencyGraph {
 }
 
 mod edge {
+    use crate::key::DatabaseKeyIndex;
     use crate::sync::thread::ThreadId;
     use crate::sync::{Condvar, MutexGuard};
 
     use std::pin::Pin;
+    use std::time::Instant;
 
     #[derive(Default, Debug)]
     pub(super) struct EdgeCondvar {
@@ -157,12 +251,33 @@ mod edge {
         pub(super) fn wait<'a, T>(&self, mutex_guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
             self.condvar.wait(mutex_guard)
         }
+
+        /// Waits until notified or `deadline` is reached, returning `true` if the wait timed out.
+        #[inline]
+        pub(super) fn wait_until<'a, T>(
+            &self,
+            mutex_guard: MutexGuard<'a, T>,
+            deadline: Instant,
+        ) -> (MutexGuard<'a, T>, bool) {
+            self.condvar.wait_until(mutex_guard, deadline)
+        }
     }
 
     #[derive(Debug)]
     pub(super) struct Edge {
         pub(super) blocked_on_id: ThreadId,
 
+        /// The query the blocked thread is waiting for.
+        pub(super) database_key: DatabaseKeyIndex,
+
+        /// The query stack of the blocked thread, starting with the innermost query.
+        ///
+        /// Only captured while the blocking watchdog is enabled, empty otherwise.
+        pub(super) query_stack: Box<[DatabaseKeyIndex]>,
+
+        /// Only captured while the blocking watchdog is enabled.
+        pub(super) blocked_since: Option<Instant>,
+
         /// Signalled whenever a query with dependents completes.
         /// Allows those dependents to check if they are ready to unblock.
         // condvar: unsafe<'stack_frame> Pin<&'stack_frame Condvar>,
@@ -173,9 +288,18 @@ mod edge {
         /// # SAFETY
         ///
         /// The caller must ensure that the [`EdgeCondvar`] is kept alive until the [`Edge`] is dropped.
-        pub(super) unsafe fn new(blocked_on_id: ThreadId, condvar: Pin<&EdgeCondvar>) -> Self {
+        pub(super) unsafe fn new(
+            blocked_on_id: ThreadId,
+            database_key: DatabaseKeyIndex,
+            query_stack: Box<[DatabaseKeyIndex]>,
+            blocked_since: Option<Instant>,
+            condvar: Pin<&EdgeCondvar>,
+        ) -> Self {
             Self {
                 blocked_on_id,
+                database_key,
+                query_stack,
+                blocked_since,
                 // SAFETY: The caller is responsible for ensuring that the `EdgeCondvar` outlives the `Edge`.
                 condvar: unsafe {
                     std::mem::transmute::<Pin<&EdgeCondvar>, Pin<&'static EdgeCondvar>>(condvar)
@@ -0,0 +1,127 @@
+//! Fallible execution of queries, see [`Database::try_run`](crate::Database::try_run) and the
+//! `try_fetch` functions generated for tracked functions.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::key::DatabaseKeyIndex;
+use crate::Cancelled;
+
+/// Error returned when a query could not produce a value, e.g. by the `try_fetch` function
+/// generated for a tracked function or by [`Database::try_run`](crate::Database::try_run).
+///
+/// Calling a tracked function directly raises these errors as panics instead.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum SalsaError {
+    /// A query was cancelled, e.g. because of a pending write.
+    ///
+    /// Never contains [`Cancelled::PropagatedPanic`], which is reported as
+    /// [`SalsaError::PropagatedPanic`] instead.
+    Cancelled(Cancelled),
+
+    /// A query without cycle recovery was part of a cycle, or the fixpoint iteration of a
+    /// cycle did not converge.
+    #[non_exhaustive]
+    Cycle {
+        /// The query at which the cycle was detected.
+        database_key: DatabaseKeyIndex,
+
+        /// Describes the cycle, including the query stack of the thread that detected it.
+        message: String,
+    },
+
+    /// A query was blocked on another thread, and that thread panicked.
+    #[non_exhaustive]
+    PropagatedPanic,
+}
+
+crate::sync::thread_local! {
+    /// The last cycle this thread panicked with, see [`SalsaError::throw`].
+    static CYCLE_PANIC: RefCell<Option<(DatabaseKeyIndex, String)>> = const { RefCell::new(None) };
+}
+
+impl SalsaError {
+    /// Creates the error for an unrecoverable cycle at `database_key`.
+    #[cold]
+    pub(crate) fn cycle(database_key: DatabaseKeyIndex, message: String) -> SalsaError {
+        SalsaError::Cycle {
+            database_key,
+            message,
+        }
+    }
+
+    /// Raises the error as the panic salsa raises when a tracked function is called directly.
+    ///
+    /// Cycles panic with their message, which [`SalsaError::catch`] recognizes as long as
+    /// the panic is caught on this thread.
+    #[cold]
+    pub(crate) fn throw(self) -> ! {
+        match self {
+            SalsaError::Cancelled(cancelled) => cancelled.throw(),
+            SalsaError::PropagatedPanic => Cancelled::PropagatedPanic.throw(),
+            SalsaError::Cycle {
+                database_key,
+                message,
+            } => {
+                CYCLE_PANIC
+                    .with(|cycle| *cycle.borrow_mut() = Some((database_key, message.clone())));
+                panic!("{message}")
+            }
+        }
+    }
+
+    /// Runs `f`, and catches any salsa cancellation and cycle panic.
+    ///
+    /// Other panics are resumed.
+    pub(crate) fn catch<T>(f: impl FnOnce() -> T) -> Result<T, SalsaError> {
+        // Salsa's own state stays consistent when a query unwinds, and we only catch
+        // the panics raised by salsa itself.
+        match crate::cancelled::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast::<Cancelled>() {
+                Ok(cancelled) => Err(SalsaError::from(*cancelled)),
+                Err(payload) => {
+                    let cycle = CYCLE_PANIC.with(|cycle| cycle.borrow_mut().take());
+                    match (cycle, payload.downcast_ref::<String>()) {
+                        (Some((database_key, message)), Some(panic_message))
+                            if message == *panic_message =>
+                        {
+                            Err(SalsaError::cycle(database_key, message))
+                        }
+                        _ => panic::resume_unwind(payload),
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl From<Cancelled> for SalsaError {
+    fn from(cancelled: Cancelled) -> Self {
+        match cancelled {
+            Cancelled::PropagatedPanic => SalsaError::PropagatedPanic,
+            cancelled => SalsaError::Cancelled(cancelled),
+        }
+    }
+}
+
+impl fmt::Display for SalsaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SalsaError::Cancelled(cancelled) => fmt::Display::fmt(cancelled, f),
+            SalsaError::Cycle { message, .. } => f.write_str(message),
+            SalsaError::PropagatedPanic => f.write_str("query panicked on another thread"),
+        }
+    }
+}
+
+impl std::error::Error for SalsaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SalsaError::Cancelled(cancelled) => Some(cancelled),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::FxHashMap;
+
+use crate::hash::StableHasher;
+use crate::persistence::{Persist, PersistenceError};
+use crate::sync::Mutex;
+use crate::Id;
+
+/// A content-addressed identity for an input or interned struct that, unlike its [`Id`],
+/// remains the same across processes.
+///
+/// Stable identities are only tracked for structs declared with the `stable_id` option,
+/// e.g. `#[salsa::input(stable_id)]`, and are derived from a hash of the struct's
+/// debug name and fields:
+///
+/// * for interned structs, the identity is a hash of the interned fields;
+/// * for inputs, the identity is a hash of the field values the input was *created* with.
+///   Inputs created with identical values are disambiguated by their creation order, and
+///   setting a field afterwards does not change the identity.
+///
+/// The fields are hashed with their `Hash` implementations, which are not guaranteed to
+/// produce the same output across Rust versions. With `stable_id = key_fn`, the identity
+/// is instead a hash of the bytes returned by `key_fn`, which is called with a reference to
+/// the tuple of fields and returns a value implementing `AsRef<[u8]>`, e.g.
+/// `#[salsa::input(stable_id = path_key)]` with
+/// `fn path_key(fields: &(String, String)) -> &str { &fields.0 }`. The fields of inputs must
+/// implement `Hash` either way, as persisted memos record a hash of the current field values
+/// of the inputs they depend on, see [`Database::persist_memos`](crate::Database::persist_memos).
+///
+/// Note that fields holding other salsa structs are hashed by their `Id`, so the identities
+/// of such structs are only stable if the referenced structs are created in the same order.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StableId(u64);
+
+impl StableId {
+    /// Computes the stable identity of a struct with the given debug name and fields.
+    pub fn of<T: Hash + ?Sized>(debug_name: &str, fields: &T) -> Self {
+        let mut hasher = StableHasher::default();
+        debug_name.hash(&mut hasher);
+        fields.hash(&mut hasher);
+        StableId(hasher.finish())
+    }
+
+    /// Computes the stable identity of a struct with the given debug name from a key
+    /// returned by the struct's `stable_id` key function.
+    pub fn from_key<K: AsRef<[u8]> + ?Sized>(debug_name: &str, key: &K) -> Self {
+        let mut hasher = StableHasher::default();
+        hasher.write(debug_name.as_bytes());
+        hasher.write_u8(0xff);
+        hasher.write(key.as_ref());
+        StableId(hasher.finish())
+    }
+
+    /// Creates a stable identity from its `u64` representation.
+    ///
+    /// This should only be used to recreate a `StableId` together with [`StableId::as_u64`].
+    pub const fn from_u64(bits: u64) -> Self {
+        StableId(bits)
+    }
+
+    /// Returns the `u64` representation of this identity.
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the identity used for the `n`th struct whose fields hash to `self`.
+    fn disambiguate(self, n: u64) -> Self {
+        let mut hasher = StableHasher::default();
+        hasher.write_u64(self.0);
+        hasher.write_u64(n);
+        StableId(hasher.finish())
+    }
+}
+
+impl fmt::Debug for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StableId({:016x})", self.0)
+    }
+}
+
+impl Persist for StableId {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.0.encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self, PersistenceError> {
+        u64::decode(buf).map(StableId)
+    }
+}
+
+/// The stable identities of the structs allocated by an ingredient.
+pub(crate) struct StableIdMap {
+    inner: Mutex<StableIdMapInner>,
+}
+
+impl Default for StableIdMap {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(StableIdMapInner::default()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct StableIdMapInner {
+    ids: FxHashMap<StableId, Id>,
+    stable_ids: FxHashMap<Id, StableId>,
+}
+
+impl StableIdMap {
+    /// Records the stable identity for `id`, returning the identity that was assigned.
+    ///
+    /// If `stable_id` is already taken by another struct, it is disambiguated by the number
+    /// of structs that share it.
+    pub(crate) fn insert(&self, stable_id: StableId, id: Id) -> StableId {
+        let mut inner = self.inner.lock();
+        let mut candidate = stable_id;
+        let mut n = 0;
+        while inner.ids.contains_key(&candidate) {
+            n += 1;
+            candidate = stable_id.disambiguate(n);
+        }
+        inner.ids.insert(candidate, id);
+        inner.stable_ids.insert(id, candidate);
+        candidate
+    }
+
+    /// Forgets the stable identity of `id`, e.g. because its slot is being reused.
+    pub(crate) fn remove(&self, id: Id) {
+        let mut inner = self.inner.lock();
+        if let Some(stable_id) = inner.stable_ids.remove(&id) {
+            inner.ids.remove(&stable_id);
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.ids.clear();
+        inner.stable_ids.clear();
+    }
+
+    /// Returns the `Id` with the given stable identity, if any.
+    pub(crate) fn id(&self, stable_id: StableId) -> Option<Id> {
+        self.inner.lock().ids.get(&stable_id).copied()
+    }
+
+    /// Returns the stable identity of `id`, if any.
+    pub(crate) fn stable_id(&self, id: Id) -> Option<StableId> {
+        self.inner.lock().stable_ids.get(&id).copied()
+    }
+}
@@ -1,12 +1,16 @@
 //! Public API facades for the implementation details of [`Zalsa`] and [`ZalsaLocal`].
+use std::fmt;
 use std::marker::PhantomData;
 use std::panic::RefUnwindSafe;
+use std::time::{Duration, Instant};
 
 use crate::database::RawDatabase;
+use crate::event::EventSubscribers;
+use crate::executor::{DatabaseExecutor, Executor};
 use crate::sync::{Arc, Condvar, Mutex};
 use crate::zalsa::{ErasedJar, HasJar, Zalsa, ZalsaDatabase};
 use crate::zalsa_local::{self, ZalsaLocal};
-use crate::{Database, Event, EventKind};
+use crate::{CancellationToken, Database, Event, EventKind, EventKinds, EventSubscriber};
 
 /// A handle to non-local database state.
 pub struct StorageHandle<Db> {
@@ -43,15 +47,12 @@ impl<Db: Database> Default for StorageHandle<Db> {
 
 impl<Db: Database> StorageHandle<Db> {
     pub fn new(event_callback: Option<Box<dyn Fn(crate::Event) + Send + Sync + 'static>>) -> Self {
-        Self::with_jars(event_callback, Vec::new())
+        Self::with_jars(EventSubscribers::from_callback(event_callback), Vec::new())
     }
 
-    fn with_jars(
-        event_callback: Option<Box<dyn Fn(crate::Event) + Send + Sync + 'static>>,
-        jars: Vec<ErasedJar>,
-    ) -> Self {
+    fn with_jars(event_subscribers: EventSubscribers, jars: Vec<ErasedJar>) -> Self {
         Self {
-            zalsa_impl: Arc::new(Zalsa::new::<Db>(event_callback, jars)),
+            zalsa_impl: Arc::new(Zalsa::new::<Db>(event_subscribers, jars)),
             coordinate: CoordinateDrop(Arc::new(Coordinate {
                 clones: Mutex::new(1),
                 cvar: Default::default(),
@@ -128,6 +129,36 @@ impl<Db: Database> Storage<Db> {
         StorageBuilder::default()
     }
 
+    /// Sets the token that cancels the queries executed with this handle.
+    ///
+    /// Once the token is [cancelled](CancellationToken::cancel), queries executed with this
+    /// handle unwind with [`Cancelled::Requested`](crate::Cancelled::Requested), while the
+    /// other handles keep running. Clones of this handle, including the forks created by
+    /// `salsa::par_map` and `salsa::join`, inherit the token.
+    pub fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        self.zalsa_local.set_cancellation_token(token);
+    }
+
+    /// Returns the token that cancels the queries executed with this handle, if any.
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.zalsa_local.cancellation_token()
+    }
+
+    /// Sets the deadline after which the queries executed with this handle are cancelled.
+    ///
+    /// Once the deadline has passed, queries executed with this handle unwind with
+    /// [`Cancelled::DeadlineExceeded`](crate::Cancelled::DeadlineExceeded) the next time they
+    /// check for cancellation, which happens on every query fetch. Memos completed before
+    /// the deadline stay valid. Clones of this handle inherit the deadline.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.zalsa_local.set_deadline(deadline);
+    }
+
+    /// Returns the deadline after which the queries executed with this handle are cancelled, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.zalsa_local.deadline()
+    }
+
     /// Convert this instance of [`Storage`] into a [`StorageHandle`].
     ///
     /// This will discard the local state of this [`Storage`], thereby returning a value that
@@ -166,7 +197,9 @@ impl<Db: Database> Storage<Db> {
 
         self.handle
             .zalsa_impl
-            .event(&|| Event::new(EventKind::DidSetCancellationFlag));
+            .event_of_kind(EventKinds::DID_SET_CANCELLATION_FLAG, &|| {
+                Event::new(EventKind::DidSetCancellationFlag)
+            });
 
         let mut clones = self.handle.coordinate.clones.lock();
         while *clones != 1 {
@@ -179,14 +212,91 @@ impl<Db: Database> Storage<Db> {
         zalsa
     }
     // ANCHOR_END: cancel_other_workers
+
+    /// Like [`Self::cancel_others`], but gives up once `timeout` has passed.
+    ///
+    /// With a zero `timeout`, the other workers are not cancelled and this fails immediately
+    /// if any of them exist. On failure, the cancellation flag is reset again unless other
+    /// writers are still waiting.
+    fn try_cancel_others(&mut self, timeout: Duration) -> Result<&mut Zalsa, WouldBlock> {
+        debug_assert!(
+            self.zalsa_local
+                .try_with_query_stack(|stack| stack.is_empty())
+                == Some(true),
+            "attempted to cancel within query computation, this is a deadlock"
+        );
+        let cancel = !timeout.is_zero() && *self.handle.coordinate.clones.lock() != 1;
+        if cancel {
+            self.handle.zalsa_impl.runtime().set_cancellation_flag();
+
+            self.handle
+                .zalsa_impl
+                .event_of_kind(EventKinds::DID_SET_CANCELLATION_FLAG, &|| {
+                    Event::new(EventKind::DidSetCancellationFlag)
+                });
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut clones = self.handle.coordinate.clones.lock();
+        while cancel && *clones != 1 {
+            let timed_out;
+            (clones, timed_out) = self.handle.coordinate.cvar.wait_until(clones, deadline);
+            if timed_out {
+                break;
+            }
+        }
+        if *clones != 1 {
+            let live_handles = *clones - 1;
+            drop(clones);
+            if cancel {
+                self.handle.zalsa_impl.runtime().unset_cancellation_flag();
+            }
+            return Err(WouldBlock { live_handles });
+        }
+        drop(clones);
+        // The ref count on the `Arc` should now be 1
+        let zalsa = Arc::get_mut(&mut self.handle.zalsa_impl).unwrap();
+        // cancellation is done, so reset the flag
+        zalsa.runtime_mut().reset_cancellation_flag();
+        Ok(zalsa)
+    }
 }
 
+/// Error returned when mutable access to a database is not acquired because other handles
+/// to the same database are still alive, e.g. by the `try_set_<field>` setters of inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WouldBlock {
+    live_handles: usize,
+}
+
+impl WouldBlock {
+    /// Returns the number of other handles that were alive when giving up.
+    pub fn live_handles(&self) -> usize {
+        self.live_handles
+    }
+}
+
+impl fmt::Display for WouldBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot acquire mutable database access: {} other handle(s) still alive",
+            self.live_handles
+        )
+    }
+}
+
+impl std::error::Error for WouldBlock {}
+
 /// A builder for a [`Storage`] instance.
 ///
 /// This type can be created with the [`Storage::builder`] function.
 pub struct StorageBuilder<Db> {
     jars: Vec<ErasedJar>,
-    event_callback: Option<Box<dyn Fn(crate::Event) + Send + Sync + 'static>>,
+    event_subscribers: EventSubscribers,
+    auto_evict: usize,
+    auto_evict_interned: Option<usize>,
+    executor: Option<DatabaseExecutor>,
     _db: PhantomData<Db>,
 }
 
@@ -194,21 +304,62 @@ impl<Db> Default for StorageBuilder<Db> {
     fn default() -> Self {
         Self {
             jars: Vec::new(),
-            event_callback: None,
+            event_subscribers: EventSubscribers::default(),
+            auto_evict: 0,
+            auto_evict_interned: None,
+            executor: None,
             _db: PhantomData,
         }
     }
 }
 
 impl<Db: Database> StorageBuilder<Db> {
-    /// Set a callback for salsa events.
+    /// Add a callback for salsa events.
     ///
     /// The `event_callback` function will be invoked by the salsa runtime at various points during execution.
+    /// It receives events of all kinds, use [`Self::event_subscriber`] to only receive some of them.
     pub fn event_callback(
         mut self,
         callback: Box<dyn Fn(crate::Event) + Send + Sync + 'static>,
     ) -> Self {
-        self.event_callback = Some(callback);
+        self.event_subscribers.push_callback(callback);
+        self
+    }
+
+    /// Add a subscriber for salsa events.
+    ///
+    /// Each subscriber only receives the events it is [interested](EventSubscriber::interests) in.
+    pub fn event_subscriber(mut self, subscriber: impl EventSubscriber) -> Self {
+        self.event_subscribers.push(Box::new(subscriber));
+        self
+    }
+
+    /// Automatically evict memoized values every `revisions` revisions, when starting a new revision.
+    ///
+    /// This enforces the [memory budget](Database::set_memory_budget) without having to call
+    /// [`Database::trigger_lru_eviction`], and frees interned values if enabled with
+    /// [`Self::auto_evict_interned`]. The per-function LRU limits and the cleanup of
+    /// deleted entries already run on every new revision. The time spent is reported with an
+    /// [`EventKind::DidAutoEvict`] event, unless there is neither a memory budget nor interned
+    /// values to free. Automatic eviction is disabled if `revisions` is 0, which is the default.
+    pub fn auto_evict(mut self, revisions: usize) -> Self {
+        self.auto_evict = revisions;
+        self
+    }
+
+    /// Also free the interned values that were not read in the last `revisions` revisions
+    /// during [automatic eviction](Self::auto_evict), like [`Database::collect_interned_garbage`].
+    pub fn auto_evict_interned(mut self, revisions: usize) -> Self {
+        self.auto_evict_interned = Some(revisions);
+        self
+    }
+
+    /// Set the executor that runs the closures of [`crate::join`] and [`crate::par_map`].
+    ///
+    /// Defaults to [`RayonExecutor`](crate::RayonExecutor) if the `rayon` feature is enabled,
+    /// and to [`CurrentThreadExecutor`](crate::CurrentThreadExecutor) otherwise.
+    pub fn executor(mut self, executor: impl Executor) -> Self {
+        self.executor = Some(DatabaseExecutor::new(executor));
         self
     }
 
@@ -222,8 +373,15 @@ impl<Db: Database> StorageBuilder<Db> {
 
     /// Construct the [`Storage`] using the provided builder options.
     pub fn build(self) -> Storage<Db> {
+        let mut handle = StorageHandle::with_jars(self.event_subscribers, self.jars);
+        // The handle was just created, so the `Arc` is not shared yet.
+        let zalsa = Arc::get_mut(&mut handle.zalsa_impl).unwrap();
+        zalsa.set_auto_evict(self.auto_evict, self.auto_evict_interned);
+        if let Some(executor) = self.executor {
+            zalsa.set_executor(executor);
+        }
         Storage {
-            handle: StorageHandle::with_jars(self.event_callback, self.jars),
+            handle,
             zalsa_local: ZalsaLocal::new(),
         }
     }
@@ -240,6 +398,10 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
         self.storage_mut().cancel_others()
     }
 
+    fn zalsa_mut_timeout(&mut self, timeout: Duration) -> Result<&mut Zalsa, WouldBlock> {
+        self.storage_mut().try_cancel_others(timeout)
+    }
+
     #[inline(always)]
     fn zalsa_local(&self) -> &ZalsaLocal {
         &self.storage().zalsa_local
@@ -253,9 +415,12 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
 
 impl<Db: Database> Clone for Storage<Db> {
     fn clone(&self) -> Self {
+        let mut zalsa_local = ZalsaLocal::new();
+        zalsa_local.set_cancellation_token(self.zalsa_local.cancellation_token().cloned());
+        zalsa_local.set_deadline(self.zalsa_local.deadline());
         Self {
             handle: self.handle.clone(),
-            zalsa_local: ZalsaLocal::new(),
+            zalsa_local,
         }
     }
 }
@@ -33,6 +33,26 @@ pub mod shim {
             self.0.wait(guard).unwrap()
         }
 
+        /// Returns `true` if the wait timed out.
+        ///
+        /// Shuttle does not model time, and its `wait_timeout` never times out. Instead, the
+        /// scheduler decides whether the wait times out right away, like with a deadline
+        /// that is reached before any other thread runs, or waits for a notification.
+        pub fn wait_until<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+            deadline: std::time::Instant,
+        ) -> (MutexGuard<'a, T>, bool) {
+            use shuttle::rand::RngCore;
+
+            if deadline <= std::time::Instant::now()
+                || shuttle::rand::thread_rng().next_u32() % 2 == 0
+            {
+                return (guard, true);
+            }
+            (self.wait(guard), false)
+        }
+
         pub fn notify_one(&self) {
             self.0.notify_one();
         }
@@ -145,6 +165,16 @@ pub mod shim {
             guard
         }
 
+        /// Returns `true` if the wait timed out.
+        pub fn wait_until<'a, T>(
+            &self,
+            mut guard: MutexGuard<'a, T>,
+            deadline: std::time::Instant,
+        ) -> (MutexGuard<'a, T>, bool) {
+            let result = self.0.wait_until(&mut guard, deadline);
+            (guard, result.timed_out())
+        }
+
         pub fn notify_one(&self) {
             self.0.notify_one();
         }
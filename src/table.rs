@@ -102,6 +102,14 @@ struct Page {
     /// Number of elements of `data` that are initialized.
     allocated: AtomicUsize,
 
+    /// Number of initialized elements of `data` that were freed by their ingredient, e.g.
+    /// deleted tracked structs, and are not in use until the ingredient reuses them.
+    freed: AtomicUsize,
+
+    /// Whether `data` was released by [`Table::release_page`], in which case no elements
+    /// are initialized anymore.
+    released: bool,
+
     /// The "allocation lock" is held when we allocate a new entry.
     ///
     /// It ensures that we can load the index, initialize it, and then update the length atomically
@@ -135,7 +143,7 @@ unsafe impl Send for Page /* where for<M: Memo> M: Send */ {}
 // requires `Sync`.`
 unsafe impl Sync for Page /* where for<M: Memo> M: Sync */ {}
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PageIndex(usize);
 
 impl PageIndex {
@@ -144,6 +152,12 @@ impl PageIndex {
         debug_assert!(idx < MAX_PAGES);
         Self(idx)
     }
+
+    /// Returns the index of the page containing the slot for `id`.
+    #[inline]
+    pub(crate) fn of(id: Id) -> Self {
+        split_id(id).0
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -174,6 +188,26 @@ impl Table {
         self.pages[page_idx.0].ingredient
     }
 
+    /// Returns the [`IngredientIndex`] for an [`Id`], or `None` if `id` has not been allocated.
+    pub(crate) fn try_ingredient_index(&self, id: Id) -> Option<IngredientIndex> {
+        let index = id.index() as usize;
+        let page = self.pages.get(index >> PAGE_LEN_BITS)?;
+        ((index & PAGE_LEN_MASK) < page.allocated.load(Ordering::Acquire))
+            .then_some(page.ingredient)
+    }
+
+    /// Returns the ids of all slots allocated by the given ingredient.
+    pub(crate) fn ids_of(&self, ingredient: IngredientIndex) -> impl Iterator<Item = Id> + '_ {
+        self.pages
+            .iter()
+            .filter(move |(_, page)| page.ingredient == ingredient)
+            .flat_map(|(page_index, page)| {
+                let page_index = PageIndex::new(page_index);
+                (0..page.allocated.load(Ordering::Acquire))
+                    .map(move |slot| make_id(page_index, SlotIndex::new(slot)))
+            })
+    }
+
     /// Get a reference to the data for `id`, which must have been allocated from this table with type `T`.
     ///
     /// # Panics
@@ -312,6 +346,80 @@ impl Table {
             .or_default()
             .push(page);
     }
+
+    /// Records that the slot for `id` was freed by its ingredient, i.e. that it is not in use
+    /// until the ingredient reuses it, see [`Table::record_reused_slot`].
+    pub(crate) fn record_freed_slot(&self, id: Id) {
+        let (page, _) = split_id(id);
+        self.pages[page.0].freed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the freed slot for `id` was reused by its ingredient.
+    pub(crate) fn record_reused_slot(&self, id: Id) {
+        let (page, _) = split_id(id);
+        self.pages[page.0].freed.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Returns the oldest revision in which a memo of any slot was last verified, if there are
+    /// any memos.
+    pub(crate) fn min_memo_verified_at(&mut self) -> Option<Revision> {
+        self.pages
+            .iter()
+            .flat_map(|(_, page)| {
+                (0..page.allocated.load(Ordering::Acquire)).filter_map(move |slot| {
+                    // SAFETY: We have `&mut` access to the table, and supply a proper slot pointer.
+                    let memos = unsafe {
+                        &mut *(page.slot_vtable.memos_mut)(page.get(SlotIndex::new(slot)))
+                    };
+                    // SAFETY: The `Page` keeps the correct memo types.
+                    unsafe { page.memo_types.attach_memos_mut(memos) }.min_verified_at()
+                })
+            })
+            .min()
+    }
+
+    /// Returns the pages whose slots were all allocated and then freed.
+    ///
+    /// Pages that are not fully allocated are never returned, as they may still be used for
+    /// new allocations.
+    pub(crate) fn empty_pages(&mut self) -> Vec<PageIndex> {
+        self.pages
+            .iter()
+            .filter(|(_, page)| {
+                let allocated = page.allocated.load(Ordering::Acquire);
+                !page.released
+                    && allocated == PAGE_LEN
+                    && page.freed.load(Ordering::Relaxed) == allocated
+            })
+            .map(|(page_index, _)| PageIndex::new(page_index))
+            .collect()
+    }
+
+    /// Releases the data of the given page, returning the number of bytes released.
+    ///
+    /// Ids of the released slots remain valid for [`Table::ingredient_index`], but the slots
+    /// themselves can no longer be accessed, see [`Table::try_ingredient_index`].
+    ///
+    /// # Safety
+    ///
+    /// The page must be returned by [`Table::empty_pages`], and the ingredient owning it must
+    /// not reference its slots anymore, e.g. in a list of freed slots.
+    pub(crate) unsafe fn release_page(&mut self, page: PageIndex) -> usize {
+        let page_index = page.0;
+        let page = self
+            .pages
+            .get_mut(page_index)
+            .unwrap_or_else(|| panic!("index `{page_index}` is uninitialized"));
+        let len = mem::take(page.allocated.get_mut());
+        *page.freed.get_mut() = 0;
+        page.released = true;
+
+        // SAFETY: We supply the data pointer and the initialized length, and mark the page as
+        // released so that the data is not dropped again.
+        unsafe { (page.slot_vtable.drop_impl)(page.data.as_ptr(), len, &page.memo_types) };
+
+        PAGE_LEN * page.slot_vtable.layout.size()
+    }
 }
 
 impl<'db, T: Slot> PageView<'db, T> {
@@ -336,7 +444,7 @@ impl<'db, T: Slot> PageView<'db, T> {
     {
         let _guard = self.0.allocation_lock.lock();
         let index = self.0.allocated.load(Ordering::Acquire);
-        if index >= PAGE_LEN {
+        if index >= PAGE_LEN || self.0.released {
             return Err(value);
         }
 
@@ -388,6 +496,8 @@ impl Page {
             slot_type_name: std::any::type_name::<T>(),
             ingredient,
             allocated: Default::default(),
+            freed: Default::default(),
+            released: false,
             allocation_lock: Default::default(),
             data: NonNull::from(Box::leak(data)).cast::<()>(),
             memo_types,
@@ -446,6 +556,10 @@ fn type_assert_failed<T: 'static>(page: &Page) -> ! {
 
 impl Drop for Page {
     fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+
         let len = *self.allocated.get_mut();
         // SAFETY: We supply the data pointer and the initialized length
         unsafe { (self.slot_vtable.drop_impl)(self.data.as_ptr(), len, &self.memo_types) };
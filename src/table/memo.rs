@@ -4,7 +4,7 @@ use std::mem;
 use std::ptr::{self, NonNull};
 
 use crate::sync::atomic::{AtomicPtr, Ordering};
-use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOriginRef};
+use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOriginRef, Revision};
 
 /// The "memo table" stores the memoized results of tracked function calls.
 /// Every tracked function must take a salsa struct as its first argument
@@ -42,6 +42,9 @@ pub trait Memo: Any + Send + Sync {
     /// Returns the `origin` of this memo
     fn origin(&self) -> QueryOriginRef<'_>;
 
+    /// Returns the revision this memo was last verified in.
+    fn verified_at(&self) -> Revision;
+
     /// Returns memory usage information about the memoized value.
     #[cfg(feature = "salsa_unstable")]
     fn memory_usage(&self) -> crate::database::MemoInfo;
@@ -119,6 +122,10 @@ impl Memo for DummyMemo {
         unreachable!("should not get here")
     }
 
+    fn verified_at(&self) -> Revision {
+        unreachable!("should not get here")
+    }
+
     #[cfg(feature = "salsa_unstable")]
     fn memory_usage(&self) -> crate::database::MemoInfo {
         crate::database::MemoInfo {
@@ -295,6 +302,23 @@ impl MemoTableWithTypesMut<'_> {
         f(unsafe { MemoEntryType::from_dummy(memo).as_mut() });
     }
 
+    /// Returns the oldest revision in which one of the memos was last verified, if there are
+    /// any memos.
+    pub(crate) fn min_verified_at(&self) -> Option<Revision> {
+        self.memos
+            .memos
+            .iter()
+            .zip(self.types.types.iter())
+            .filter_map(|(memo, type_)| {
+                let memo = NonNull::new(memo.atomic_memo.load(Ordering::Acquire))?;
+
+                // SAFETY: The types match as per our constructor invariant.
+                let dyn_memo: &dyn Memo = unsafe { (type_.to_dyn_fn)(memo).as_ref() };
+                Some(dyn_memo.verified_at())
+            })
+            .min()
+    }
+
     /// To drop an entry, we need its type, so we don't implement `Drop`, and instead have this method.
     ///
     /// Note that calling this multiple times is safe, dropping an uninitialized entry is a no-op.
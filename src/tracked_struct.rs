@@ -7,6 +7,7 @@ use std::ops::Index;
 use std::{fmt, mem};
 
 use crossbeam_queue::SegQueue;
+use quarantine::Quarantine;
 use thin_vec::ThinVec;
 use tracked_field::FieldIngredientImpl;
 
@@ -18,15 +19,34 @@ use crate::key::DatabaseKeyIndex;
 use crate::plumbing::ZalsaLocal;
 use crate::revision::OptionalAtomicRevision;
 use crate::runtime::Stamp;
+use crate::salsa_error::SalsaError;
 use crate::salsa_struct::SalsaStructInDb;
 use crate::sync::Arc;
 use crate::table::memo::{MemoTable, MemoTableTypes, MemoTableWithTypesMut};
 use crate::table::{Slot, Table};
 use crate::zalsa::{IngredientIndex, Zalsa};
-use crate::{Durability, Event, EventKind, Id, Revision};
+use crate::{Database, Durability, Event, EventKind, EventKinds, Id, Revision};
 
+mod quarantine;
 pub mod tracked_field;
 
+impl dyn Database {
+    /// Returns how many slots of tracked structs were recycled after the generation of
+    /// their ids overflowed.
+    ///
+    /// Whenever the slot of a deleted tracked struct is reused, the generation of its id is
+    /// incremented, so that the ids of the old and the new tracked struct are distinct. Once
+    /// the generation cannot be incremented anymore, the slot is quarantined until every memo
+    /// was verified after the revision it was freed in, and then reused with the initial
+    /// generation.
+    pub fn recycled_tracked_struct_slots(&self) -> usize {
+        self.zalsa()
+            .ingredients()
+            .map(|ingredient| ingredient.recycled_slots())
+            .sum()
+    }
+}
+
 // ANCHOR: Configuration
 /// Trait that defines the key properties of a tracked struct.
 ///
@@ -167,6 +187,9 @@ where
     /// Store freed ids
     free_list: SegQueue<Id>,
 
+    /// Freed ids whose generation overflowed
+    quarantine: Quarantine,
+
     memo_table_types: Arc<MemoTableTypes>,
 }
 
@@ -280,6 +303,10 @@ where
     /// with different values.
     durability: Durability,
 
+    /// The generation of the id of this tracked struct, which is incremented whenever the
+    /// slot is reused.
+    generation: u32,
+
     /// The revision when this tracked struct was last updated.
     /// This field also acts as a kind of "lock". Once it is equal
     /// to `Some(current_revision)`, the fields are locked and
@@ -364,6 +391,7 @@ where
             ingredient_index: index,
             phantom: PhantomData,
             free_list: Default::default(),
+            quarantine: Default::default(),
             memo_table_types: Arc::new(MemoTableTypes::default()),
         }
     }
@@ -436,9 +464,10 @@ where
         current_deps: &Stamp,
         fields: C::Fields<'db>,
     ) -> Id {
-        let value = |_| Value {
+        let value = |id: Id| Value {
             updated_at: OptionalAtomicRevision::new(Some(current_revision)),
             durability: current_deps.durability,
+            generation: id.generation(),
             // lifetime erase for storage
             fields: unsafe { mem::transmute::<C::Fields<'db>, C::Fields<'static>>(fields) },
             revisions: C::new_revisions(current_deps.changed_at),
@@ -447,20 +476,19 @@ where
             memos: unsafe { MemoTable::new(self.memo_table_types()) },
         };
 
-        while let Some(id) = self.free_list.pop() {
+        let reused = self
+            .free_list
+            .pop()
             // Increment the ID generation before reusing it, as if we have allocated a new
             // slot in the table.
-            //
-            // If the generation would overflow, we are forced to leak the slot. Note that this
-            // shouldn't be a problem in general as sufficient bits are reserved for the generation.
-            let Some(id) = id.next_generation() else {
-                crate::tracing::info!(
-                    "leaking tracked struct {:?} due to generation overflow",
-                    self.database_key_index(id)
-                );
+            .map(|id| {
+                id.next_generation()
+                    .expect("ids at their maximum generation are quarantined")
+            })
+            .or_else(|| self.quarantine.recycle());
 
-                continue;
-            };
+        if let Some(id) = reused {
+            zalsa.table().record_reused_slot(id);
 
             // SAFETY: We just removed `id` from the free-list, so we have exclusive access.
             let data = unsafe { &mut *Self::data_raw(zalsa.table(), id) };
@@ -554,10 +582,11 @@ where
 
             // Updating the fields may make it necessary to increment the generation of the ID. In
             // the unlikely case that the ID is already at its maximum generation, we are forced to leak
-            // the previous slot and allocate a new value.
+            // the previous slot and allocate a new value. The previous slot is quarantined
+            // once it is deleted.
             if id.generation() == u32::MAX {
                 crate::tracing::info!(
-                    "leaking tracked struct {:?} due to generation overflow",
+                    "retiring tracked struct {:?} due to generation overflow",
                     self.database_key_index(id)
                 );
 
@@ -612,6 +641,7 @@ where
             id = id
                 .next_generation()
                 .expect("already verified that generation is not maximum");
+            data.generation = id.generation();
         }
 
         if current_deps.durability < data.durability {
@@ -645,7 +675,7 @@ where
     /// unspecified results (but not UB). See [`InternedIngredient::delete_index`] for more
     /// discussion and important considerations.
     pub(crate) fn delete_entity(&self, zalsa: &Zalsa, id: Id) {
-        zalsa.event(&|| {
+        zalsa.event_of_kind(EventKinds::DID_DISCARD, &|| {
             Event::new(crate::EventKind::DidDiscard {
                 key: self.database_key_index(id),
             })
@@ -676,6 +706,9 @@ where
 
         // SAFETY: We have acquired the write lock
         let data = unsafe { &mut *data_raw };
+
+        // Stale outputs are reported without their generation, so restore the current one.
+        let id = id.with_generation(data.generation);
         let memo_table = data.memo_table_mut();
 
         // SAFETY: The memo table belongs to a value that we allocated, so it
@@ -683,7 +716,16 @@ where
         unsafe { self.clear_memos(zalsa, memo_table, id) };
 
         // now that all cleanup has occurred, make available for re-use
-        self.free_list.push(id);
+        zalsa.table().record_freed_slot(id);
+        if id.generation() == u32::MAX {
+            crate::tracing::info!(
+                "quarantining tracked struct {:?} due to generation overflow",
+                self.database_key_index(id)
+            );
+            self.quarantine.push(id, current_revision);
+        } else {
+            self.free_list.push(id);
+        }
     }
 
     /// Clears the given memo table.
@@ -717,7 +759,9 @@ where
 
                 let executor = DatabaseKeyIndex::new(ingredient_index, id);
 
-                zalsa.event(&|| Event::new(EventKind::DidDiscard { key: executor }));
+                zalsa.event_of_kind(EventKinds::DID_DISCARD, &|| {
+                    Event::new(EventKind::DidDiscard { key: executor })
+                });
 
                 for stale_output in memo.origin().outputs() {
                     stale_output.remove_stale_output(zalsa, executor);
@@ -797,6 +841,26 @@ where
     pub fn entries<'db>(&'db self, zalsa: &'db Zalsa) -> impl Iterator<Item = &'db Value<C>> {
         zalsa.table().slots_of::<Value<C>>()
     }
+
+    /// Advances the ids on the free list to `generation`, as if their slots had been reused
+    /// that many times.
+    ///
+    /// Only used to test the recycling of slots after the generation of their ids overflowed.
+    #[cfg(test)]
+    pub(crate) fn advance_free_generations(&self, generation: u32) {
+        assert!(
+            generation < u32::MAX,
+            "ids at their maximum generation are quarantined"
+        );
+
+        let mut free = Vec::with_capacity(self.free_list.len());
+        while let Some(id) = self.free_list.pop() {
+            free.push(id.with_generation(id.generation().max(generation)));
+        }
+        for id in free {
+            self.free_list.push(id);
+        }
+    }
 }
 
 impl<C> Ingredient for IngredientImpl<C>
@@ -818,9 +882,9 @@ where
         _input: Id,
         _revision: Revision,
         _cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         // Any change to a tracked struct results in a new ID generation.
-        VerifyResult::unchanged()
+        Ok(VerifyResult::unchanged())
     }
 
     fn mark_validated_output(
@@ -859,6 +923,34 @@ where
         &mut self.memo_table_types
     }
 
+    fn forget_released_slots(&mut self, released: &dyn Fn(Id) -> bool) {
+        let free_list = mem::take(&mut self.free_list);
+        for id in free_list.into_iter().filter(|&id| !released(id)) {
+            self.free_list.push(id);
+        }
+        self.quarantine.forget(released);
+    }
+
+    fn has_quarantined_slots(&mut self) -> bool {
+        self.quarantine.has_pending()
+    }
+
+    fn release_quarantined_slots(&mut self, verified_at: Revision) {
+        self.quarantine.release(verified_at);
+    }
+
+    fn recycled_slots(&self) -> usize {
+        self.quarantine.recycled()
+    }
+
+    fn current_id(&self, zalsa: &Zalsa, slot_id: Id) -> Option<Id> {
+        let data = Self::data(zalsa.table(), slot_id);
+        // Slots on the free-list have no `updated_at` revision.
+        data.updated_at
+            .load()
+            .map(|_| slot_id.with_generation(data.generation))
+    }
+
     /// Returns memory usage information about any tracked structs.
     #[cfg(feature = "salsa_unstable")]
     fn memory_usage(&self, db: &dyn crate::Database) -> Option<Vec<crate::database::SlotInfo>> {
@@ -1065,4 +1157,130 @@ mod tests {
             assert_eq!(d.get(&i8), Some(Id::from_index(7)));
         };
     }
+
+    #[test]
+    fn quarantine_recycles_after_all_memos_verified() {
+        let mut quarantine = Quarantine::default();
+        // SAFETY: We don't use the IDs within salsa internals so this is fine
+        let id = unsafe { Id::from_index(0) }.with_generation(u32::MAX);
+        let freed_at = Revision::start();
+
+        quarantine.push(id, freed_at);
+        assert!(quarantine.has_pending());
+        quarantine.release(freed_at);
+        assert_eq!(quarantine.recycle(), None);
+        assert_eq!(quarantine.recycled(), 0);
+
+        quarantine.release(freed_at.next());
+        assert!(!quarantine.has_pending());
+        assert_eq!(quarantine.recycle(), Some(id.with_generation(0)));
+        assert_eq!(quarantine.recycle(), None);
+        assert_eq!(quarantine.recycled(), 1);
+    }
+
+    /// Tests the recycling of slots after the generation of their ids overflowed, once no memo
+    /// can depend on the previous ids anymore.
+    #[cfg(feature = "inventory")]
+    mod recycle {
+        use expect_test::expect;
+        use test_log::test;
+
+        use crate::plumbing::AsId;
+        use crate::{Database, Setter};
+
+        #[salsa::input]
+        struct Input {
+            count: usize,
+        }
+
+        #[salsa::tracked]
+        struct Tracked<'db> {
+            value: usize,
+        }
+
+        #[salsa::tracked(returns(ref))]
+        fn create_tracked(db: &dyn Database, input: Input) -> Vec<Tracked<'_>> {
+            (0..input.count(db)).map(|i| Tracked::new(db, i)).collect()
+        }
+
+        #[salsa::tracked]
+        fn count(db: &dyn Database, input: Input) -> usize {
+            input.count(db)
+        }
+
+        fn tracked_ids(db: &dyn Database, input: Input) -> String {
+            let ids = create_tracked(db, input)
+                .iter()
+                .map(AsId::as_id)
+                .collect::<Vec<_>>();
+            format!("{ids:?}")
+        }
+
+        fn recycled_slots(db: &dyn Database) -> usize {
+            db.recycled_tracked_struct_slots()
+        }
+
+        #[test]
+        fn recycles_slots_after_generation_overflow() {
+            let mut db = crate::DatabaseImpl::new();
+            let input = Input::new(&db, 1);
+            expect!["[Id(400)]"].assert_eq(&tracked_ids(&db, input));
+
+            // Delete the tracked struct, and reuse its slot with the maximum generation.
+            input.set_count(&mut db).to(0);
+            expect!["[]"].assert_eq(&tracked_ids(&db, input));
+            Tracked::ingredient(&db).advance_free_generations(u32::MAX - 1);
+            input.set_count(&mut db).to(1);
+            expect!["[Id(400gffffffff)]"].assert_eq(&tracked_ids(&db, input));
+
+            // The slot is quarantined once it is deleted again.
+            input.set_count(&mut db).to(0);
+            expect!["[]"].assert_eq(&tracked_ids(&db, input));
+
+            // The memo of `create_tracked` was last verified in the revision the slot was
+            // freed in, so the slot is not recycled yet.
+            input.set_count(&mut db).to(1);
+            expect!["[Id(401)]"].assert_eq(&tracked_ids(&db, input));
+            assert_eq!(recycled_slots(&db), 0);
+
+            // Every memo was verified after the slot was freed, so it is recycled.
+            input.set_count(&mut db).to(2);
+            expect!["[Id(401), Id(400)]"].assert_eq(&tracked_ids(&db, input));
+            assert_eq!(recycled_slots(&db), 1);
+        }
+
+        #[test]
+        fn stale_memos_keep_slots_quarantined() {
+            let mut db = crate::DatabaseImpl::new();
+            let input = Input::new(&db, 1);
+            let other = Input::new(&db, 0);
+
+            // This memo is never verified again.
+            assert_eq!(count(&db, other), 0);
+
+            expect!["[Id(400)]"].assert_eq(&tracked_ids(&db, input));
+            input.set_count(&mut db).to(0);
+            expect!["[]"].assert_eq(&tracked_ids(&db, input));
+            Tracked::ingredient(&db).advance_free_generations(u32::MAX - 1);
+            input.set_count(&mut db).to(1);
+            expect!["[Id(400gffffffff)]"].assert_eq(&tracked_ids(&db, input));
+            input.set_count(&mut db).to(0);
+            expect!["[]"].assert_eq(&tracked_ids(&db, input));
+
+            // The memos are scanned in the first two of these revisions, but not in the third,
+            // as the stale memo was not verified in between.
+            for count in 1..4 {
+                input.set_count(&mut db).to(count);
+                tracked_ids(&db, input);
+            }
+            expect!["[Id(401), Id(402), Id(403)]"].assert_eq(&tracked_ids(&db, input));
+            assert_eq!(recycled_slots(&db), 0);
+
+            // Verifying the stale memo releases the slot.
+            assert_eq!(count(&db, other), 0);
+            input.set_count(&mut db).to(4);
+            expect!["[Id(401), Id(402), Id(403), Id(400)]"].assert_eq(&tracked_ids(&db, input));
+            assert_eq!(recycled_slots(&db), 1);
+        }
+    }
 }
@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+use crossbeam_queue::SegQueue;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::Mutex;
+use crate::{Id, Revision};
+
+/// Holds the freed ids whose generation cannot be incremented anymore, until their
+/// slots can be recycled with the initial generation.
+///
+/// Recycling a slot makes the ids of its first generations valid again. To avoid confusing
+/// them with the ids of the tracked structs previously stored in the slot, a slot freed in
+/// revision `R` is only released for recycling once every memo in the database was verified
+/// after `R`. Verifying a memo that depends on a deleted tracked struct re-executes it, so no
+/// memo can depend on the previous ids of the slot anymore. Memos that are never verified
+/// again keep the slot quarantined.
+#[derive(Default)]
+pub(super) struct Quarantine {
+    /// The quarantined ids and the revisions they were freed in, oldest first.
+    ids: Mutex<VecDeque<(Id, Revision)>>,
+
+    /// The released ids, with their generation reset.
+    recyclable: SegQueue<Id>,
+
+    /// The number of slots recycled so far.
+    recycled: AtomicUsize,
+}
+
+impl Quarantine {
+    /// Quarantines `id`, which was freed in `revision`.
+    pub(super) fn push(&self, id: Id, revision: Revision) {
+        self.ids.lock().push_back((id, revision));
+    }
+
+    /// Returns `true` if there are quarantined ids that were not released yet.
+    pub(super) fn has_pending(&mut self) -> bool {
+        !self.ids.get_mut().is_empty()
+    }
+
+    /// Releases the ids freed before `verified_at` for recycling, where every memo was last
+    /// verified in `verified_at` or later.
+    pub(super) fn release(&mut self, verified_at: Revision) {
+        let ids = self.ids.get_mut();
+        while let Some(&(id, freed_at)) = ids.front() {
+            if freed_at >= verified_at {
+                break;
+            }
+            ids.pop_front();
+            self.recyclable.push(id.with_generation(0));
+        }
+    }
+
+    /// Returns a released id, if any.
+    pub(super) fn recycle(&self) -> Option<Id> {
+        let id = self.recyclable.pop()?;
+        self.recycled.fetch_add(1, Ordering::Relaxed);
+        Some(id)
+    }
+
+    /// Returns the number of slots recycled so far.
+    pub(super) fn recycled(&self) -> usize {
+        self.recycled.load(Ordering::Relaxed)
+    }
+
+    /// Forgets the ids for which `released` returns `true`.
+    pub(super) fn forget(&mut self, released: &dyn Fn(Id) -> bool) {
+        self.ids.get_mut().retain(|&(id, _)| !released(id));
+        let recyclable = std::mem::take(&mut self.recyclable);
+        for id in recyclable.into_iter().filter(|&id| !released(id)) {
+            self.recyclable.push(id);
+        }
+    }
+}
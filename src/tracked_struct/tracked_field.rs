@@ -3,6 +3,7 @@ use std::marker::PhantomData;
 use crate::cycle::CycleHeadKeys;
 use crate::function::VerifyResult;
 use crate::ingredient::Ingredient;
+use crate::salsa_error::SalsaError;
 use crate::sync::Arc;
 use crate::table::memo::MemoTableTypes;
 use crate::tracked_struct::{Configuration, Value};
@@ -62,10 +63,10 @@ where
         input: Id,
         revision: crate::Revision,
         _cycle_heads: &mut CycleHeadKeys,
-    ) -> VerifyResult {
+    ) -> Result<VerifyResult, SalsaError> {
         let data = <super::IngredientImpl<C>>::data(zalsa.table(), input);
         let field_changed_at = data.revisions[self.field_index];
-        VerifyResult::changed_if(field_changed_at > revision)
+        Ok(VerifyResult::changed_if(field_changed_at > revision))
     }
 
     fn fmt_index(&self, index: crate::Id, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
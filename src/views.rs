@@ -138,21 +138,27 @@ impl Views {
     ///
     /// If the underlying type of `db` is not the same as the database type this downcasts was created for.
     pub fn downcaster_for<DbView: ?Sized + Any>(&self) -> &DatabaseDownCaster<DbView> {
+        self.try_downcaster_for().unwrap_or_else(|| {
+            panic!(
+                "No downcaster registered for type `{}` in `Views`",
+                std::any::type_name::<DbView>(),
+            )
+        })
+    }
+
+    /// Retrieve an downcaster function to `dyn DbView`, if one has been registered.
+    pub(crate) fn try_downcaster_for<DbView: ?Sized + Any>(
+        &self,
+    ) -> Option<&DatabaseDownCaster<DbView>> {
         let view_type_id = TypeId::of::<DbView>();
-        for (_, view) in self.view_casters.iter() {
-            if view.target_type_id == view_type_id {
+        self.view_casters
+            .iter()
+            .find(|(_, view)| view.target_type_id == view_type_id)
+            .map(|(_, view)| {
                 // SAFETY: We are unerasing the type erased function pointer having made sure the
                 // TypeId matches.
-                return unsafe {
-                    &*((view as *const ViewCaster).cast::<DatabaseDownCaster<DbView>>())
-                };
-            }
-        }
-
-        panic!(
-            "No downcaster registered for type `{}` in `Views`",
-            std::any::type_name::<DbView>(),
-        );
+                unsafe { &*((view as *const ViewCaster).cast::<DatabaseDownCaster<DbView>>()) }
+            })
     }
 }
 
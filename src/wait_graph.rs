@@ -0,0 +1,102 @@
+//! Diagnostics for threads that are blocked on queries executing on other threads.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::key::DatabaseKeyIndex;
+use crate::sync::thread::ThreadId;
+use crate::Database;
+
+impl dyn Database {
+    /// Returns a snapshot of the threads that are currently blocked on queries executing
+    /// on other threads, e.g. to diagnose a suspected deadlock.
+    ///
+    /// The query stacks of the blocked threads and how long they have been blocked are only
+    /// recorded while the [blocking watchdog](Database::set_blocking_watchdog) is enabled.
+    pub fn wait_graph(&self) -> WaitGraph {
+        self.zalsa().runtime().wait_graph()
+    }
+}
+
+/// The threads blocked on queries executing on other threads, see `dyn Database::wait_graph`.
+///
+/// The `Display` implementation renders the graph in a human-readable form.
+#[derive(Clone, Debug, Default)]
+pub struct WaitGraph {
+    pub(crate) blocked_threads: Vec<BlockedThread>,
+}
+
+impl WaitGraph {
+    /// Returns the blocked threads.
+    pub fn blocked_threads(&self) -> &[BlockedThread] {
+        &self.blocked_threads
+    }
+
+    /// Returns `true` if no thread is blocked.
+    pub fn is_empty(&self) -> bool {
+        self.blocked_threads.is_empty()
+    }
+}
+
+impl fmt::Display for WaitGraph {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.blocked_threads.is_empty() {
+            return writeln!(fmt, "no blocked threads");
+        }
+        for thread in &self.blocked_threads {
+            write!(
+                fmt,
+                "{:?} blocked on {:?}",
+                thread.thread_id, thread.other_thread_id
+            )?;
+            if let Some(blocked_for) = thread.blocked_for {
+                write!(fmt, " for {blocked_for:?}")?;
+            }
+            writeln!(fmt, ", waiting for {:?}", thread.database_key)?;
+            writeln!(fmt, "  query stack:")?;
+            for (idx, database_key) in thread.query_stack.iter().enumerate() {
+                writeln!(fmt, "  {idx:>4}: {database_key:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A thread blocked on a query executing on another thread, see [`WaitGraph`].
+#[derive(Clone, Debug)]
+pub struct BlockedThread {
+    pub(crate) thread_id: ThreadId,
+    pub(crate) other_thread_id: ThreadId,
+    pub(crate) database_key: DatabaseKeyIndex,
+    pub(crate) query_stack: Box<[DatabaseKeyIndex]>,
+    pub(crate) blocked_for: Option<Duration>,
+}
+
+impl BlockedThread {
+    /// Returns the id of the blocked thread.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Returns the id of the thread executing the query.
+    pub fn other_thread_id(&self) -> ThreadId {
+        self.other_thread_id
+    }
+
+    /// Returns the query the thread is waiting for.
+    pub fn database_key(&self) -> DatabaseKeyIndex {
+        self.database_key
+    }
+
+    /// Returns the queries the blocked thread is executing, starting with the innermost one.
+    ///
+    /// This is empty unless the blocking watchdog is enabled.
+    pub fn query_stack(&self) -> &[DatabaseKeyIndex] {
+        &self.query_stack
+    }
+
+    /// Returns how long the thread has been blocked, if the blocking watchdog is enabled.
+    pub fn blocked_for(&self) -> Option<Duration> {
+        self.blocked_for
+    }
+}
@@ -1,20 +1,26 @@
 use std::any::{Any, TypeId};
 use std::hash::BuildHasherDefault;
+use std::num::NonZeroUsize;
 use std::panic::RefUnwindSafe;
+use std::time::{Duration, Instant};
 
 use hashbrown::HashMap;
 use rustc_hash::FxHashMap;
 
 use crate::database::RawDatabase;
-use crate::hash::TypeIdHasher;
+use crate::event::EventSubscribers;
+use crate::executor::{DatabaseExecutor, Executor};
+use crate::hash::{FxHashSet, TypeIdHasher};
 use crate::ingredient::{Ingredient, Jar};
+use crate::key::DatabaseKeyIndex;
+use crate::memory_lru::MemoryLru;
 use crate::plumbing::SalsaStructInDb;
 use crate::runtime::Runtime;
 use crate::table::memo::MemoTableWithTypes;
-use crate::table::Table;
+use crate::table::{PageIndex, Table};
 use crate::views::Views;
 use crate::zalsa_local::ZalsaLocal;
-use crate::{Database, Durability, Id, Revision};
+use crate::{Cancelled, Database, Durability, EventKinds, Id, Revision, WouldBlock};
 
 /// Internal plumbing trait.
 ///
@@ -47,6 +53,13 @@ pub unsafe trait ZalsaDatabase: Any {
     #[doc(hidden)]
     fn zalsa_mut(&mut self) -> &mut Zalsa;
 
+    /// Plumbing method: Like [`Self::zalsa_mut`], but fails if other database handles
+    /// are still alive after `timeout`.
+    ///
+    /// **WARNING:** Triggers cancellation to other database handles.
+    #[doc(hidden)]
+    fn zalsa_mut_timeout(&mut self, timeout: Duration) -> Result<&mut Zalsa, WouldBlock>;
+
     /// Access the thread-local state associated with this database
     #[doc(hidden)]
     fn zalsa_local(&self) -> &ZalsaLocal;
@@ -79,7 +92,7 @@ impl IngredientIndex {
     /// The maximum supported ingredient index.
     ///
     /// This reserves one bit for an optional tag.
-    const MAX_INDEX: u32 = 0x7FFF_FFFF;
+    pub(crate) const MAX_INDEX: u32 = 0x7FFF_FFFF;
 
     /// Create an ingredient index from a `u32`.
     pub(crate) fn new(v: u32) -> Self {
@@ -168,7 +181,63 @@ pub struct Zalsa {
     /// Each handle gets its own runtime, but the runtimes have shared state between them.
     runtime: Runtime,
 
-    event_callback: Option<Box<dyn Fn(crate::Event) + Send + Sync>>,
+    /// Whether to record why memos fail verification, see [`EventKind::WillReexecute`].
+    ///
+    /// [`EventKind::WillReexecute`]: crate::EventKind::WillReexecute
+    explain_reexecutions: bool,
+
+    /// Whether tracked functions are profiled, see [`crate::Database::set_profiling`].
+    profiling: bool,
+
+    /// How long a thread can be blocked on another thread before the wait graph is
+    /// logged, see [`crate::Database::set_blocking_watchdog`].
+    blocking_watchdog: Option<Duration>,
+
+    /// The budget for the memoized values of all tracked functions, in bytes,
+    /// see [`crate::Database::set_memory_budget`].
+    memory_budget: Option<usize>,
+
+    /// When the memos of all tracked functions were last used.
+    /// Only recorded if there is a memory budget.
+    memory_lru: MemoryLru,
+
+    /// Every how many revisions memos are evicted automatically, see `StorageBuilder::auto_evict`.
+    auto_evict: Option<NonZeroUsize>,
+
+    /// The `revisions` for which interned values are kept by the automatic eviction,
+    /// see `StorageBuilder::auto_evict_interned`.
+    auto_evict_interned: Option<usize>,
+
+    /// Executes the closures of `salsa::join` and `salsa::par_map`, see `StorageBuilder::executor`.
+    executor: DatabaseExecutor,
+
+    /// The minimum number of consecutive input edges of a memo that are verified in parallel,
+    /// see [`crate::Database::set_parallel_verification`].
+    parallel_verification: Option<usize>,
+
+    /// When the memos are scanned next to release quarantined tracked struct slots.
+    quarantine_scan: QuarantineScan,
+}
+
+/// Schedules the scans of all memos for [`Zalsa::release_quarantined_slots`].
+///
+/// A memo that is never verified again keeps the oldest revision in which a memo was verified
+/// from advancing, so the scans back off exponentially while it doesn't advance.
+#[derive(Default)]
+struct QuarantineScan {
+    /// The revision from which on the memos are scanned again.
+    next: usize,
+
+    /// The number of revisions after the last scan until the next one.
+    interval: usize,
+
+    /// The oldest revision in which a memo was verified at the last scan.
+    verified_at: Option<Revision>,
+}
+
+impl QuarantineScan {
+    /// The maximum number of revisions between the scans.
+    const MAX_INTERVAL: usize = 1024;
 }
 
 /// All fields on Zalsa are locked behind [`Mutex`]es and [`RwLock`]s and cannot enter
@@ -180,7 +249,7 @@ impl RefUnwindSafe for Zalsa {}
 
 impl Zalsa {
     pub(crate) fn new<Db: Database>(
-        event_callback: Option<Box<dyn Fn(crate::Event) + Send + Sync + 'static>>,
+        event_subscribers: EventSubscribers,
         jars: Vec<ErasedJar>,
     ) -> Self {
         let mut zalsa = Self {
@@ -189,9 +258,18 @@ impl Zalsa {
             ingredient_to_id_struct_type_id_map: Default::default(),
             ingredients_vec: Vec::new(),
             ingredients_requiring_reset: boxcar::Vec::new(),
-            runtime: Runtime::default(),
+            runtime: Runtime::new(event_subscribers),
             memo_ingredient_indices: Default::default(),
-            event_callback,
+            explain_reexecutions: false,
+            profiling: false,
+            blocking_watchdog: None,
+            memory_budget: None,
+            memory_lru: MemoryLru::default(),
+            auto_evict: None,
+            auto_evict_interned: None,
+            executor: DatabaseExecutor::default(),
+            parallel_verification: None,
+            quarantine_scan: QuarantineScan::default(),
             #[cfg(not(feature = "inventory"))]
             nonce: NONCE.nonce(),
         };
@@ -273,13 +351,28 @@ impl Zalsa {
             [memo_ingredient_index.as_usize()]
     }
 
-    #[cfg(feature = "salsa_unstable")]
-    pub(crate) fn ingredients(&self) -> impl Iterator<Item = &dyn Ingredient> {
+    pub(crate) fn ingredients(&self) -> impl ExactSizeIterator<Item = &dyn Ingredient> {
         self.ingredients_vec
             .iter()
             .map(|ingredient| ingredient.as_ref())
     }
 
+    /// Returns the pairs of salsa struct ingredients and the tracked function ingredients
+    /// that store memos on them.
+    pub(crate) fn memo_ingredients(
+        &self,
+    ) -> impl Iterator<Item = (IngredientIndex, IngredientIndex)> + '_ {
+        self.memo_ingredient_indices
+            .iter()
+            .enumerate()
+            .flat_map(|(struct_index, functions)| {
+                let struct_index = IngredientIndex::new(struct_index as u32);
+                functions
+                    .iter()
+                    .map(move |&function_index| (struct_index, function_index))
+            })
+    }
+
     /// Starts unwinding the stack if the current revision is cancelled.
     ///
     /// This method can be called by query implementations that perform
@@ -290,12 +383,33 @@ impl Zalsa {
     /// invocation.
     #[inline]
     pub(crate) fn unwind_if_revision_cancelled(&self, zalsa_local: &ZalsaLocal) {
-        self.event(&|| crate::Event::new(crate::EventKind::WillCheckCancellation));
-        if self.runtime().load_cancellation_flag() {
-            zalsa_local.unwind_cancelled(self.current_revision());
+        if let Err(cancelled) = self.check_revision_cancelled(zalsa_local) {
+            cancelled.throw();
         }
     }
 
+    /// Like [`Self::unwind_if_revision_cancelled`], but returns the cancellation instead of
+    /// unwinding.
+    #[inline]
+    pub(crate) fn check_revision_cancelled(
+        &self,
+        zalsa_local: &ZalsaLocal,
+    ) -> Result<(), Cancelled> {
+        self.event_of_kind(EventKinds::WILL_CHECK_CANCELLATION, &|| {
+            crate::Event::new(crate::EventKind::WillCheckCancellation)
+        });
+        let cancelled = if self.runtime().load_cancellation_flag() {
+            Cancelled::PendingWrite
+        } else if zalsa_local.is_cancellation_requested() {
+            Cancelled::Requested
+        } else if zalsa_local.is_deadline_exceeded() {
+            Cancelled::DeadlineExceeded
+        } else {
+            return Ok(());
+        };
+        Err(zalsa_local.cancelled(self.current_revision(), cancelled))
+    }
+
     pub(crate) fn next_memo_ingredient_index(
         &mut self,
         struct_ingredient_index: IngredientIndex,
@@ -424,9 +538,38 @@ impl Zalsa {
                 .get_mut(index)
                 .unwrap_or_else(|| panic!("index `{index}` is uninitialized"));
 
-            ingredient.reset_for_new_revision(self.runtime.table_mut());
+            ingredient.reset_for_new_revision(&mut self.runtime);
         }
 
+        self.release_quarantined_slots(new_revision);
+
+        // Skip the eviction and its event if there is nothing to evict.
+        let has_work = self.memory_budget.is_some() || self.auto_evict_interned.is_some();
+        if has_work
+            && self
+                .auto_evict
+                .is_some_and(|every| new_revision.as_usize() % every.get() == 0)
+        {
+            let start = Instant::now();
+            self.evict_over_memory_budget();
+            if let Some(revisions) = self.auto_evict_interned {
+                self.collect_interned_garbage(revisions);
+            }
+            let duration = start.elapsed();
+            self.event_of_kind(EventKinds::DID_AUTO_EVICT, &|| {
+                crate::Event::new(crate::EventKind::DidAutoEvict {
+                    revision: new_revision,
+                    duration,
+                })
+            });
+        }
+
+        self.event_of_kind(EventKinds::DID_START_NEW_REVISION, &|| {
+            crate::Event::new(crate::EventKind::DidStartNewRevision {
+                revision: new_revision,
+            })
+        });
+
         new_revision
     }
 
@@ -439,7 +582,160 @@ impl Zalsa {
             self.ingredients_vec
                 .get_mut(index)
                 .unwrap_or_else(|| panic!("index `{index}` is uninitialized"))
-                .reset_for_new_revision(self.runtime.table_mut());
+                .reset_for_new_revision(&mut self.runtime);
+        }
+        self.evict_over_memory_budget();
+    }
+
+    /// Releases the slots of tracked structs that were quarantined after the generation of
+    /// their ids overflowed for recycling, once every memo was verified after they were freed.
+    ///
+    /// This scans all memos, but only while slots are quarantined, which requires the slot of
+    /// a tracked struct to be reused four billion times, and less often while the scans don't
+    /// find newly verified memos, see [`QuarantineScan`].
+    fn release_quarantined_slots(&mut self, new_revision: Revision) {
+        if !self
+            .ingredients_vec
+            .iter_mut()
+            .any(|ingredient| ingredient.has_quarantined_slots())
+        {
+            self.quarantine_scan = QuarantineScan::default();
+            return;
+        }
+        if new_revision.as_usize() < self.quarantine_scan.next {
+            return;
+        }
+
+        let verified_at = self
+            .runtime
+            .table_mut()
+            .min_memo_verified_at()
+            .unwrap_or_else(Revision::max);
+
+        let scan = &mut self.quarantine_scan;
+        scan.interval = if scan.verified_at == Some(verified_at) {
+            (scan.interval * 2).min(QuarantineScan::MAX_INTERVAL)
+        } else {
+            1
+        };
+        scan.verified_at = Some(verified_at);
+        scan.next = new_revision.as_usize() + scan.interval;
+
+        for ingredient in &mut self.ingredients_vec {
+            ingredient.release_quarantined_slots(verified_at);
+        }
+    }
+
+    /// Releases the pages of the table whose slots were all freed, returning the number of
+    /// bytes released.
+    pub(crate) fn release_empty_pages(&mut self) -> usize {
+        let _span = crate::tracing::debug_span!("release_empty_pages").entered();
+        let pages = self
+            .runtime
+            .table_mut()
+            .empty_pages()
+            .into_iter()
+            .collect::<FxHashSet<_>>();
+        if pages.is_empty() {
+            return 0;
+        }
+
+        let released = |id: Id| pages.contains(&PageIndex::of(id));
+        for ingredient in &mut self.ingredients_vec {
+            ingredient.forget_released_slots(&released);
+        }
+        self.memory_lru.retain(|key| !released(key.key_index()));
+
+        let table = self.runtime.table_mut();
+        pages
+            .iter()
+            // SAFETY: The pages are empty, and their ingredients forgot about their slots.
+            .map(|&page| unsafe { table.release_page(page) })
+            .sum()
+    }
+
+    /// Frees the interned values that were not read in the last `revisions` revisions,
+    /// returning the number of freed values.
+    pub(crate) fn collect_interned_garbage(&mut self, revisions: usize) -> usize {
+        let _span = crate::tracing::debug_span!("collect_interned_garbage").entered();
+        self.ingredients_vec
+            .iter()
+            // SAFETY: We have `&mut` access to the database.
+            .map(|ingredient| unsafe { ingredient.collect_garbage(self, revisions) })
+            .sum()
+    }
+
+    /// Evicts the least recently used memoized values until the values that can be evicted
+    /// fit into the memory budget, if there is one.
+    fn evict_over_memory_budget(&mut self) {
+        let Some(budget) = self.memory_budget else {
+            return;
+        };
+        let memos = self
+            .memory_lru
+            .take()
+            .into_iter()
+            .map(|key| {
+                let size = self
+                    .lookup_ingredient(key.ingredient_index())
+                    .evictable_memory(self, key.key_index());
+                (key, size)
+            })
+            // Values that were already evicted don't need to be tracked anymore.
+            .filter(|&(_, size)| size > 0)
+            .collect::<Vec<_>>();
+
+        let mut total = memos.iter().map(|&(_, size)| size).sum::<usize>();
+        let mut evicted = 0;
+        for &(key, size) in &memos {
+            if total <= budget {
+                break;
+            }
+            let index = key.ingredient_index().as_u32() as usize;
+            self.ingredients_vec[index].evict_value(&mut self.runtime, key.key_index());
+            total -= size;
+            evicted += 1;
+        }
+        crate::tracing::debug!("evicted {evicted} memos, {total} bytes remaining");
+
+        self.memory_lru
+            .extend(memos[evicted..].iter().map(|&(key, _)| key));
+    }
+
+    /// Records that the memo of `key` was used, if there is a memory budget.
+    #[inline(always)]
+    pub(crate) fn record_memo_use(&self, key: DatabaseKeyIndex) {
+        if self.memory_budget.is_some() {
+            self.memory_lru.record_use(key);
+        }
+    }
+
+    pub(crate) fn set_auto_evict(&mut self, revisions: usize, interned_revisions: Option<usize>) {
+        self.auto_evict = NonZeroUsize::new(revisions);
+        self.auto_evict_interned = interned_revisions;
+    }
+
+    /// Returns the executor of `salsa::join` and `salsa::par_map`.
+    #[inline]
+    pub(crate) fn executor(&self) -> &dyn Executor {
+        self.executor.as_dyn()
+    }
+
+    /// Returns the executor of `salsa::join` and `salsa::par_map` if it is the rayon executor.
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub(crate) fn rayon_executor(&self) -> Option<&crate::RayonExecutor> {
+        self.executor.as_rayon()
+    }
+
+    pub(crate) fn set_executor(&mut self, executor: DatabaseExecutor) {
+        self.executor = executor;
+    }
+
+    pub(crate) fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory_budget = budget;
+        if budget.is_none() {
+            self.memory_lru.clear();
         }
     }
 
@@ -448,19 +744,63 @@ impl Zalsa {
         self.table().ingredient_index(id)
     }
 
+    /// Reports an event, which is only constructed if there are event subscribers.
+    ///
+    /// Prefer [`Self::event_of_kind`], which only constructs the event if a subscriber
+    /// is interested in its kind.
     #[inline(always)]
     pub fn event(&self, event: &dyn Fn() -> crate::Event) {
-        if self.event_callback.is_some() {
-            self.event_cold(event);
-        }
+        self.runtime.event_any(event);
+    }
+
+    /// Reports an event of the given kind, which is only constructed if there
+    /// is a subscriber interested in it.
+    #[inline(always)]
+    pub fn event_of_kind(&self, kind: EventKinds, event: &dyn Fn() -> crate::Event) {
+        self.runtime.event(kind, event);
+    }
+
+    /// Returns `true` if re-executions should be explained, see [`EventKind::WillReexecute`].
+    ///
+    /// [`EventKind::WillReexecute`]: crate::EventKind::WillReexecute
+    #[inline(always)]
+    pub(crate) fn explains_reexecutions(&self) -> bool {
+        self.explain_reexecutions && self.runtime.is_subscribed(EventKinds::WILL_REEXECUTE)
+    }
+
+    pub(crate) fn set_explain_reexecutions(&mut self, enabled: bool) {
+        self.explain_reexecutions = enabled;
+    }
+
+    /// Returns `true` if tracked functions are profiled, see [`crate::Database::set_profiling`].
+    #[inline(always)]
+    pub(crate) fn is_profiling(&self) -> bool {
+        self.profiling
+    }
+
+    pub(crate) fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Returns the timeout of the blocking watchdog, see [`crate::Database::set_blocking_watchdog`].
+    #[inline]
+    pub(crate) fn blocking_watchdog(&self) -> Option<Duration> {
+        self.blocking_watchdog
+    }
+
+    pub(crate) fn set_blocking_watchdog(&mut self, timeout: Option<Duration>) {
+        self.blocking_watchdog = timeout;
+    }
+
+    /// Returns the minimum number of consecutive input edges of a memo that are verified in
+    /// parallel, see [`crate::Database::set_parallel_verification`].
+    #[inline]
+    pub(crate) fn parallel_verification(&self) -> Option<usize> {
+        self.parallel_verification
     }
 
-    // Avoid inlining, as events are typically only enabled for debugging purposes.
-    #[cold]
-    #[inline(never)]
-    pub fn event_cold(&self, event: &dyn Fn() -> crate::Event) {
-        let event_callback = self.event_callback.as_ref().unwrap();
-        event_callback(event());
+    pub(crate) fn set_parallel_verification(&mut self, min_edges: Option<usize>) {
+        self.parallel_verification = min_edges.map(|min_edges| min_edges.max(2));
     }
 }
 
@@ -1,6 +1,7 @@
-use std::cell::{RefCell, UnsafeCell};
+use std::cell::{Cell, RefCell, UnsafeCell};
 use std::panic::UnwindSafe;
 use std::ptr::{self, NonNull};
+use std::time::{Duration, Instant};
 
 use rustc_hash::FxHashMap;
 use thin_vec::ThinVec;
@@ -15,11 +16,12 @@ use crate::cycle::{empty_cycle_heads, CycleHeads, IterationCount};
 use crate::durability::Durability;
 use crate::key::DatabaseKeyIndex;
 use crate::runtime::Stamp;
+use crate::salsa_error::SalsaError;
 use crate::sync::atomic::AtomicBool;
 use crate::table::{PageIndex, Slot, Table};
 use crate::tracked_struct::{Disambiguator, Identity, IdentityHash, IdentityMap};
 use crate::zalsa::{IngredientIndex, Zalsa};
-use crate::{Cancelled, Id, Revision};
+use crate::{CancellationToken, Cancelled, Id, Revision};
 
 /// State that is specific to a single execution thread.
 ///
@@ -37,6 +39,26 @@ pub struct ZalsaLocal {
     /// Stores the most recent page for a given ingredient.
     /// This is thread-local to avoid contention.
     most_recent_pages: UnsafeCell<FxHashMap<IngredientIndex, PageIndex>>,
+
+    /// The chain of dependencies that failed verification, starting with the dependency
+    /// that changed. Only recorded if [`Zalsa::explains_reexecutions`].
+    changed_dependencies: RefCell<Vec<DatabaseKeyIndex>>,
+
+    /// The time spent in nested executions and blocked on other threads, for each
+    /// execution being profiled. Only used if [`Zalsa::is_profiling`].
+    profile_frames: RefCell<Vec<Duration>>,
+
+    /// The token that cancels the queries executed with this handle, see
+    /// [`crate::Storage::set_cancellation_token`].
+    cancellation_token: Option<CancellationToken>,
+
+    /// The deadline after which the queries executed with this handle are cancelled, see
+    /// [`crate::Storage::set_deadline`].
+    deadline: Option<Instant>,
+
+    /// Whether this handle only verifies memos, and gives up on the queries it would have to
+    /// execute to verify them, see [`Self::set_verify_only`].
+    verify_only: Cell<bool>,
 }
 
 impl ZalsaLocal {
@@ -44,9 +66,59 @@ impl ZalsaLocal {
         ZalsaLocal {
             query_stack: RefCell::new(QueryStack::default()),
             most_recent_pages: UnsafeCell::new(FxHashMap::default()),
+            changed_dependencies: RefCell::new(Vec::new()),
+            profile_frames: RefCell::new(Vec::new()),
+            cancellation_token: None,
+            deadline: None,
+            verify_only: Cell::new(false),
         }
     }
 
+    pub(crate) fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
+
+    pub(crate) fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        self.cancellation_token = token;
+    }
+
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Makes this handle give up on verifying a memo that needs a query to be executed, by
+    /// unwinding with [`crate::run_async::yield_now`] instead.
+    ///
+    /// Used for the forks that verify the inputs of a memo in parallel, which must not
+    /// execute queries as they verify the inputs out of order.
+    pub(crate) fn set_verify_only(&self) {
+        self.verify_only.set(true);
+    }
+
+    #[inline]
+    pub(crate) fn is_verify_only(&self) -> bool {
+        self.verify_only.get()
+    }
+
+    /// Returns `true` if the deadline of this handle has passed.
+    #[inline]
+    pub(crate) fn is_deadline_exceeded(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns `true` if the cancellation token of this handle was cancelled.
+    #[inline]
+    pub(crate) fn is_cancellation_requested(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
     pub(crate) fn record_unfilled_pages(&mut self, table: &Table) {
         let most_recent_pages = self.most_recent_pages.get_mut();
         most_recent_pages
@@ -54,6 +126,47 @@ impl ZalsaLocal {
             .for_each(|(ingredient, page)| table.record_unfilled_page(ingredient, page));
     }
 
+    /// Records that `dependency` failed verification, after any dependencies of it that did.
+    pub(crate) fn record_changed_dependency(&self, dependency: DatabaseKeyIndex) {
+        self.changed_dependencies.borrow_mut().push(dependency);
+    }
+
+    /// Takes the chain of dependencies recorded with [`Self::record_changed_dependency`].
+    pub(crate) fn take_changed_dependencies(&self) -> Vec<DatabaseKeyIndex> {
+        std::mem::take(&mut *self.changed_dependencies.borrow_mut())
+    }
+
+    /// Discards the chain of dependencies recorded with [`Self::record_changed_dependency`],
+    /// e.g. because the query it was recorded for is not re-executed.
+    pub(crate) fn clear_changed_dependencies(&self) {
+        self.changed_dependencies.borrow_mut().clear();
+    }
+
+    /// Restores a chain of dependencies returned by [`Self::take_changed_dependencies`], so that
+    /// the queries depending on a re-executed query can extend it.
+    pub(crate) fn restore_changed_dependencies(&self, dependencies: Vec<DatabaseKeyIndex>) {
+        *self.changed_dependencies.borrow_mut() = dependencies;
+    }
+
+    /// Starts a frame for an execution being profiled.
+    pub(crate) fn push_profile_frame(&self) {
+        self.profile_frames.borrow_mut().push(Duration::ZERO);
+    }
+
+    /// Ends the innermost frame pushed with [`Self::push_profile_frame`], returning the
+    /// time reported with [`Self::report_profiled_child_time`] while it was active.
+    pub(crate) fn pop_profile_frame(&self) -> Duration {
+        self.profile_frames.borrow_mut().pop().unwrap_or_default()
+    }
+
+    /// Reports time that should not count towards the self time of the innermost
+    /// execution being profiled.
+    pub(crate) fn report_profiled_child_time(&self, time: Duration) {
+        if let Some(frame) = self.profile_frames.borrow_mut().last_mut() {
+            *frame += time;
+        }
+    }
+
     /// Allocate a new id in `table` for the given ingredient
     /// storing `value`. Remembers the most recent page from this
     /// thread and attempts to reuse it.
@@ -329,6 +442,20 @@ impl ZalsaLocal {
         }
     }
 
+    /// Register that the currently active query read a query that failed with `error`,
+    /// which makes the active query fail as well.
+    #[cold]
+    pub(crate) fn report_failure(&self, error: &SalsaError) {
+        // SAFETY: We do not access the query stack reentrantly.
+        unsafe {
+            self.with_query_stack_unchecked_mut(|stack| {
+                if let Some(top_query) = stack.last_mut() {
+                    top_query.add_failure(error);
+                }
+            })
+        }
+    }
+
     /// Update the top query on the stack to act as though it read a value
     /// of durability `durability` which changed in `revision`.
     // FIXME: Use or remove this.
@@ -394,11 +521,12 @@ impl ZalsaLocal {
         }
     }
 
+    /// Returns `cancelled`, after reporting the cancellation as an untracked read.
     #[cold]
-    pub(crate) fn unwind_cancelled(&self, current_revision: Revision) {
+    pub(crate) fn cancelled(&self, current_revision: Revision, cancelled: Cancelled) -> Cancelled {
         // Why is this reporting an untracked read? We do not store the query revisions on unwind do we?
         self.report_untracked_read(current_revision);
-        Cancelled::PendingWrite.throw();
+        cancelled
     }
 }
 
@@ -594,6 +722,42 @@ impl QueryRevisions {
         }
     }
 
+    /// Creates the revisions of a memo restored from persisted data.
+    ///
+    /// Restored memos are marked as changed in `changed_at`, which is expected to be the
+    /// revision in which they are restored.
+    pub(crate) fn restored(
+        changed_at: Revision,
+        durability: Durability,
+        edges: impl IntoIterator<Item = QueryEdge>,
+    ) -> Self {
+        Self {
+            changed_at,
+            durability,
+            origin: QueryOrigin::derived(edges),
+            #[cfg(feature = "accumulator")]
+            accumulated_inputs: Default::default(),
+            verified_final: AtomicBool::new(true),
+            extra: QueryRevisionsExtra::default(),
+        }
+    }
+
+    /// Returns `true` if these revisions can be written to persisted data, i.e. if they
+    /// only consist of input edges and carry none of the state in [`QueryRevisionsExtra`].
+    pub(crate) fn is_persistable(&self) -> bool {
+        #[cfg(feature = "accumulator")]
+        if self.accumulated_inputs.load().is_any() {
+            return false;
+        }
+
+        self.extra.0.is_none()
+            && matches!(
+                self.origin.as_ref(),
+                QueryOriginRef::Derived(edges)
+                    if edges.iter().all(|edge| matches!(edge.kind(), QueryEdgeKind::Input(_)))
+            )
+    }
+
     /// Returns a reference to the `AccumulatedMap` for this query, or `None` if the map is empty.
     #[cfg(feature = "accumulator")]
     pub(crate) fn accumulated(&self) -> Option<&AccumulatedMap> {
@@ -1062,6 +1226,18 @@ impl ActiveQueryGuard<'_> {
         }
     }
 
+    /// Takes the failure of a query read by this query, see [`ZalsaLocal::report_failure`].
+    pub(crate) fn take_failure(&self) -> Option<SalsaError> {
+        // SAFETY: We do not access the query stack reentrantly.
+        unsafe {
+            self.local_state.with_query_stack_unchecked_mut(|stack| {
+                #[cfg(debug_assertions)]
+                assert_eq!(stack.len(), self.push_len);
+                stack.last_mut().unwrap().take_failure()
+            })
+        }
+    }
+
     /// Invoked when the query has successfully completed execution.
     fn complete(self) -> QueryRevisions {
         // SAFETY: We do not access the query stack reentrantly.
@@ -1099,5 +1275,9 @@ impl Drop for ActiveQueryGuard<'_> {
                 );
             })
         };
+
+        // The query is unwinding, so any changed dependencies recorded for the queries being
+        // verified won't be used to explain their re-execution.
+        self.local_state.clear_changed_dependencies();
     }
 }
@@ -0,0 +1,154 @@
+#![cfg(feature = "inventory")]
+
+//! Test the automatic eviction of memoized values when starting new revisions.
+
+mod common;
+use common::{format_event_kind, HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{Database as _, Durability, EventKind, Setter, Storage};
+use test_log::test;
+
+/// Database that evicts automatically every other revision and logs the eviction events.
+///
+/// Interned values are only freed by databases created with [`Database::new`].
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Database {
+    fn new(auto_evict_interned: Option<usize>) -> Self {
+        let mut builder = Storage::builder().auto_evict(2);
+        if let Some(revisions) = auto_evict_interned {
+            builder = builder.auto_evict_interned(revisions);
+        }
+        let logger = Logger::default();
+        Self {
+            storage: builder
+                .event_callback(Box::new({
+                    let logger = logger.clone();
+                    move |event| match event.kind {
+                        EventKind::DidEvictLru { .. }
+                        | EventKind::DidAutoEvict { .. }
+                        | EventKind::DidGarbageCollectInterned { .. } => {
+                            logger.push_log_with(move || format_event_kind(&event.kind));
+                        }
+                        _ => {}
+                    }
+                }))
+                .build(),
+            logger,
+        }
+    }
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input(debug)]
+struct MyInput {
+    len: usize,
+}
+
+fn vec_heap_size(value: &Vec<u8>) -> usize {
+    value.capacity()
+}
+
+#[salsa::tracked(returns(ref), heap_size = vec_heap_size)]
+fn bytes(db: &dyn LogDatabase, input: MyInput) -> Vec<u8> {
+    vec![0; input.len(db)]
+}
+
+// Disable the lazy reuse of slots, so that only the automatic eviction frees values.
+#[salsa::interned(revisions = 1000)]
+#[derive(Debug)]
+struct Interned<'db> {
+    len: usize,
+}
+
+#[salsa::tracked]
+fn intern(db: &dyn LogDatabase, input: MyInput) -> Interned<'_> {
+    Interned::new(db, input.len(db))
+}
+
+#[test]
+fn enforces_memory_budget() {
+    let mut db = Database::default();
+    db.set_memory_budget(Some(1100));
+    let a = MyInput::new(&db, 1000);
+    let b = MyInput::new(&db, 1000);
+
+    bytes(&db, a);
+    bytes(&db, b);
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        [
+            "DidEvictLru { database_key: bytes(Id(0)) }",
+            "DidAutoEvict { revision: R2 }",
+        ]"#]]);
+
+    bytes(&db, a);
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        []"#]]);
+
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        [
+            "DidEvictLru { database_key: bytes(Id(1)) }",
+            "DidAutoEvict { revision: R4 }",
+        ]"#]]);
+}
+
+#[test]
+fn frees_interned_values() {
+    let mut db = Database::new(Some(0));
+    let input = MyInput::new(&db, 1);
+
+    intern(&db, input);
+    input.set_len(&mut db).to(2);
+    db.assert_logs(expect![[r#"
+        [
+            "DidGarbageCollectInterned { key: Interned(Id(400)), revision: R1 }",
+            "DidAutoEvict { revision: R2 }",
+        ]"#]]);
+
+    // Values are only freed every other revision.
+    intern(&db, input);
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        []"#]]);
+
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        [
+            "DidGarbageCollectInterned { key: Interned(Id(401)), revision: R2 }",
+            "DidAutoEvict { revision: R4 }",
+        ]"#]]);
+}
+
+#[test]
+fn skips_event_without_work() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1000);
+
+    bytes(&db, input);
+    db.synthetic_write(Durability::LOW);
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        []"#]]);
+}
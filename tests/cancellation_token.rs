@@ -0,0 +1,78 @@
+#![cfg(feature = "inventory")]
+
+//! Test cancelling individual database handles with a cancellation token.
+
+use salsa::{CancellationToken, Cancelled, Database, Storage};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn checked(db: &dyn Database, input: MyInput) -> u32 {
+    db.unwind_if_revision_cancelled();
+    input.field(db)
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct TokenDatabase {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for TokenDatabase {}
+
+#[test]
+fn cancels_only_the_handle() {
+    let db = TokenDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    let token = CancellationToken::new();
+    let mut cancelled_db = db.clone();
+    cancelled_db
+        .storage
+        .set_cancellation_token(Some(token.clone()));
+    assert_eq!(checked(&cancelled_db, input), 1);
+
+    token.cancel();
+    assert!(token.is_cancelled());
+    let result = Cancelled::catch(|| checked(&cancelled_db, input));
+    assert!(matches!(result, Err(Cancelled::Requested { .. })));
+    let result = Cancelled::catch(|| cancelled_db.unwind_if_revision_cancelled());
+    assert!(matches!(result, Err(Cancelled::Requested { .. })));
+
+    // Other handles keep running.
+    assert_eq!(checked(&db, input), 1);
+
+    // Removing the token makes the handle usable again.
+    cancelled_db.storage.set_cancellation_token(None);
+    assert_eq!(checked(&cancelled_db, input), 1);
+}
+
+#[test]
+fn forks_inherit_the_token() {
+    let db = TokenDatabase::default();
+    let token = CancellationToken::new();
+    let mut cancelled_db = db.clone();
+    cancelled_db
+        .storage
+        .set_cancellation_token(Some(token.clone()));
+
+    let fork = cancelled_db.clone();
+    assert!(fork.storage.cancellation_token().is_some());
+    token.cancel();
+    let result = Cancelled::catch(|| fork.unwind_if_revision_cancelled());
+    assert!(matches!(result, Err(Cancelled::Requested { .. })));
+
+    let result = Cancelled::catch(|| {
+        salsa::join(
+            &cancelled_db as &dyn Database,
+            |db| db.unwind_if_revision_cancelled(),
+            |_| {},
+        )
+    });
+    assert!(matches!(result, Err(Cancelled::Requested { .. })));
+}
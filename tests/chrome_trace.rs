@@ -0,0 +1,107 @@
+#![cfg(feature = "inventory")]
+
+//! Test recording salsa events in the Chrome Trace Event Format.
+
+use expect_test::expect;
+use salsa::{ChromeTraceRecorder, Database, Setter, Storage};
+use test_log::test;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn checked(db: &dyn Database, input: MyInput) -> u32 {
+    let value = inner(db, input);
+    assert_ne!(value, 0, "the field is not zero");
+    value
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + 1
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct TraceDatabase {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for TraceDatabase {}
+
+/// Puts every trace event on its own line and hides the timestamps and thread ids.
+fn normalize(json: &str) -> String {
+    json.split("},{")
+        .map(|event| {
+            let Some(start) = event.find("\"ts\":") else {
+                return event.to_string();
+            };
+            let end = start + event[start..].find(',').unwrap();
+            format!("{}\"ts\":_{}", &event[..start], &event[end..])
+        })
+        .collect::<Vec<_>>()
+        .join("}\n{")
+        .replace(&format!("{:?}", std::thread::current().id()), "ThreadId(_)")
+}
+
+#[test]
+fn nested_executions() {
+    let recorder = ChromeTraceRecorder::new();
+    let mut db = TraceDatabase {
+        storage: Storage::builder()
+            .event_subscriber(recorder.clone())
+            .build(),
+    };
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(outer(&db, input), 3);
+    input.set_field(&mut db).to(2);
+    assert_eq!(outer(&db, input), 5);
+
+    expect![[r#"
+        [{"name":"thread_name","ph":"M","pid":1,"tid":0,"args":{"name":"ThreadId(_)"}}
+        {"name":"outer(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"inner(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}
+        {"name":"cancellation","cat":"marker","ph":"i","ts":_,"pid":1,"tid":0,"s":"g"}
+        {"name":"new revision R2","cat":"marker","ph":"i","ts":_,"pid":1,"tid":0,"s":"g"}
+        {"name":"inner(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}
+        {"name":"outer(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}]"#]]
+    .assert_eq(&normalize(&recorder.to_json(&db)));
+}
+
+#[test]
+fn ends_spans_of_panicking_queries() {
+    let recorder = ChromeTraceRecorder::new();
+    let db = TraceDatabase {
+        storage: Storage::builder()
+            .event_subscriber(recorder.clone())
+            .build(),
+    };
+
+    let input = MyInput::new(&db, 0);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| checked(&db, input)));
+    assert!(result.is_err());
+    assert_eq!(outer(&db, input), 1);
+
+    expect![[r#"
+        [{"name":"thread_name","ph":"M","pid":1,"tid":0,"args":{"name":"ThreadId(_)"}}
+        {"name":"checked(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"inner(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}
+        {"name":"outer(Id(0))","cat":"execute","ph":"B","ts":_,"pid":1,"tid":0}
+        {"name":"","cat":"","ph":"E","ts":_,"pid":1,"tid":0}]"#]]
+    .assert_eq(&normalize(&recorder.to_json(&db)));
+}
@@ -0,0 +1,171 @@
+#![cfg(feature = "inventory")]
+
+//! Test that `Database::collect_interned_garbage` frees interned values
+//! that were not read recently.
+
+mod common;
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::{Database, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct Input {
+    field: usize,
+}
+
+// Disable the lazy reuse of slots, so that only explicit garbage collection frees values.
+#[salsa::interned(revisions = 1000)]
+#[derive(Debug)]
+struct Interned<'db> {
+    field: usize,
+}
+
+#[salsa::tracked]
+fn intern(db: &dyn Database, input: Input) -> Interned<'_> {
+    Interned::new(db, input.field(db))
+}
+
+#[salsa::tracked]
+fn double<'db>(db: &'db dyn Database, interned: Interned<'db>) -> usize {
+    interned.field(db) * 2
+}
+
+#[test]
+fn frees_values_not_read_recently() {
+    let mut db = common::EventLoggerDatabase::default();
+    let input = Input::new(&db, 0);
+
+    assert_eq!(double(&db, intern(&db, input)), 0);
+    input.set_field(&mut db).to(1);
+    assert_eq!(double(&db, intern(&db, input)), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "WillCheckCancellation",
+            "WillExecute { database_key: intern(Id(0)) }",
+            "DidInternValue { key: Interned(Id(400)), revision: R1 }",
+            "DidExecute { database_key: intern(Id(0)), backdated: false }",
+            "WillCheckCancellation",
+            "WillExecute { database_key: double(Id(400)) }",
+            "DidExecute { database_key: double(Id(400)), backdated: false }",
+            "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: Input.field(Id(0)), durability: Durability(0) }",
+            "WillCheckCancellation",
+            "WillExecute { database_key: intern(Id(0)) }",
+            "DidInternValue { key: Interned(Id(401)), revision: R2 }",
+            "DidExecute { database_key: intern(Id(0)), backdated: false }",
+            "WillCheckCancellation",
+            "WillExecute { database_key: double(Id(401)) }",
+            "DidExecute { database_key: double(Id(401)), backdated: false }",
+        ]"#]]);
+
+    // The value interned in R1 was not read in R2.
+    assert_eq!(db.collect_interned_garbage(0), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "DidSetCancellationFlag",
+            "DidGarbageCollectInterned { key: Interned(Id(400)), revision: R1 }",
+            "DidDiscard { key: double(Id(400)) }",
+        ]"#]]);
+
+    // The freed slot is reused with a new generation.
+    input.set_field(&mut db).to(0);
+    assert_eq!(double(&db, intern(&db, input)), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R3 }",
+            "DidSetInputField { key: Input.field(Id(0)), durability: Durability(0) }",
+            "WillCheckCancellation",
+            "WillExecute { database_key: intern(Id(0)) }",
+            "DidInternValue { key: Interned(Id(400g1)), revision: R3 }",
+            "DidExecute { database_key: intern(Id(0)), backdated: false }",
+            "WillCheckCancellation",
+            "WillExecute { database_key: double(Id(400g1)) }",
+            "DidExecute { database_key: double(Id(400g1)), backdated: false }",
+        ]"#]]);
+
+    // The value interned in R2 was read in the revision before the current one.
+    assert_eq!(db.collect_interned_garbage(1), 0);
+    assert_eq!(db.collect_interned_garbage(0), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "DidSetCancellationFlag",
+            "DidSetCancellationFlag",
+            "DidGarbageCollectInterned { key: Interned(Id(401)), revision: R2 }",
+            "DidDiscard { key: double(Id(401)) }",
+        ]"#]]);
+}
+
+#[test]
+fn keeps_values_read_in_current_revision() {
+    let mut db = common::EventLoggerDatabase::default();
+    let input = Input::new(&db, 0);
+
+    intern(&db, input);
+    assert_eq!(db.collect_interned_garbage(0), 0);
+
+    input.set_field(&mut db).to(1);
+    intern(&db, input);
+    assert_eq!(db.collect_interned_garbage(0), 1);
+    assert_eq!(db.collect_interned_garbage(0), 0);
+}
+
+#[test]
+fn invalidates_queries_reading_freed_values() {
+    #[salsa::tracked]
+    fn intern_constant(db: &dyn Database, input: Input) -> usize {
+        let _ = input.field(db);
+        Interned::new(db, 42).field(db)
+    }
+
+    let mut db = common::EventLoggerDatabase::default();
+    let input = Input::new(&db, 0);
+
+    assert_eq!(intern_constant(&db, input), 42);
+    input.set_field(&mut db).to(1);
+    assert_eq!(db.collect_interned_garbage(0), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "WillCheckCancellation",
+            "WillExecute { database_key: intern_constant(Id(0)) }",
+            "DidInternValue { key: Interned(Id(400)), revision: R1 }",
+            "DidExecute { database_key: intern_constant(Id(0)), backdated: false }",
+            "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: Input.field(Id(0)), durability: Durability(0) }",
+            "DidSetCancellationFlag",
+            "DidGarbageCollectInterned { key: Interned(Id(400)), revision: R1 }",
+        ]"#]]);
+
+    // The query re-executes as the value it interned was freed.
+    input.set_field(&mut db).to(2);
+    assert_eq!(intern_constant(&db, input), 42);
+    db.assert_logs(expect![[r#"
+        [
+            "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R3 }",
+            "DidSetInputField { key: Input.field(Id(0)), durability: Durability(0) }",
+            "WillCheckCancellation",
+            "WillExecute { database_key: intern_constant(Id(0)) }",
+            "DidInternValue { key: Interned(Id(400g1)), revision: R3 }",
+            "DidExecute { database_key: intern_constant(Id(0)), backdated: true }",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(expected = "attempted to read an interned value that was garbage collected")]
+fn reading_freed_value_panics() {
+    use salsa::plumbing::{AsId, FromId};
+
+    let mut db = common::EventLoggerDatabase::default();
+    let input = Input::new(&db, 0);
+
+    let id = intern(&db, input).as_id();
+    input.set_field(&mut db).to(1);
+    assert_eq!(db.collect_interned_garbage(0), 1);
+
+    // Reading the value through its stale ID must not observe the dropped fields.
+    Interned::from_id(id).field(&db);
+}
@@ -4,7 +4,7 @@
 
 use std::sync::{Arc, Mutex};
 
-use salsa::{Database, Storage};
+use salsa::{Database, EventKind, Storage};
 
 /// Logging userdata: provides [`LogDatabase`][] trait.
 ///
@@ -12,12 +12,23 @@ use salsa::{Database, Storage};
 /// you can also embed it in another struct and implement [`HasLogger`][] for that struct.
 #[derive(Clone, Default)]
 pub struct Logger {
-    logs: Arc<Mutex<Vec<String>>>,
+    logs: Arc<Mutex<Vec<LogEntry>>>,
 }
 
+/// A log entry that is formatted when the logs are asserted.
+type LogEntry = Box<dyn FnOnce() -> String + Send>;
+
 impl Logger {
     pub fn push_log(&self, string: String) {
-        self.logs.lock().unwrap().push(string);
+        self.push_log_with(move || string);
+    }
+
+    /// Log an entry that is formatted once the logs are asserted, with the database attached.
+    ///
+    /// Use this for salsa events, some of which (e.g. input writes) are reported while no
+    /// database is attached and would otherwise print the raw ingredient index of their key.
+    pub fn push_log_with(&self, format: impl FnOnce() -> String + Send + 'static) {
+        self.logs.lock().unwrap().push(Box::new(format));
     }
 }
 
@@ -31,14 +42,20 @@ pub trait HasLogger {
 pub trait LogDatabase: HasLogger + Database {
     /// Log an event from inside a tracked function.
     fn push_log(&self, string: String) {
-        self.logger().logs.lock().unwrap().push(string);
+        self.logger().push_log(string);
+    }
+
+    /// Takes the logged entries, formatting them with this database attached.
+    fn take_logs(&self) -> Vec<String> {
+        let logs = std::mem::take(&mut *self.logger().logs.lock().unwrap());
+        salsa::attach(self, || logs.into_iter().map(|format| format()).collect())
     }
 
     /// Asserts what the (formatted) logs should look like,
     /// clearing the logged events. This takes `&mut self` because
     /// it is meant to be run from outside any tracked functions.
     fn assert_logs(&self, expected: expect_test::Expect) {
-        let logs = std::mem::take(&mut *self.logger().logs.lock().unwrap());
+        let logs = self.take_logs();
         expected.assert_eq(&format!("{logs:#?}"));
     }
 
@@ -46,7 +63,7 @@ pub trait LogDatabase: HasLogger + Database {
     /// clearing the logged events. This takes `&mut self` because
     /// it is meant to be run from outside any tracked functions.
     fn assert_logs_len(&self, expected: usize) {
-        let logs = std::mem::take(&mut *self.logger().logs.lock().unwrap());
+        let logs = self.take_logs();
         assert_eq!(logs.len(), expected, "Actual logs: {logs:#?}");
     }
 }
@@ -85,7 +102,7 @@ impl Default for EventLoggerDatabase {
         Self {
             storage: Storage::new(Some(Box::new({
                 let logger = logger.clone();
-                move |event| logger.push_log(format!("{:?}", event.kind))
+                move |event| logger.push_log_with(move || format_event_kind(&event.kind))
             }))),
             logger,
         }
@@ -170,6 +187,21 @@ impl HasLogger for ExecuteValidateLoggerDatabase {
     }
 }
 
+/// Formats `kind` for the logs, leaving out the durations that differ between runs.
+pub fn format_event_kind(kind: &EventKind) -> String {
+    match kind {
+        EventKind::DidExecute {
+            database_key,
+            backdated,
+            ..
+        } => format!("DidExecute {{ database_key: {database_key:?}, backdated: {backdated} }}"),
+        EventKind::DidAutoEvict { revision, .. } => {
+            format!("DidAutoEvict {{ revision: {revision:?} }}")
+        }
+        _ => format!("{kind:?}"),
+    }
+}
+
 /// Trait implemented by databases that lets them provide a fixed u32 value.
 pub trait HasValue {
     fn get_value(&self) -> u32;
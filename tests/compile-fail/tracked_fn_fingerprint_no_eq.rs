@@ -0,0 +1,12 @@
+use salsa::Database as Db;
+
+#[salsa::input]
+struct MyInput {}
+
+#[salsa::tracked(fingerprint, no_eq)]
+fn tracked_fn(db: &dyn Db, input: MyInput) -> u32 {
+    _ = (db, input);
+    0
+}
+
+fn main() {}
@@ -2,7 +2,7 @@
 
 //! Test tracked struct output from a query in a cycle.
 mod common;
-use common::{HasLogger, LogDatabase, Logger};
+use common::{format_event_kind, HasLogger, LogDatabase, Logger};
 use expect_test::expect;
 use salsa::{Setter, Storage};
 
@@ -94,11 +94,13 @@ impl Default for Database {
                 move |event| match event.kind {
                     salsa::EventKind::WillExecute { .. }
                     | salsa::EventKind::DidValidateMemoizedValue { .. } => {
-                        logger.push_log(format!("salsa_event({:?})", event.kind));
+                        logger.push_log_with(move || format!("salsa_event({:?})", event.kind));
                     }
                     salsa::EventKind::WillCheckCancellation => {}
                     _ => {
-                        logger.push_log(format!("salsa_event({:?})", event.kind));
+                        logger.push_log_with(move || {
+                            format!("salsa_event({})", format_event_kind(&event.kind))
+                        });
                     }
                 }
             }))),
@@ -141,7 +143,7 @@ fn revalidate_no_changes() {
     assert_eq!(query_c(&db, c_input), 10);
     assert_eq!(query_b(&db, ab_input), 3);
 
-    db.assert_logs_len(15);
+    db.assert_logs_len(26);
 
     // trigger a new revision, but one that doesn't touch the query_a/query_b cycle
     c_input.set_value(&mut db).to(20);
@@ -151,6 +153,8 @@ fn revalidate_no_changes() {
     db.assert_logs(expect![[r#"
         [
             "salsa_event(DidSetCancellationFlag)",
+            "salsa_event(DidStartNewRevision { revision: R2 })",
+            "salsa_event(DidSetInputField { key: InputValue.value(Id(1)), durability: Durability(0) })",
             "salsa_event(DidValidateMemoizedValue { database_key: read_value(Id(400)) })",
             "salsa_event(DidValidateInternedValue { key: query_d::interned_arguments(Id(800)), revision: R2 })",
             "salsa_event(DidValidateMemoizedValue { database_key: query_d(Id(800)) })",
@@ -171,7 +175,7 @@ fn revalidate_with_change_after_output_read() {
 
     assert_eq!(query_b(&db, ab_input), 3);
 
-    db.assert_logs_len(14);
+    db.assert_logs_len(24);
 
     // trigger a new revision that changes the output of query_d
     d_input.set_value(&mut db).to(20);
@@ -181,12 +185,15 @@ fn revalidate_with_change_after_output_read() {
     db.assert_logs(expect![[r#"
         [
             "salsa_event(DidSetCancellationFlag)",
+            "salsa_event(DidStartNewRevision { revision: R2 })",
+            "salsa_event(DidSetInputField { key: InputValue.value(Id(1)), durability: Durability(0) })",
             "salsa_event(DidValidateMemoizedValue { database_key: read_value(Id(400)) })",
             "salsa_event(DidValidateInternedValue { key: query_d::interned_arguments(Id(800)), revision: R2 })",
             "salsa_event(WillExecute { database_key: query_b(Id(0)) })",
             "salsa_event(DidValidateInternedValue { key: query_d::interned_arguments(Id(800)), revision: R2 })",
             "salsa_event(WillExecute { database_key: query_a(Id(0)) })",
             "salsa_event(WillExecute { database_key: query_d(Id(800)) })",
+            "salsa_event(DidExecute { database_key: query_d(Id(800)), backdated: false })",
             "salsa_event(WillDiscardStaleOutput { execute_key: query_a(Id(0)), output_key: Output(Id(403)) })",
             "salsa_event(DidDiscard { key: Output(Id(403)) })",
             "salsa_event(DidDiscard { key: read_value(Id(403)) })",
@@ -196,14 +203,22 @@ fn revalidate_with_change_after_output_read() {
             "salsa_event(WillDiscardStaleOutput { execute_key: query_a(Id(0)), output_key: Output(Id(402)) })",
             "salsa_event(DidDiscard { key: Output(Id(402)) })",
             "salsa_event(DidDiscard { key: read_value(Id(402)) })",
+            "salsa_event(DidExecute { database_key: query_a(Id(0)), backdated: false })",
             "salsa_event(WillIterateCycle { database_key: query_b(Id(0)), iteration_count: IterationCount(1), fell_back: false })",
             "salsa_event(WillExecute { database_key: query_a(Id(0)) })",
             "salsa_event(WillExecute { database_key: read_value(Id(403g1)) })",
+            "salsa_event(DidExecute { database_key: read_value(Id(403g1)), backdated: false })",
+            "salsa_event(DidExecute { database_key: query_a(Id(0)), backdated: false })",
             "salsa_event(WillIterateCycle { database_key: query_b(Id(0)), iteration_count: IterationCount(2), fell_back: false })",
             "salsa_event(WillExecute { database_key: query_a(Id(0)) })",
             "salsa_event(WillExecute { database_key: read_value(Id(401g1)) })",
+            "salsa_event(DidExecute { database_key: read_value(Id(401g1)), backdated: false })",
+            "salsa_event(DidExecute { database_key: query_a(Id(0)), backdated: false })",
             "salsa_event(WillIterateCycle { database_key: query_b(Id(0)), iteration_count: IterationCount(3), fell_back: false })",
             "salsa_event(WillExecute { database_key: query_a(Id(0)) })",
             "salsa_event(WillExecute { database_key: read_value(Id(402g1)) })",
+            "salsa_event(DidExecute { database_key: read_value(Id(402g1)), backdated: false })",
+            "salsa_event(DidExecute { database_key: query_a(Id(0)), backdated: false })",
+            "salsa_event(DidExecute { database_key: query_b(Id(0)), backdated: true })",
         ]"#]]);
 }
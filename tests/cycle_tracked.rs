@@ -152,6 +152,7 @@ fn main() {
         [
             "WillCheckCancellation",
             "WillExecute { database_key: create_graph(Id(0)) }",
+            "DidExecute { database_key: create_graph(Id(0)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: cost_to_start(Id(402)) }",
             "WillCheckCancellation",
@@ -161,10 +162,12 @@ fn main() {
             "WillCheckCancellation",
             "WillExecute { database_key: cost_to_start(Id(400)) }",
             "WillCheckCancellation",
+            "DidExecute { database_key: cost_to_start(Id(400)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: cost_to_start(Id(401)) }",
             "WillCheckCancellation",
             "WillCheckCancellation",
+            "DidExecute { database_key: cost_to_start(Id(401)), backdated: false }",
             "WillIterateCycle { database_key: cost_to_start(Id(403)), iteration_count: IterationCount(1), fell_back: false }",
             "WillCheckCancellation",
             "WillCheckCancellation",
@@ -172,12 +175,18 @@ fn main() {
             "WillExecute { database_key: cost_to_start(Id(401)) }",
             "WillCheckCancellation",
             "WillCheckCancellation",
+            "DidExecute { database_key: cost_to_start(Id(401)), backdated: false }",
+            "DidExecute { database_key: cost_to_start(Id(403)), backdated: false }",
+            "DidExecute { database_key: cost_to_start(Id(402)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: GraphInput.simple(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: create_graph(Id(0)) }",
             "WillDiscardStaleOutput { execute_key: create_graph(Id(0)), output_key: Node(Id(403)) }",
             "DidDiscard { key: Node(Id(403)) }",
             "DidDiscard { key: cost_to_start(Id(403)) }",
+            "DidExecute { database_key: create_graph(Id(0)), backdated: false }",
             "WillCheckCancellation",
             "WillCheckCancellation",
             "WillExecute { database_key: cost_to_start(Id(402)) }",
@@ -190,5 +199,8 @@ fn main() {
             "WillCheckCancellation",
             "WillExecute { database_key: cost_to_start(Id(400)) }",
             "WillCheckCancellation",
+            "DidExecute { database_key: cost_to_start(Id(400)), backdated: true }",
+            "DidExecute { database_key: cost_to_start(Id(401)), backdated: false }",
+            "DidExecute { database_key: cost_to_start(Id(402)), backdated: false }",
         ]"#]]);
 }
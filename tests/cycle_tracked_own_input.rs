@@ -111,16 +111,21 @@ fn main() {
     db.assert_logs(expect![[r#"
         [
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: ClassNode.type_params(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: infer_class(Id(0)) }",
             "WillCheckCancellation",
             "WillExecute { database_key: infer_type_param(Id(400)) }",
             "WillCheckCancellation",
+            "DidExecute { database_key: infer_type_param(Id(400)), backdated: false }",
             "DidInternValue { key: Class(Id(c00)), revision: R2 }",
             "WillIterateCycle { database_key: infer_class(Id(0)), iteration_count: IterationCount(1), fell_back: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: infer_type_param(Id(400)) }",
             "WillCheckCancellation",
+            "DidExecute { database_key: infer_type_param(Id(400)), backdated: false }",
+            "DidExecute { database_key: infer_class(Id(0)), backdated: false }",
         ]"#]]);
 
     let class = ty.class().unwrap();
@@ -0,0 +1,92 @@
+#![cfg(feature = "inventory")]
+
+//! Test cancelling the queries of a database handle once its deadline has passed.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use salsa::{Cancelled, Database, Event, EventKinds, EventSubscriber, Storage};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + 1
+}
+
+/// Counts the executions of queries.
+#[derive(Clone, Default)]
+struct Executions(Arc<Mutex<usize>>);
+
+impl Executions {
+    fn take(&self) -> usize {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl EventSubscriber for Executions {
+    fn interests(&self) -> EventKinds {
+        EventKinds::WILL_EXECUTE
+    }
+
+    fn on_event(&self, _: &Event) {
+        *self.0.lock().unwrap() += 1;
+    }
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct DeadlineDatabase {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for DeadlineDatabase {}
+
+#[test]
+fn exceeded_deadline_keeps_memos() {
+    let executions = Executions::default();
+    let mut db = DeadlineDatabase {
+        storage: Storage::builder()
+            .event_subscriber(executions.clone())
+            .build(),
+    };
+    let input = MyInput::new(&db, 1);
+    assert_eq!(inner(&db, input), 2);
+    assert_eq!(executions.take(), 1);
+
+    db.storage.set_deadline(Some(Instant::now()));
+    let result = Cancelled::catch(|| outer(&db, input));
+    assert!(matches!(result, Err(Cancelled::DeadlineExceeded { .. })));
+    let result = Cancelled::catch(|| db.unwind_if_revision_cancelled());
+    assert!(matches!(result, Err(Cancelled::DeadlineExceeded { .. })));
+
+    // Clones inherit the deadline.
+    let clone = db.clone();
+    assert_eq!(clone.storage.deadline(), db.storage.deadline());
+
+    // The memo of `inner` completed before the deadline is still valid.
+    db.storage.set_deadline(None);
+    assert_eq!(outer(&db, input), 3);
+    assert_eq!(executions.take(), 1);
+}
+
+#[test]
+fn future_deadline() {
+    let mut db = DeadlineDatabase {
+        storage: Storage::default(),
+    };
+    let input = MyInput::new(&db, 1);
+    db.storage
+        .set_deadline(Some(Instant::now() + Duration::from_secs(3600)));
+    assert_eq!(outer(&db, input), 3);
+}
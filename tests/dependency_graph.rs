@@ -0,0 +1,135 @@
+#![cfg(feature = "inventory")]
+
+//! Test exporting the dependency graph of memoized values.
+
+use expect_test::expect;
+use salsa::{Database, DatabaseImpl, DependencyEdgeKind, Setter};
+use test_log::test;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(debug)]
+struct MyTracked<'db> {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn create(db: &dyn Database, input: MyInput) -> MyTracked<'_> {
+    MyTracked::new(db, inner(db, input))
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    create(db, input).value(db) + 1
+}
+
+#[test]
+fn dot() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(outer(&db, input), 3);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(outer(&db, input), 5);
+
+    let graph = (&db as &dyn Database).dependency_graph();
+    expect![[r#"
+        digraph salsa {
+            n0 [label="create(Id(0))\nchanged_at: R2\nverified_at: R2\ndurability: 0"];
+            n1 [label="outer(Id(0))\nchanged_at: R2\nverified_at: R2\ndurability: 0"];
+            n2 [label="inner(Id(0))\nchanged_at: R2\nverified_at: R2\ndurability: 0"];
+            n3 [label="MyTracked(Id(401))"];
+            n4 [label="MyInput.field(Id(0))\nchanged_at: R2\ndurability: 0"];
+            n0 -> n2;
+            n0 -> n3 [style=dashed];
+            n1 -> n0;
+            n2 -> n4;
+        }
+    "#]]
+    .assert_eq(&graph.to_dot());
+}
+
+#[test]
+fn json_from_key() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(inner(&db, input), 2);
+    assert_eq!(outer(&db, input), 3);
+
+    let db: &dyn Database = &db;
+    let full = db.dependency_graph();
+    let inner_key = full
+        .nodes()
+        .iter()
+        .find(|node| node.debug_name() == "inner")
+        .unwrap()
+        .key();
+
+    let graph = db.dependency_graph_from(inner_key);
+    assert_eq!(graph.nodes().len(), 2);
+    assert_eq!(graph.edges().len(), 1);
+    assert_eq!(graph.edges()[0].kind(), DependencyEdgeKind::Input);
+    expect![[r#"{"nodes":[{"id":0,"ingredient":"inner","key":"inner(Id(0))","changed_at":1,"verified_at":1,"durability":0},{"id":1,"ingredient":"field","key":"MyInput.field(Id(0))","changed_at":1,"verified_at":null,"durability":0}],"edges":[{"from":0,"to":1,"kind":"input"}]}"#]]
+    .assert_eq(&graph.to_json());
+}
+
+#[salsa::tracked]
+fn maybe_create(db: &dyn Database, input: MyInput) -> Option<MyTracked<'_>> {
+    (input.field(db) == 1).then(|| MyTracked::new(db, 0))
+}
+
+#[salsa::tracked]
+fn tracked_value<'db>(db: &'db dyn Database, tracked: MyTracked<'db>) -> u32 {
+    tracked.value(db)
+}
+
+#[salsa::tracked]
+fn read_created(db: &dyn Database, input: MyInput) -> u32 {
+    maybe_create(db, input).map_or(0, |tracked| tracked_value(db, tracked))
+}
+
+#[test]
+fn reused_slots() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let other = MyInput::new(&db, 2);
+    assert!(maybe_create(&db, input).is_some());
+
+    // Deletes the tracked struct, whose slot is then reused with a new generation.
+    input.set_field(&mut db).to(2);
+    other.set_field(&mut db).to(1);
+    assert!(maybe_create(&db, input).is_none());
+    assert_eq!(read_created(&db, other), 0);
+
+    // Node order and durabilities depend on the ingredient indices and the enabled
+    // durability levels, so only compare the edges between the node labels.
+    let graph = (&db as &dyn Database).dependency_graph();
+    let nodes = graph.nodes();
+    let mut edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .map(|edge| {
+            let (from, to) = (nodes[edge.from()].label(), nodes[edge.to()].label());
+            format!("{from} -> {to} ({:?})", edge.kind())
+        })
+        .collect();
+    edges.sort();
+    expect![[r#"
+        [
+            "maybe_create(Id(0)) -> MyInput.field(Id(0)) (Input)",
+            "maybe_create(Id(1)) -> MyInput.field(Id(1)) (Input)",
+            "maybe_create(Id(1)) -> MyTracked(Id(400g1)) (Output)",
+            "read_created(Id(1)) -> maybe_create(Id(1)) (Input)",
+            "read_created(Id(1)) -> tracked_value(Id(400g1)) (Input)",
+        ]
+    "#]]
+    .assert_debug_eq(&edges);
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "inventory")]
+
+//! Test that every durability level lets queries skip deep verification
+//! when only less durable inputs changed.
+
+mod common;
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+use salsa::{Database, Durability};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + 1
+}
+
+#[test]
+fn levels() {
+    assert_eq!(Durability::from_level(0), Durability::LOW);
+    assert_eq!(Durability::from_level(1), Durability::MEDIUM);
+    assert_eq!(
+        Durability::from_level(Durability::LEVELS - 1),
+        Durability::HIGH
+    );
+    assert!((1..Durability::LEVELS)
+        .all(|level| Durability::from_level(level - 1) < Durability::from_level(level)));
+}
+
+#[test]
+#[should_panic(expected = "invalid durability")]
+fn level_out_of_range() {
+    Durability::from_level(Durability::LEVELS);
+}
+
+#[test]
+fn skips_deep_verification() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let inputs = (0..Durability::LEVELS)
+        .map(|level| {
+            MyInput::builder(0)
+                .field_durability(Durability::from_level(level))
+                .new(&db)
+        })
+        .collect::<Vec<_>>();
+    for &input in &inputs {
+        assert_eq!(outer(&db, input), 1);
+    }
+    db.assert_logs_len(2 * Durability::LEVELS);
+
+    for written in 0..Durability::LEVELS {
+        db.synthetic_write(Durability::from_level(written));
+        for (level, &input) in inputs.iter().enumerate() {
+            assert_eq!(outer(&db, input), 1);
+            // Only `outer` is validated if its durability is higher than the write's,
+            // otherwise `inner` is validated first.
+            db.assert_logs_len(if level > written { 1 } else { 2 });
+        }
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "inventory")]
+
+//! Test registering several event subscribers with different interests.
+
+use std::sync::{Arc, Mutex};
+
+use expect_test::expect;
+use salsa::{Database, Event, EventKinds, EventSubscriber, Setter, Storage};
+use test_log::test;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[derive(Clone)]
+struct Recorder {
+    interests: EventKinds,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn new(interests: EventKinds) -> Self {
+        Self {
+            interests,
+            events: Arc::default(),
+        }
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl EventSubscriber for Recorder {
+    fn interests(&self) -> EventKinds {
+        self.interests
+    }
+
+    fn on_event(&self, event: &Event) {
+        let name = format!("{:?}", event.kind);
+        let name = name.split([' ', '{']).next().unwrap().to_string();
+        self.events.lock().unwrap().push(name);
+    }
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct SubscriberDatabase {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for SubscriberDatabase {}
+
+#[test]
+fn subscribers_receive_their_kinds() {
+    let executions = Recorder::new(EventKinds::WILL_EXECUTE | EventKinds::DID_EXECUTE);
+    let revisions = Recorder::new(EventKinds::DID_START_NEW_REVISION);
+    let nothing = Recorder::new(EventKinds::NONE);
+    let mut db = SubscriberDatabase {
+        storage: Storage::builder()
+            .event_subscriber(executions.clone())
+            .event_subscriber(revisions.clone())
+            .event_subscriber(nothing.clone())
+            .build(),
+    };
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+    input.set_field(&mut db).to(2);
+    assert_eq!(double(&db, input), 4);
+
+    expect![[r#"
+        [
+            "WillExecute",
+            "DidExecute",
+            "WillExecute",
+            "DidExecute",
+        ]
+    "#]]
+    .assert_debug_eq(&executions.take());
+    expect![[r#"
+        [
+            "DidStartNewRevision",
+        ]
+    "#]]
+    .assert_debug_eq(&revisions.take());
+    assert!(nothing.take().is_empty());
+}
+
+#[test]
+fn callback_and_subscriber() {
+    let revisions = Recorder::new(EventKinds::DID_START_NEW_REVISION);
+    let all = Arc::new(Mutex::new(0));
+    let mut db = SubscriberDatabase {
+        storage: Storage::builder()
+            .event_callback(Box::new({
+                let all = all.clone();
+                move |_| *all.lock().unwrap() += 1
+            }))
+            .event_subscriber(revisions.clone())
+            .build(),
+    };
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+    input.set_field(&mut db).to(2);
+
+    assert_eq!(revisions.take(), ["DidStartNewRevision"]);
+    assert!(*all.lock().unwrap() > 1);
+}
+
+#[test]
+fn event_kinds() {
+    let kinds = EventKinds::WILL_EXECUTE | EventKinds::DID_EXECUTE;
+    assert!(kinds.contains(EventKinds::WILL_EXECUTE));
+    assert!(!kinds.contains(EventKinds::WILL_EXECUTE | EventKinds::DID_DISCARD));
+    assert!(kinds.intersects(EventKinds::WILL_EXECUTE | EventKinds::DID_DISCARD));
+    assert!(!kinds.intersects(EventKinds::NONE));
+    assert!(EventKinds::ALL.contains(kinds));
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "inventory")]
+
+//! Test the events reported for executions, LRU evictions and input writes.
+
+mod common;
+use common::{format_event_kind, HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{Database as _, Durability, EventKind, Setter, Storage};
+use test_log::test;
+
+/// Database that logs the execution, eviction and input write events.
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        let logger = Logger::default();
+        Self {
+            storage: Storage::new(Some(Box::new({
+                let logger = logger.clone();
+                move |event| match event.kind {
+                    EventKind::DidExecute { .. }
+                    | EventKind::DidEvictLru { .. }
+                    | EventKind::DidSetInputField { .. } => {
+                        logger.push_log_with(move || format_event_kind(&event.kind));
+                    }
+                    _ => {}
+                }
+            }))),
+            logger,
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn parity(db: &dyn LogDatabase, input: MyInput) -> bool {
+    input.field(db) % 2 == 0
+}
+
+#[salsa::tracked(lru = 1)]
+fn evicted(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[test]
+fn execute_reports_backdating() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    assert!(!parity(&db, input));
+    input.set_field(&mut db).to(3);
+    assert!(!parity(&db, input));
+    input.set_field(&mut db).to(4);
+    assert!(parity(&db, input));
+
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: parity(Id(0)), backdated: false }",
+            "DidSetInputField { key: MyInput.field(Id(0)), durability: Durability(0) }",
+            "DidExecute { database_key: parity(Id(0)), backdated: true }",
+            "DidSetInputField { key: MyInput.field(Id(0)), durability: Durability(0) }",
+            "DidExecute { database_key: parity(Id(0)), backdated: false }",
+        ]"#]]);
+}
+
+#[test]
+fn lru_eviction() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+
+    assert_eq!(evicted(&db, a), 1);
+    assert_eq!(evicted(&db, b), 2);
+    db.synthetic_write(Durability::LOW);
+
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: evicted(Id(0)), backdated: false }",
+            "DidExecute { database_key: evicted(Id(1)), backdated: false }",
+            "DidEvictLru { database_key: evicted(Id(0)) }",
+        ]"#]]);
+}
+
+#[test]
+fn set_input_field() {
+    let mut db = Database::default();
+    let input = MyInput::new(&db, 1);
+
+    input.set_field(&mut db).to(2);
+    input
+        .set_field(&mut db)
+        .with_durability(Durability::MEDIUM)
+        .to(3);
+    input.set_field(&mut db).to(4);
+
+    db.assert_logs(expect![[r#"
+        [
+            "DidSetInputField { key: MyInput.field(Id(0)), durability: Durability(0) }",
+            "DidSetInputField { key: MyInput.field(Id(0)), durability: Durability(1) }",
+            "DidSetInputField { key: MyInput.field(Id(0)), durability: Durability(1) }",
+        ]"#]]);
+}
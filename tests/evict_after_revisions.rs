@@ -0,0 +1,103 @@
+#![cfg(feature = "inventory")]
+
+//! Test the eviction of memoized values that haven't been verified for a number of revisions.
+
+mod common;
+use common::{format_event_kind, HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{Database as _, Durability, EventKind, Storage};
+use test_log::test;
+
+/// Database that logs the execution and eviction events.
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        let logger = Logger::default();
+        Self {
+            storage: Storage::new(Some(Box::new({
+                let logger = logger.clone();
+                move |event| match event.kind {
+                    EventKind::DidExecute { .. } | EventKind::DidEvictLru { .. } => {
+                        logger.push_log_with(move || format_event_kind(&event.kind));
+                    }
+                    _ => {}
+                }
+            }))),
+            logger,
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(evict_after_revisions = 2)]
+fn double(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn evicts_unverified_values() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+
+    assert_eq!(double(&db, a), 2);
+    assert_eq!(double(&db, b), 4);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(double(&db, a), 2);
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: double(Id(0)), backdated: false }",
+            "DidExecute { database_key: double(Id(1)), backdated: false }",
+        ]"#]]);
+
+    // `b` was last verified three revisions ago, `a` two revisions ago.
+    db.synthetic_write(Durability::LOW);
+    db.assert_logs(expect![[r#"
+        [
+            "DidEvictLru { database_key: double(Id(1)) }",
+        ]"#]]);
+
+    assert_eq!(double(&db, a), 2);
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: double(Id(1)), backdated: false }",
+        ]"#]]);
+}
+
+#[test]
+fn disabled_at_runtime() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 1);
+
+    double::set_evict_after_revisions(&mut db, 0);
+    assert_eq!(double(&db, a), 2);
+    for _ in 0..4 {
+        db.synthetic_write(Durability::LOW);
+    }
+    assert_eq!(double(&db, a), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: double(Id(0)), backdated: false }",
+        ]"#]]);
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "inventory")]
+
+//! Test `salsa::join` and `salsa::par_map` with a custom executor.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use salsa::{CurrentThreadExecutor, Executor, Storage, Task};
+
+/// Executor that executes the closures in reverse order on the calling thread, and counts
+/// the dispatched calls.
+#[derive(Clone, Default)]
+struct ReverseExecutor {
+    calls: Arc<AtomicUsize>,
+}
+
+impl Executor for ReverseExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        b();
+        a();
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        (0..len).rev().for_each(op);
+    }
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl Database {
+    fn new(executor: impl Executor) -> Self {
+        Self {
+            storage: Storage::builder().executor(executor).build(),
+        }
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    values: Vec<u32>,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn salsa::Database, input: MyInput, index: usize) -> u32 {
+    input.values(db)[index] * 2
+}
+
+#[salsa::tracked]
+fn doubled(db: &dyn salsa::Database, input: MyInput) -> Vec<u32> {
+    let len = input.values(db).len();
+    salsa::par_map(db, 0..len, |db, index| double(db, input, index))
+}
+
+#[salsa::tracked]
+fn sum_of_halves(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    let mid = input.values(db).len() / 2;
+    let (a, b) = salsa::join(
+        db,
+        |db| input.values(db)[..mid].iter().sum::<u32>(),
+        |db| input.values(db)[mid..].iter().sum::<u32>(),
+    );
+    a + b
+}
+
+#[test]
+fn custom_executor() {
+    let executor = ReverseExecutor::default();
+    let db = Database::new(executor.clone());
+    let input = MyInput::new(&db, vec![1, 2, 3, 4]);
+
+    // The outputs keep the order of the inputs, even though they are computed in reverse.
+    assert_eq!(doubled(&db, input), [2, 4, 6, 8]);
+    assert_eq!(sum_of_halves(&db, input), 10);
+    assert_eq!(executor.calls.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn current_thread_executor() {
+    let db = Database::new(CurrentThreadExecutor);
+    let input = MyInput::new(&db, vec![1, 2, 3]);
+
+    assert_eq!(doubled(&db, input), [2, 4, 6]);
+    assert_eq!(sum_of_halves(&db, input), 6);
+}
+
+#[test]
+fn current_thread_executor_completes_after_panic() {
+    let executed = AtomicUsize::new(0);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        CurrentThreadExecutor.join(
+            Box::new(|| panic!("a panicked")),
+            Box::new(|| {
+                executed.fetch_add(1, Ordering::Relaxed);
+            }),
+        );
+    }));
+    assert!(result.is_err());
+    assert_eq!(executed.load(Ordering::Relaxed), 1);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        CurrentThreadExecutor.for_each(3, &|index| {
+            executed.fetch_add(1, Ordering::Relaxed);
+            assert_ne!(index, 0, "first closure panicked");
+        });
+    }));
+    assert!(result.is_err());
+    assert_eq!(executed.load(Ordering::Relaxed), 4);
+}
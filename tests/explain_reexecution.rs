@@ -0,0 +1,210 @@
+#![cfg(feature = "inventory")]
+
+//! Test the `WillReexecute` events explaining why queries are re-executed.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, EventKind, ReexecuteReason, Setter, Storage};
+use test_log::test;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+    unrelated: u32,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + input.unrelated(db)
+}
+
+#[salsa::tracked]
+fn panics_on_two(db: &dyn Database, input: MyInput) -> u32 {
+    assert_ne!(inner(db, input), 4, "field is two");
+    input.unrelated(db)
+}
+
+#[salsa::tracked]
+fn untracked(db: &dyn Database, input: MyInput) -> u32 {
+    db.report_untracked_read();
+    input.unrelated(db)
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct ExplainDatabase {
+    storage: Storage<Self>,
+    reasons: Arc<Mutex<Vec<(String, ReexecuteReason)>>>,
+}
+
+impl Default for ExplainDatabase {
+    fn default() -> Self {
+        let reasons = Arc::<Mutex<Vec<_>>>::default();
+        let mut db = Self {
+            storage: Storage::new(Some(Box::new({
+                let reasons = reasons.clone();
+                move |event| {
+                    if let EventKind::WillReexecute {
+                        database_key,
+                        reason,
+                    } = event.kind
+                    {
+                        reasons
+                            .lock()
+                            .unwrap()
+                            .push((format!("{database_key:?}"), reason));
+                    }
+                }
+            }))),
+            reasons,
+        };
+        db.set_explain_reexecutions(true);
+        db
+    }
+}
+
+#[salsa::db]
+impl Database for ExplainDatabase {}
+
+impl ExplainDatabase {
+    fn take_reasons(&self) -> Vec<(String, ReexecuteReason)> {
+        std::mem::take(&mut *self.reasons.lock().unwrap())
+    }
+
+    fn take_changed_paths(&self) -> Vec<(String, Vec<String>)> {
+        self.take_reasons()
+            .into_iter()
+            .map(|(key, reason)| match reason {
+                ReexecuteReason::ChangedDependency { path, changed_at } => {
+                    assert!(changed_at.is_some());
+                    let path =
+                        salsa::attach(self, || path.iter().map(|key| format!("{key:?}")).collect());
+                    (key, path)
+                }
+                reason => panic!("unexpected reason for {key}: {reason:?}"),
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn changed_input_field() {
+    let mut db = ExplainDatabase::default();
+    let input = MyInput::new(&db, 1, 10);
+    assert_eq!(outer(&db, input), 12);
+    assert!(db.take_reasons().is_empty());
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(outer(&db, input), 14);
+    assert_eq!(
+        db.take_changed_paths(),
+        [
+            (
+                "inner(Id(0))".to_string(),
+                vec!["MyInput.field(Id(0))".to_string()]
+            ),
+            (
+                "outer(Id(0))".to_string(),
+                vec![
+                    "inner(Id(0))".to_string(),
+                    "MyInput.field(Id(0))".to_string()
+                ]
+            ),
+        ]
+    );
+
+    input.set_unrelated(&mut db).to(20);
+    assert_eq!(outer(&db, input), 24);
+    assert_eq!(
+        db.take_changed_paths(),
+        [(
+            "outer(Id(0))".to_string(),
+            vec!["MyInput.unrelated(Id(0))".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn backdated_dependency() {
+    let mut db = ExplainDatabase::default();
+    let input = MyInput::new(&db, 1, 10);
+    assert_eq!(outer(&db, input), 12);
+
+    // `inner` is re-executed but backdated, so `outer` is reused.
+    input.set_field(&mut db).to(1);
+    assert_eq!(outer(&db, input), 12);
+    assert_eq!(
+        db.take_changed_paths(),
+        [(
+            "inner(Id(0))".to_string(),
+            vec!["MyInput.field(Id(0))".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn untracked_read() {
+    let mut db = ExplainDatabase::default();
+    let input = MyInput::new(&db, 1, 10);
+    assert_eq!(untracked(&db, input), 10);
+
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(untracked(&db, input), 10);
+    assert_eq!(
+        db.take_reasons(),
+        [(
+            "untracked(Id(0))".to_string(),
+            ReexecuteReason::UntrackedRead
+        )]
+    );
+}
+
+#[test]
+fn after_unwinding() {
+    let mut db = ExplainDatabase::default();
+    let input = MyInput::new(&db, 1, 10);
+    assert_eq!(panics_on_two(&db, input), 10);
+
+    input.set_field(&mut db).to(2);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        panics_on_two(&db, input);
+    }));
+    assert!(result.is_err());
+    db.take_reasons();
+
+    input.set_field(&mut db).to(3);
+    assert_eq!(panics_on_two(&db, input), 10);
+    assert_eq!(
+        db.take_changed_paths(),
+        [
+            (
+                "inner(Id(0))".to_string(),
+                vec!["MyInput.field(Id(0))".to_string()]
+            ),
+            (
+                "panics_on_two(Id(0))".to_string(),
+                vec![
+                    "inner(Id(0))".to_string(),
+                    "MyInput.field(Id(0))".to_string()
+                ]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn disabled() {
+    let mut db = ExplainDatabase::default();
+    db.set_explain_reexecutions(false);
+    let input = MyInput::new(&db, 1, 10);
+    assert_eq!(outer(&db, input), 12);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(outer(&db, input), 14);
+    assert!(db.take_reasons().is_empty());
+}
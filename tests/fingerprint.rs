@@ -0,0 +1,113 @@
+#![cfg(feature = "inventory")]
+
+//! Test that tracked functions declared with the `fingerprint` option
+//! can be backdated even after LRU evicted their values.
+
+mod common;
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input(debug)]
+struct MyInput {
+    text: String,
+}
+
+#[salsa::tracked(lru = 1, fingerprint)]
+fn length(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log(format!("length({})", input.text(db)));
+    input.text(db).len()
+}
+
+#[salsa::tracked]
+fn double_length(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log("double_length".to_string());
+    length(db, input) * 2
+}
+
+#[salsa::tracked(lru = 1)]
+fn length_without_fingerprint(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log(format!("length_without_fingerprint({})", input.text(db)));
+    input.text(db).len()
+}
+
+#[salsa::tracked]
+fn double_length_without_fingerprint(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log("double_length_without_fingerprint".to_string());
+    length_without_fingerprint(db, input) * 2
+}
+
+#[test]
+fn evicted_memo_is_backdated() {
+    let mut db = common::LoggerDatabase::default();
+    let a = MyInput::new(&db, "abc".to_string());
+    let b = MyInput::new(&db, "de".to_string());
+
+    assert_eq!(double_length(&db, a), 6);
+    assert_eq!(double_length(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "double_length",
+            "length(abc)",
+            "double_length",
+            "length(de)",
+        ]"#]]);
+
+    // Starting the new revision evicts the value of `length(a)`.
+    a.set_text(&mut db).to("xyz".to_string());
+
+    assert_eq!(double_length(&db, a), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "length(xyz)",
+        ]"#]]);
+}
+
+#[test]
+fn evicted_memo_without_fingerprint_is_not_backdated() {
+    let mut db = common::LoggerDatabase::default();
+    let a = MyInput::new(&db, "abc".to_string());
+    let b = MyInput::new(&db, "de".to_string());
+
+    assert_eq!(double_length_without_fingerprint(&db, a), 6);
+    assert_eq!(double_length_without_fingerprint(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "double_length_without_fingerprint",
+            "length_without_fingerprint(abc)",
+            "double_length_without_fingerprint",
+            "length_without_fingerprint(de)",
+        ]"#]]);
+
+    a.set_text(&mut db).to("xyz".to_string());
+
+    assert_eq!(double_length_without_fingerprint(&db, a), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "double_length_without_fingerprint",
+            "length_without_fingerprint(xyz)",
+        ]"#]]);
+}
+
+#[test]
+fn changed_fingerprint_is_not_backdated() {
+    let mut db = common::LoggerDatabase::default();
+    let a = MyInput::new(&db, "abc".to_string());
+
+    assert_eq!(double_length(&db, a), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "double_length",
+            "length(abc)",
+        ]"#]]);
+
+    a.set_text(&mut db).to("wxyz".to_string());
+
+    assert_eq!(double_length(&db, a), 8);
+    db.assert_logs(expect![[r#"
+        [
+            "length(wxyz)",
+            "double_length",
+        ]"#]]);
+}
@@ -60,10 +60,14 @@ fn test_intern_new() {
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(400)), revision: R1 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(401)), revision: R2 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
         ]"#]]);
 }
 
@@ -84,6 +88,7 @@ fn test_reintern() {
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(400)), revision: R1 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
         ]"#]]);
 
     assert_eq!(result_in_rev_1.field1(&db).0, 0);
@@ -95,9 +100,12 @@ fn test_reintern() {
     db.assert_logs(expect![[r#"
         [
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidValidateInternedValue { key: Interned(Id(400)), revision: R2 }",
+            "DidExecute { database_key: function(Id(0)), backdated: true }",
         ]"#]]);
 
     assert_eq!(result_in_rev_2.field1(&db).0, 0);
@@ -128,7 +136,10 @@ fn test_durability() {
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(400)), revision: R1 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "DidValidateMemoizedValue { database_key: function(Id(0)) }",
         ]"#]]);
@@ -199,68 +210,112 @@ fn test_reuse() {
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(400)), revision: R1 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(401)), revision: R2 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R3 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
             "DidInternValue { key: Interned(Id(402)), revision: R3 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R4 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(400)), revision: R1 }",
             "DidReuseInternedValue { key: Interned(Id(400g1)), revision: R4 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R5 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(401)), revision: R2 }",
             "DidReuseInternedValue { key: Interned(Id(401g1)), revision: R5 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R6 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(402)), revision: R3 }",
             "DidReuseInternedValue { key: Interned(Id(402g1)), revision: R6 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R7 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(400g1)), revision: R4 }",
             "DidReuseInternedValue { key: Interned(Id(400g2)), revision: R7 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R8 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(401g1)), revision: R5 }",
             "DidReuseInternedValue { key: Interned(Id(401g2)), revision: R8 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R9 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(402g1)), revision: R6 }",
             "DidReuseInternedValue { key: Interned(Id(402g2)), revision: R9 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R10 }",
+            "DidSetInputField { key: Input.field1(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(0)) }",
+            "DidGarbageCollectInterned { key: Interned(Id(400g2)), revision: R7 }",
             "DidReuseInternedValue { key: Interned(Id(400g3)), revision: R10 }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(1)) }",
             "DidInternValue { key: Interned(Id(403)), revision: R10 }",
+            "DidExecute { database_key: function(Id(1)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(2)) }",
             "DidInternValue { key: Interned(Id(404)), revision: R10 }",
+            "DidExecute { database_key: function(Id(2)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(3)) }",
             "DidInternValue { key: Interned(Id(405)), revision: R10 }",
+            "DidExecute { database_key: function(Id(3)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(4)) }",
             "DidInternValue { key: Interned(Id(406)), revision: R10 }",
+            "DidExecute { database_key: function(Id(4)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(5)) }",
             "DidInternValue { key: Interned(Id(407)), revision: R10 }",
+            "DidExecute { database_key: function(Id(5)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(6)) }",
             "DidInternValue { key: Interned(Id(408)), revision: R10 }",
+            "DidExecute { database_key: function(Id(6)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(7)) }",
             "DidValidateInternedValue { key: Interned(Id(401g2)), revision: R10 }",
+            "DidExecute { database_key: function(Id(7)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(8)) }",
             "DidValidateInternedValue { key: Interned(Id(402g2)), revision: R10 }",
+            "DidExecute { database_key: function(Id(8)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: function(Id(9)) }",
+            "DidExecute { database_key: function(Id(9)), backdated: false }",
         ]"#]]);
 }
 
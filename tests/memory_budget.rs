@@ -0,0 +1,118 @@
+#![cfg(feature = "inventory")]
+
+//! Test the eviction of memoized values across tracked functions with a memory budget.
+
+mod common;
+use common::{format_event_kind, HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{Database as _, EventKind, Storage};
+use test_log::test;
+
+/// Database that logs the execution and eviction events.
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        let logger = Logger::default();
+        Self {
+            storage: Storage::new(Some(Box::new({
+                let logger = logger.clone();
+                move |event| match event.kind {
+                    EventKind::DidExecute { .. } | EventKind::DidEvictLru { .. } => {
+                        logger.push_log_with(move || format_event_kind(&event.kind));
+                    }
+                    _ => {}
+                }
+            }))),
+            logger,
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input(debug)]
+struct MyInput {
+    len: usize,
+}
+
+fn vec_heap_size(value: &Vec<u8>) -> usize {
+    value.capacity()
+}
+
+#[salsa::tracked(returns(ref), heap_size = vec_heap_size)]
+fn bytes(db: &dyn LogDatabase, input: MyInput) -> Vec<u8> {
+    vec![0; input.len(db)]
+}
+
+#[salsa::tracked(returns(ref), heap_size = vec_heap_size)]
+fn other_bytes(db: &dyn LogDatabase, input: MyInput) -> Vec<u8> {
+    vec![1; input.len(db)]
+}
+
+#[test]
+fn evicts_least_recently_used_across_functions() {
+    let mut db = Database::default();
+    db.set_memory_budget(Some(2100));
+    let a = MyInput::new(&db, 1000);
+    let b = MyInput::new(&db, 1000);
+    let c = MyInput::new(&db, 1000);
+
+    bytes(&db, a);
+    other_bytes(&db, b);
+    bytes(&db, c);
+    bytes(&db, a);
+    db.trigger_lru_eviction();
+
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: bytes(Id(0)), backdated: false }",
+            "DidExecute { database_key: other_bytes(Id(1)), backdated: false }",
+            "DidExecute { database_key: bytes(Id(2)), backdated: false }",
+            "DidEvictLru { database_key: other_bytes(Id(1)) }",
+        ]"#]]);
+
+    db.set_memory_budget(Some(1100));
+    db.trigger_lru_eviction();
+    db.assert_logs(expect![[r#"
+        [
+            "DidEvictLru { database_key: bytes(Id(2)) }",
+        ]"#]]);
+
+    // The evicted values are recomputed when needed.
+    bytes(&db, a);
+    other_bytes(&db, b);
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: other_bytes(Id(1)), backdated: false }",
+        ]"#]]);
+}
+
+#[test]
+fn no_budget() {
+    let mut db = Database::default();
+    let a = MyInput::new(&db, 1000);
+    let b = MyInput::new(&db, 1000);
+
+    bytes(&db, a);
+    bytes(&db, b);
+    db.trigger_lru_eviction();
+
+    db.assert_logs(expect![[r#"
+        [
+            "DidExecute { database_key: bytes(Id(0)), backdated: false }",
+            "DidExecute { database_key: bytes(Id(1)), backdated: false }",
+        ]"#]]);
+}
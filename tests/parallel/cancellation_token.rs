@@ -0,0 +1,92 @@
+// Shuttle doesn't like panics inside of its runtime.
+#![cfg(not(feature = "shuttle"))]
+
+//! Test cancelling a single handle with a cancellation token, while
+//! another handle is blocked on the query it executes.
+//!
+//! ```text
+//! Thread T1 (token)          Thread T2
+//! -----------------          ---------
+//! query_a()
+//! signal stage 1             query_a() blocks on T1, signals stage 2
+//! wait for stage 2              |
+//! unwinds with `Requested`      v
+//!                            executes query_a() itself
+//! ```
+
+use salsa::{CancellationToken, Cancelled};
+
+use crate::setup::{Knobs, KnobsDatabase};
+use crate::sync::thread;
+
+#[salsa::tracked]
+fn query_a(db: &dyn KnobsDatabase) -> u32 {
+    db.signal(1);
+    db.wait_for(2);
+    db.unwind_if_revision_cancelled();
+    1
+}
+
+#[salsa::tracked]
+fn query_b(db: &dyn KnobsDatabase) -> u32 {
+    db.unwind_if_revision_cancelled();
+    2
+}
+
+#[salsa::tracked]
+fn query_panics(db: &dyn KnobsDatabase) -> u32 {
+    db.signal(1);
+    db.wait_for(2);
+    panic!("query_panics panicked")
+}
+
+#[test]
+fn the_test() {
+    let db = Knobs::default();
+    let token = CancellationToken::new();
+    let mut db_t1 = db.clone();
+    db_t1.set_cancellation_token(Some(token.clone()));
+    let db_t2 = db.clone();
+    db.signal_on_will_block(2);
+
+    let t1 = thread::spawn(move || Cancelled::catch(|| query_a(&db_t1)));
+    db.wait_for(1);
+    token.cancel();
+    let t2 = thread::spawn(move || query_a(&db_t2));
+
+    assert!(matches!(
+        t1.join().unwrap(),
+        Err(Cancelled::Requested { .. })
+    ));
+    assert_eq!(t2.join().unwrap(), 1);
+}
+
+/// A panic of a thread that caught a cancellation before isn't mistaken for a cancellation:
+/// the threads blocked on the panicking query don't execute it themselves.
+#[test]
+fn panic_after_caught_cancellation() {
+    let db = Knobs::default();
+    let token = CancellationToken::new();
+    token.cancel();
+    let mut db_cancelled = db.clone();
+    db_cancelled.set_cancellation_token(Some(token));
+    let db_t1 = db.clone();
+    let db_t2 = db.clone();
+    db.signal_on_will_block(2);
+
+    let t1 = thread::spawn(move || {
+        assert!(matches!(
+            Cancelled::catch(|| query_b(&db_cancelled)),
+            Err(Cancelled::Requested { .. })
+        ));
+        query_panics(&db_t1)
+    });
+    db.wait_for(1);
+    let t2 = thread::spawn(move || Cancelled::catch(|| query_panics(&db_t2)));
+
+    assert!(t1.join().is_err());
+    assert!(matches!(
+        t2.join().unwrap(),
+        Err(Cancelled::PropagatedPanic { .. })
+    ));
+}
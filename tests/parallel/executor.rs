@@ -0,0 +1,97 @@
+#![allow(dead_code)]
+
+//! A minimal single-threaded executor, to test the async integration without depending
+//! on an async runtime.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Wake, Waker};
+
+use super::sync::{Arc, Condvar, Mutex};
+
+/// Executes futures on the current thread, polling them in the order they were woken.
+#[derive(Default)]
+pub(crate) struct LocalExecutor<'a> {
+    tasks: Vec<Option<Pin<Box<dyn Future<Output = ()> + 'a>>>>,
+    queue: Arc<ReadyQueue>,
+}
+
+/// The indices of the tasks that were woken.
+#[derive(Default)]
+struct ReadyQueue {
+    ready: Mutex<VecDeque<usize>>,
+    cond_var: Condvar,
+}
+
+struct TaskWaker {
+    task: usize,
+    queue: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.queue.ready.lock().unwrap().push_back(self.task);
+        self.queue.cond_var.notify_one();
+    }
+}
+
+impl<'a> LocalExecutor<'a> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `future` as a task, which is polled for the first time after the tasks
+    /// spawned before it.
+    pub(crate) fn spawn(&mut self, future: impl Future<Output = ()> + 'a) {
+        let task = self.tasks.len();
+        self.tasks.push(Some(Box::pin(future)));
+        self.queue.ready.lock().unwrap().push_back(task);
+    }
+
+    /// Polls the tasks until all of them completed.
+    ///
+    /// The thread is only parked while none of the tasks is woken.
+    pub(crate) fn run(&mut self) {
+        while self.tasks.iter().any(Option::is_some) {
+            let task = {
+                let mut ready = self.queue.ready.lock().unwrap();
+                loop {
+                    match ready.pop_front() {
+                        Some(task) => break task,
+                        None => ready = self.queue.cond_var.wait(ready).unwrap(),
+                    }
+                }
+            };
+            let Some(future) = &mut self.tasks[task] else {
+                continue;
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                task,
+                queue: self.queue.clone(),
+            }));
+            if let Poll::Ready(()) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                self.tasks[task] = None;
+            }
+        }
+    }
+}
+
+/// Returns a future that returns [`Poll::Pending`] once, so that the other woken tasks
+/// are polled before the task continues.
+pub(crate) fn yield_now() -> impl Future<Output = ()> {
+    let mut yielded = false;
+    std::future::poll_fn(move |cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+}
@@ -1,8 +1,10 @@
 #![cfg(feature = "inventory")]
 
+mod executor;
 mod setup;
 mod signal;
 
+mod cancellation_token;
 mod cycle_a_t1_b_t2;
 mod cycle_a_t1_b_t2_fallback;
 mod cycle_ab_peeping_c;
@@ -14,6 +16,11 @@ mod cycle_provisional_depending_on_itself;
 mod parallel_cancellation;
 mod parallel_join;
 mod parallel_map;
+mod prefetch;
+mod run_async;
+mod try_set;
+mod try_set_timeout;
+mod wait_graph;
 
 #[cfg(not(feature = "shuttle"))]
 pub(crate) mod sync {
@@ -17,8 +17,9 @@ fn tracked_fn(db: &dyn salsa::Database, input: ParallelInput) -> Vec<u32> {
 
 #[salsa::tracked]
 fn a1(db: &dyn KnobsDatabase, input: ParallelInput) -> Vec<u32> {
-    db.signal(1);
     salsa::par_map(db, input.field(db), |db, field| {
+        // The database is forked for every field before the first one is mapped.
+        db.signal(1);
         db.wait_for(2);
         field + dummy(db)
     })
@@ -58,7 +59,7 @@ fn direct_calls_panic() {
 //
 // Thread A                   Thread B
 // --------                   --------
-// a1
+// a1, par_map
 // |                          wait for stage 1
 // signal stage 1             set input, triggers cancellation
 // wait for stage 2 (blocks)  triggering cancellation sends stage 2
@@ -82,6 +83,7 @@ fn execute_cancellation() {
 
     let counts = (2..=20).collect::<Vec<u32>>();
 
+    db.wait_for(1);
     db.signal_on_did_cancel(2);
     input.set_field(&mut db).to(counts);
 
@@ -0,0 +1,57 @@
+// Shuttle doesn't like panics inside of its runtime.
+#![cfg(not(feature = "shuttle"))]
+
+//! Test that `Database::prefetch` skips queries that are executing on another thread.
+//!
+//! ```text
+//! Thread T1                  Main thread
+//! ---------                  -----------
+//! query(a)
+//! signal stage 1             prefetch(a, b): skips a, computes b
+//! wait for stage 2           signal stage 2
+//! returns
+//! ```
+
+use salsa::Database;
+
+use crate::setup::{Knobs, KnobsDatabase};
+use crate::sync::thread;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+    blocks: bool,
+}
+
+#[salsa::tracked]
+fn query(db: &dyn KnobsDatabase, input: MyInput) -> u32 {
+    if input.blocks(db) {
+        db.signal(1);
+        db.wait_for(2);
+    }
+    input.field(db)
+}
+
+#[test]
+fn skips_running_queries() {
+    let db = Knobs::default();
+    let a = MyInput::new(&db, 1, true);
+    let b = MyInput::new(&db, 2, false);
+
+    let t1 = thread::spawn({
+        let db = db.clone();
+        move || query(&db, a)
+    });
+    db.wait_for(1);
+
+    let prefetched = db.prefetch([a, b], |db, input| {
+        query(db, input);
+    });
+    assert_eq!(prefetched.completed(), 1);
+    assert_eq!(prefetched.skipped(), 1);
+
+    db.signal(2);
+    assert_eq!(t1.join().unwrap(), 1);
+    assert_eq!(query(&db, a), 1);
+    assert_eq!(query(&db, b), 2);
+}
@@ -0,0 +1,200 @@
+// Shuttle doesn't like panics inside of its runtime.
+#![cfg(not(feature = "shuttle"))]
+
+//! Test that `run_async` yields to the executor instead of blocking on another thread.
+//!
+//! ```text
+//! Thread T1                  Main thread (local executor)
+//! ---------                  ----------------------------
+//! query_a()
+//! signal stage 1             task 1: query_b() yields on query_a() in T1
+//! wait for stage 2           task 2: signal stage 2
+//! returns                       |
+//!                            task 1 is woken, query_b() fetches query_a()
+//! ```
+
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Waker};
+
+use salsa::{CancellationToken, Cancelled, Database, SalsaError, Setter};
+
+use crate::executor::{yield_now, LocalExecutor};
+use crate::setup::{Knobs, KnobsDatabase};
+use crate::sync::thread;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn query_a(db: &dyn KnobsDatabase, input: MyInput) -> u32 {
+    db.signal(1);
+    db.wait_for(2);
+    db.unwind_if_revision_cancelled();
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn query_b(db: &dyn KnobsDatabase, input: MyInput) -> u32 {
+    query_a(db, input) + 1
+}
+
+#[salsa::tracked]
+fn query_panics(db: &dyn KnobsDatabase) -> u32 {
+    db.signal(3);
+    db.wait_for(4);
+    panic!("query_panics panicked")
+}
+
+#[test]
+fn yields_instead_of_blocking() {
+    let db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let t1 = thread::spawn({
+        let db = db.clone();
+        move || query_a(&db, input)
+    });
+    db.wait_for(1);
+
+    let result = Cell::new(None);
+    let mut executor = LocalExecutor::new();
+    executor.spawn(async {
+        let value = db.clone().run_async(|db| query_b(db, input)).await;
+        result.set(Some(value.unwrap()));
+    });
+    executor.spawn(async {
+        // Task 1 yielded, otherwise this task would never be polled.
+        assert_eq!(result.get(), None);
+        db.signal(2);
+    });
+    executor.run();
+
+    assert_eq!(result.get(), Some(2));
+    assert_eq!(t1.join().unwrap(), 1);
+}
+
+#[test]
+fn completes_without_contention() {
+    let db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+    db.signal(2);
+
+    let mut executor = LocalExecutor::new();
+    executor.spawn(async {
+        let value = db.clone().run_async(|db| query_b(db, input)).await;
+        assert_eq!(value.unwrap(), 2);
+    });
+    executor.run();
+}
+
+#[test]
+fn cancellation_token_wakes_pending_future() {
+    let db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let t1 = thread::spawn({
+        let db = db.clone();
+        move || query_a(&db, input)
+    });
+    db.wait_for(1);
+
+    let token = CancellationToken::new();
+    let mut db_async = db.clone();
+    db_async.set_cancellation_token(Some(token.clone()));
+
+    let mut executor = LocalExecutor::new();
+    executor.spawn(async {
+        let value = db_async.run_async(|db| query_b(db, input)).await;
+        assert!(matches!(
+            value,
+            Err(SalsaError::Cancelled(Cancelled::Requested { .. }))
+        ));
+    });
+    executor.spawn(async {
+        yield_now().await;
+        token.cancel();
+    });
+    executor.run();
+
+    db.signal(2);
+    assert_eq!(t1.join().unwrap(), 1);
+}
+
+#[test]
+fn pending_write_wakes_pending_future() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let t1 = thread::spawn({
+        let db = db.clone();
+        move || Cancelled::catch(|| query_a(&db, input))
+    });
+    db.wait_for(1);
+
+    let mut executor = LocalExecutor::new();
+    executor.spawn({
+        let db = db.clone();
+        async move {
+            let value = db.run_async(|db| query_b(db, input)).await;
+            assert!(matches!(
+                value,
+                Err(SalsaError::Cancelled(Cancelled::PendingWrite { .. }))
+            ));
+        }
+    });
+
+    let writer = thread::spawn(move || {
+        db.signal_on_did_cancel(2);
+        input.set_field(&mut db).to(2);
+        db
+    });
+    executor.run();
+
+    let db = writer.join().unwrap();
+    assert!(matches!(
+        t1.join().unwrap(),
+        Err(Cancelled::PendingWrite { .. })
+    ));
+    assert_eq!(query_b(&db, input), 3);
+}
+
+/// A panic of a thread whose future yielded before isn't mistaken for a cancellation of its
+/// handle: the threads blocked on the panicking query don't execute it themselves.
+#[test]
+fn panic_after_yield() {
+    let db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+    let db_t3 = db.clone();
+
+    let t1 = thread::spawn({
+        let db = db.clone();
+        move || query_a(&db, input)
+    });
+    db.wait_for(1);
+
+    let t2 = thread::spawn({
+        let db = db.clone();
+        move || {
+            let mut future = pin!(db.clone().run_async(|db| query_b(db, input)));
+            let poll = future
+                .as_mut()
+                .poll(&mut Context::from_waker(Waker::noop()));
+            assert!(poll.is_pending());
+            query_panics(&db)
+        }
+    });
+    db.wait_for(3);
+    db.signal_on_will_block(4);
+    let t3 = thread::spawn(move || Cancelled::catch(|| query_panics(&db_t3)));
+
+    assert_eq!(t1.join().unwrap(), 1);
+    assert!(t2.join().is_err());
+    assert!(matches!(
+        t3.join().unwrap(),
+        Err(Cancelled::PropagatedPanic { .. })
+    ));
+}
@@ -43,6 +43,10 @@ impl Knobs {
     pub fn signal_on_will_block(&self, stage: usize) {
         self.signal_on_will_block.store(stage, Ordering::Release);
     }
+
+    pub fn set_cancellation_token(&mut self, token: Option<salsa::CancellationToken>) {
+        self.storage.set_cancellation_token(token);
+    }
 }
 
 impl Clone for Knobs {
@@ -0,0 +1,134 @@
+// Shuttle doesn't like panics inside of its runtime, and the timeouts of the setters depend
+// on real time, which shuttle does not model.
+#![cfg(not(feature = "shuttle"))]
+
+//! Test the `try_set_<field>` setters while another thread executes a query.
+//!
+//! ```text
+//! Thread A                   Thread B
+//! --------                   --------
+//! a1
+//! |                          wait for stage 1
+//! signal stage 1             try set input, triggers cancellation
+//! wait for stage 2 (blocks)  triggering cancellation sends stage 2
+//! |
+//! (unblocked)
+//! unwinds with `PendingWrite`
+//! ```
+
+use std::time::Duration;
+
+use salsa::{Cancelled, Setter};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    db.unwind_if_revision_cancelled();
+    input.field(db)
+}
+
+#[test]
+fn cancels_other_handle() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || Cancelled::catch(|| a1(&db, input))
+    });
+
+    db.wait_for(1);
+    assert_eq!(
+        input
+            .try_set_field(&mut db, Duration::ZERO)
+            .err()
+            .unwrap()
+            .live_handles(),
+        1
+    );
+
+    db.signal_on_did_cancel(2);
+    input
+        .try_set_field(&mut db, Duration::from_secs(60))
+        .unwrap()
+        .to(2);
+
+    assert!(matches!(
+        thread_a.join().unwrap(),
+        Err(Cancelled::PendingWrite { .. })
+    ));
+}
+
+#[test]
+fn times_out() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let db_b = db.clone();
+    let thread_b = std::thread::spawn(move || {
+        // Hold on to the handle without executing queries.
+        db_b.wait_for(1);
+        drop(db_b);
+    });
+
+    let error = input
+        .try_set_field(&mut db, Duration::from_millis(10))
+        .err()
+        .unwrap();
+    assert_eq!(error.live_handles(), 1);
+
+    db.signal(1);
+    thread_b.join().unwrap();
+    input
+        .try_set_field(&mut db, Duration::from_secs(60))
+        .unwrap()
+        .to(2);
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(3);
+    db.unwind_if_revision_cancelled();
+    input.field(db)
+}
+
+#[test]
+fn time_out_keeps_cancellation_of_waiting_writer() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_reader = std::thread::spawn({
+        let db = db.clone();
+        move || Cancelled::catch(|| b1(&db, input))
+    });
+    db.wait_for(1);
+
+    let mut db_writer = db.clone();
+    db.signal_on_did_cancel(2);
+    let thread_writer = std::thread::spawn(move || input.set_field(&mut db_writer).to(2));
+    db.wait_for(2);
+
+    // Giving up must not reset the cancellation flag set by the waiting writer.
+    let error = input
+        .try_set_field(&mut db, Duration::from_millis(10))
+        .err()
+        .unwrap();
+    assert_eq!(error.live_handles(), 2);
+    db.signal(3);
+    drop(db);
+
+    assert!(matches!(
+        thread_reader.join().unwrap(),
+        Err(Cancelled::PendingWrite { .. })
+    ));
+    thread_writer.join().unwrap();
+}
@@ -0,0 +1,46 @@
+//! Test that the `try_set_<field>` setters either time out or wait for the other handles
+//! to be dropped.
+//!
+//! ```text
+//! Thread A                           Thread B
+//! --------                           --------
+//! try set input with a timeout       drop the handle
+//! (times out or waits for thread B)
+//! ```
+
+use std::time::Duration;
+
+use salsa::Setter;
+
+use crate::sync::thread;
+use crate::Knobs;
+
+#[salsa::input(debug)]
+struct MyInput {
+    field: i32,
+}
+
+#[test]
+fn times_out_or_waits_for_dropped_handle() {
+    crate::sync::check(|| {
+        let mut db = Knobs::default();
+        let input = MyInput::new(&db, 1);
+
+        let db_b = db.clone();
+        let thread_b = thread::spawn(move || drop(db_b));
+
+        match input.try_set_field(&mut db, Duration::from_secs(60)) {
+            Ok(setter) => {
+                setter.to(2);
+            }
+            Err(error) => assert_eq!(error.live_handles(), 1),
+        }
+
+        thread_b.join().unwrap();
+        input
+            .try_set_field(&mut db, Duration::from_secs(60))
+            .unwrap()
+            .to(3);
+        assert_eq!(input.field(&db), 3);
+    });
+}
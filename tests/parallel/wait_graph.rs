@@ -0,0 +1,68 @@
+#![cfg(not(feature = "shuttle"))]
+
+//! Test the wait graph of threads blocked on queries executing on other threads.
+//!
+//! ```text
+//! Thread T1          Thread T2
+//! ---------          ---------
+//! query_a()             |
+//!    |                  v
+//!    |               query_b()
+//!    |                  |
+//!    +<-----------------+ blocks on query_a
+//! ```
+
+use std::time::Duration;
+
+use salsa::Database;
+
+use crate::sync::thread;
+use crate::{Knobs, KnobsDatabase};
+
+// Signal 1: T1 has entered `query_a`
+// Signal 2: T2 is about to block on `query_a`
+// Signal 3: The wait graph has been inspected
+
+#[salsa::tracked]
+fn query_a(db: &dyn KnobsDatabase) -> u32 {
+    db.signal(1);
+    db.wait_for(3);
+    1
+}
+
+#[salsa::tracked]
+fn query_b(db: &dyn KnobsDatabase) -> u32 {
+    query_a(db) + 1
+}
+
+#[test_log::test]
+fn the_test() {
+    let mut db = Knobs::default();
+    // Time out immediately, so that the watchdog logs the wait graph.
+    db.set_blocking_watchdog(Some(Duration::from_nanos(1)));
+    let db_t1 = db.clone();
+    let db_t2 = db.clone();
+    db.signal_on_will_block(2);
+
+    let t1 = thread::spawn(move || query_a(&db_t1));
+    db.wait_for(1);
+    let t2 = thread::spawn(move || query_b(&db_t2));
+    db.wait_for(2);
+
+    let graph = (&db as &dyn Database).wait_graph();
+    let [blocked] = graph.blocked_threads() else {
+        panic!("expected a single blocked thread:\n{graph}");
+    };
+    assert_eq!(blocked.thread_id(), t2.thread().id());
+    assert_eq!(blocked.other_thread_id(), t1.thread().id());
+    assert!(blocked.blocked_for().is_some());
+    salsa::attach(&db, || {
+        assert_eq!(format!("{:?}", blocked.database_key()), "query_a(Id(0))");
+        assert_eq!(format!("{:?}", blocked.query_stack()), "[query_b(Id(400))]");
+    });
+
+    db.signal(3);
+    assert_eq!(t1.join().unwrap(), 1);
+    assert_eq!(t2.join().unwrap(), 2);
+    assert!((&db as &dyn Database).wait_graph().is_empty());
+}
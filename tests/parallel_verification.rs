@@ -0,0 +1,209 @@
+#![cfg(feature = "inventory")]
+
+//! Test the parallel verification of the inputs of memos.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{CurrentThreadExecutor, Database as _, EventKind, Executor, Setter, Storage, Task};
+use test_log::test;
+
+/// Executor that executes the closures in reverse order on the calling thread.
+struct ReverseExecutor;
+
+impl Executor for ReverseExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        b();
+        a();
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        (0..len).rev().for_each(op);
+    }
+}
+
+/// Database that verifies memos in parallel with the given executor and logs the executed
+/// and validated queries.
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Database {
+    fn new(executor: impl Executor) -> Self {
+        let logger = Logger::default();
+        let mut db = Self {
+            storage: Storage::builder()
+                .executor(executor)
+                .event_callback(Box::new({
+                    let logger = logger.clone();
+                    move |event| match event.kind {
+                        EventKind::WillExecute { database_key } => {
+                            logger.push_log(format!("{database_key:?}"));
+                        }
+                        EventKind::DidValidateMemoizedValue { database_key } => {
+                            logger.push_log(format!("validated {database_key:?}"));
+                        }
+                        _ => {}
+                    }
+                }))
+                .build(),
+            logger,
+        };
+        db.set_parallel_verification(Some(2));
+        db
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input(debug)]
+struct Item {
+    value: u32,
+}
+
+#[salsa::input(debug)]
+struct List {
+    len: usize,
+    #[returns(ref)]
+    items: Vec<Item>,
+}
+
+#[salsa::tracked]
+fn value(db: &dyn salsa::Database, item: Item) -> u32 {
+    let value = item.value(db);
+    assert_ne!(value, 0, "items in the list have a value");
+    value
+}
+
+#[salsa::tracked]
+fn is_even(db: &dyn salsa::Database, item: Item) -> bool {
+    value(db, item) % 2 == 0
+}
+
+#[salsa::tracked]
+fn count_even(db: &dyn salsa::Database, list: List) -> usize {
+    list.items(db)[..list.len(db)]
+        .iter()
+        .filter(|&&item| is_even(db, item))
+        .count()
+}
+
+fn new_list(db: &dyn salsa::Database, values: &[u32]) -> (List, Vec<Item>) {
+    let items = values
+        .iter()
+        .map(|&value| Item::new(db, value))
+        .collect::<Vec<_>>();
+    (List::new(db, items.len(), items.clone()), items)
+}
+
+#[test]
+fn reuses_unchanged_inputs() {
+    let mut db = Database::new(CurrentThreadExecutor);
+    let (list, items) = new_list(&db, &[1, 2, 3]);
+    assert_eq!(count_even(&db, list), 1);
+    db.assert_logs_len(7);
+
+    // The parity of the second value does not change.
+    items[1].set_value(&mut db).to(4);
+    assert_eq!(count_even(&db, list), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "validated value(Id(0))",
+            "validated is_even(Id(0))",
+            "validated value(Id(2))",
+            "validated is_even(Id(2))",
+            "value(Id(1))",
+            "is_even(Id(1))",
+            "validated count_even(Id(400))",
+        ]"#]]);
+
+    // The later inputs are verified in parallel even though the first input changed, but
+    // the third item is only executed again by `count_even`.
+    items[0].set_value(&mut db).to(2);
+    items[2].set_value(&mut db).to(5);
+    assert_eq!(count_even(&db, list), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "validated value(Id(1))",
+            "validated is_even(Id(1))",
+            "value(Id(0))",
+            "is_even(Id(0))",
+            "count_even(Id(400))",
+            "value(Id(2))",
+            "is_even(Id(2))",
+        ]"#]]);
+}
+
+#[test]
+fn does_not_execute_queries_in_parallel() {
+    let mut db = Database::new(ReverseExecutor);
+    let (list, items) = new_list(&db, &[1, 2, 3]);
+    assert_eq!(count_even(&db, list), 1);
+    db.assert_logs_len(7);
+
+    // `value` would panic for the third item, which is no longer in the list once the
+    // changed length has been verified. The memo of `is_even` for it is verified in
+    // parallel, but `value` needs to be executed to verify it, so it is verified in order,
+    // and never once the changed length has been verified.
+    list.set_len(&mut db).to(2);
+    items[2].set_value(&mut db).to(0);
+    assert_eq!(count_even(&db, list), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "validated value(Id(1))",
+            "validated is_even(Id(1))",
+            "validated value(Id(0))",
+            "validated is_even(Id(0))",
+            "count_even(Id(400))",
+        ]"#]]);
+}
+
+#[test]
+fn verifies_dependencies_deeply_in_parallel() {
+    let mut db = Database::new(ReverseExecutor);
+    let (list, _) = new_list(&db, &[1, 2, 3]);
+    assert_eq!(count_even(&db, list), 1);
+    db.assert_logs_len(7);
+
+    // The memos of `is_even` depend on the unchanged items, but need to be verified deeply.
+    // The executor verifies the inputs of `count_even` last to first.
+    Item::new(&db, 4).set_value(&mut db).to(5);
+    assert_eq!(count_even(&db, list), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "validated value(Id(2))",
+            "validated is_even(Id(2))",
+            "validated value(Id(1))",
+            "validated is_even(Id(1))",
+            "validated value(Id(0))",
+            "validated is_even(Id(0))",
+            "validated count_even(Id(400))",
+        ]"#]]);
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn rayon_executor() {
+    let mut db = salsa::DatabaseImpl::new();
+    db.set_parallel_verification(Some(2));
+    let (list, items) = new_list(&db, &(1..=100).collect::<Vec<_>>());
+    assert_eq!(count_even(&db, list), 50);
+
+    for (index, item) in items.iter().enumerate() {
+        item.set_value(&mut db).to(index as u32 * 2 + 2);
+    }
+    assert_eq!(count_even(&db, list), 100);
+
+    items[99].set_value(&mut db).to(1);
+    assert_eq!(count_even(&db, list), 99);
+}
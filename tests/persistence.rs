@@ -0,0 +1,187 @@
+#![cfg(feature = "inventory")]
+
+//! Test that memos of `tracked(persist)` functions can be
+//! written out and restored into a fresh database.
+
+mod common;
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::{Database, PersistenceError, Setter};
+use test_log::test;
+
+#[salsa::input(stable_id)]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(persist)]
+fn double(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked(persist)]
+fn describe(db: &dyn salsa::Database, input: MyInput) -> Option<String> {
+    Some(format!(
+        "{} doubled is {}",
+        input.field(db),
+        double(db, input)
+    ))
+}
+
+#[salsa::input(stable_id = name_key)]
+struct NamedInput {
+    name: String,
+    field: u32,
+}
+
+fn name_key(fields: &(String, u32)) -> &str {
+    &fields.0
+}
+
+#[salsa::tracked(persist)]
+fn named_double(db: &dyn salsa::Database, input: NamedInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn not_persisted(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) + 1
+}
+
+fn persisted_db() -> Vec<u8> {
+    let db = common::ExecuteValidateLoggerDatabase::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    assert_eq!(describe(&db, a).as_deref(), Some("1 doubled is 2"));
+    assert_eq!(double(&db, b), 4);
+    assert_eq!(not_persisted(&db, b), 3);
+    db.persist_memos()
+}
+
+#[test]
+fn restored_memos_are_reused() {
+    let data = persisted_db();
+
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    assert_eq!(db.restore_memos(&data), Ok(3));
+
+    assert_eq!(describe(&db, a).as_deref(), Some("1 doubled is 2"));
+    assert_eq!(double(&db, b), 4);
+    assert_eq!(not_persisted(&db, b), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: describe(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(1)) })",
+            "salsa_event(WillExecute { database_key: not_persisted(Id(1)) })",
+        ]"#]]);
+}
+
+#[test]
+fn inputs_are_matched_by_stable_id() {
+    let data = persisted_db();
+
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let b = MyInput::new(&db, 2);
+    let a = MyInput::new(&db, 1);
+    assert_eq!(db.restore_memos(&data), Ok(3));
+
+    assert_eq!(describe(&db, a).as_deref(), Some("1 doubled is 2"));
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(1)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: describe(Id(1)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(0)) })",
+        ]"#]]);
+}
+
+#[test]
+fn restored_memos_are_invalidated() {
+    let data = persisted_db();
+
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    assert_eq!(db.restore_memos(&data), Ok(3));
+
+    a.set_field(&mut db).to(10);
+    assert_eq!(describe(&db, a).as_deref(), Some("10 doubled is 20"));
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: describe(Id(0)) })",
+            "salsa_event(WillExecute { database_key: double(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(1)) })",
+        ]"#]]);
+}
+
+#[test]
+fn missing_inputs_are_skipped() {
+    let data = persisted_db();
+
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let a = MyInput::new(&db, 1);
+    assert_eq!(db.restore_memos(&data), Ok(2));
+
+    assert_eq!(describe(&db, a).as_deref(), Some("1 doubled is 2"));
+    let b = MyInput::new(&db, 2);
+    assert_eq!(double(&db, b), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: double(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: describe(Id(0)) })",
+            "salsa_event(WillExecute { database_key: double(Id(1)) })",
+        ]"#]]);
+}
+
+#[test]
+fn invalid_data_is_rejected() {
+    let mut data = persisted_db();
+
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    MyInput::new(&db, 1);
+    MyInput::new(&db, 2);
+    assert_eq!(
+        db.restore_memos(b"not salsa data"),
+        Err(PersistenceError::UnsupportedFormat)
+    );
+
+    // Corrupt the schema fingerprint following the magic and format version.
+    data[12] ^= 0xFF;
+    assert_eq!(
+        db.restore_memos(&data),
+        Err(PersistenceError::SchemaMismatch)
+    );
+
+    data[12] ^= 0xFF;
+    data.pop();
+    assert_eq!(db.restore_memos(&data), Err(PersistenceError::Corrupted));
+}
+
+#[test]
+fn inputs_with_different_values_are_skipped() {
+    let data = {
+        let db = common::ExecuteValidateLoggerDatabase::default();
+        let a = NamedInput::new(&db, "a".to_string(), 1);
+        let b = NamedInput::new(&db, "b".to_string(), 2);
+        assert_eq!(named_double(&db, a), 2);
+        assert_eq!(named_double(&db, b), 4);
+        db.persist_memos()
+    };
+
+    let mut db = common::ExecuteValidateLoggerDatabase::default();
+    let a = NamedInput::new(&db, "a".to_string(), 1);
+    let b = NamedInput::new(&db, "b".to_string(), 3);
+    assert_eq!(db.restore_memos(&data), Ok(1));
+
+    assert_eq!(named_double(&db, a), 2);
+    assert_eq!(named_double(&db, b), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: named_double(Id(0)) })",
+            "salsa_event(WillExecute { database_key: named_double(Id(1)) })",
+        ]"#]]);
+}
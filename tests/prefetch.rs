@@ -0,0 +1,126 @@
+#![cfg(feature = "inventory")]
+
+//! Test warming queries with `Database::prefetch`.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+use expect_test::expect;
+use salsa::{CancellationToken, CurrentThreadExecutor, Database as _, EventKind, Storage};
+use test_log::test;
+
+/// Database that executes the prefetched operations on the calling thread, in order, and
+/// logs the executed queries.
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        let logger = Logger::default();
+        Self {
+            storage: Storage::builder()
+                .executor(CurrentThreadExecutor)
+                .event_callback(Box::new({
+                    let logger = logger.clone();
+                    move |event| {
+                        if let EventKind::WillExecute { database_key } = event.kind {
+                            logger.push_log(format!("{database_key:?}"));
+                        }
+                    }
+                }))
+                .build(),
+            logger,
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input(debug)]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn salsa::Database, file: File) -> usize {
+    file.text(db).lines().count()
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn salsa::Database, file: File) -> usize {
+    file.text(db).split_whitespace().count()
+}
+
+#[test]
+fn computes_queries_ahead_of_time() {
+    let db = Database::default();
+    let files = ["a b\nc", "d", "e\nf\ng"].map(|text| File::new(&db, text.to_string()));
+
+    let prefetched = db.prefetch(files, |db, file| {
+        line_count(db, file);
+    });
+    assert_eq!(prefetched.completed(), 3);
+    assert_eq!(prefetched.skipped(), 0);
+    assert_eq!(prefetched.cancelled(), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(Id(0))",
+            "line_count(Id(1))",
+            "line_count(Id(2))",
+        ]"#]]);
+
+    // The memos are reused by the handle that prefetched them.
+    assert_eq!(files.map(|file| line_count(&db, file)), [2, 1, 3]);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn prefetches_different_queries() {
+    let db = Database::default();
+    let file = File::new(&db, "a b\nc".to_string());
+
+    let queries: [fn(&Database, File) -> usize; 2] = [
+        |db, file| line_count(db, file),
+        |db, file| word_count(db, file),
+    ];
+    let prefetched = db.prefetch(queries, |db, query| {
+        query(db, file);
+    });
+    assert_eq!(prefetched.completed(), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(Id(0))",
+            "word_count(Id(0))",
+        ]"#]]);
+}
+
+#[test]
+fn stops_once_cancelled() {
+    let mut db = Database::default();
+    let files = ["a", "b", "c"].map(|text| File::new(&db, text.to_string()));
+    let token = CancellationToken::new();
+    db.storage.set_cancellation_token(Some(token.clone()));
+
+    let prefetched = db.prefetch(files.into_iter().enumerate(), |db, (index, file)| {
+        if index == 1 {
+            token.cancel();
+        }
+        line_count(db, file);
+    });
+    assert_eq!(prefetched.completed(), 1);
+    assert_eq!(prefetched.cancelled(), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "line_count(Id(0))",
+        ]"#]]);
+}
@@ -69,6 +69,8 @@ fn test_leaked_inputs_ignored() {
             "DidInternValue { key: counter_field::interned_arguments(Id(800)), revision: R1 }",
             "WillCheckCancellation",
             "WillExecute { database_key: counter_field(Id(800)) }",
+            "DidExecute { database_key: counter_field(Id(800)), backdated: false }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
         ]"#]]);
 
     assert_eq!(result_in_rev_1, (0, 0));
@@ -84,12 +86,16 @@ fn test_leaked_inputs_ignored() {
     db.assert_logs(expect![[r#"
         [
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: MyInput.field2(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "DidValidateInternedValue { key: counter_field::interned_arguments(Id(800)), revision: R2 }",
             "WillCheckCancellation",
             "WillExecute { database_key: counter_field(Id(800)) }",
+            "DidExecute { database_key: counter_field(Id(800)), backdated: true }",
             "WillExecute { database_key: function(Id(0)) }",
             "WillCheckCancellation",
+            "DidExecute { database_key: function(Id(0)), backdated: true }",
         ]"#]]);
 
     // Salsa will re-execute `counter_field` before re-executing
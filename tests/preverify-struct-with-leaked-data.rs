@@ -65,6 +65,8 @@ fn test_leaked_inputs_ignored() {
             "WillExecute { database_key: function(Id(0)) }",
             "WillCheckCancellation",
             "WillExecute { database_key: counter_field(Id(400)) }",
+            "DidExecute { database_key: counter_field(Id(400)), backdated: false }",
+            "DidExecute { database_key: function(Id(0)), backdated: false }",
         ]"#]]);
 
     assert_eq!(result_in_rev_1, (0, 0));
@@ -80,11 +82,14 @@ fn test_leaked_inputs_ignored() {
     db.assert_logs(expect![[r#"
         [
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: MyInput.field2(Id(0)), durability: Durability(0) }",
             "WillCheckCancellation",
             "WillCheckCancellation",
             "DidValidateMemoizedValue { database_key: counter_field(Id(400)) }",
             "WillExecute { database_key: function(Id(0)) }",
             "WillCheckCancellation",
+            "DidExecute { database_key: function(Id(0)), backdated: true }",
         ]"#]]);
 
     // Because salsa does not see any way for the tracked
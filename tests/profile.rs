@@ -0,0 +1,101 @@
+#![cfg(feature = "inventory")]
+
+//! Test the built-in profiler of tracked functions.
+
+use salsa::{Database, DatabaseImpl, Durability, QueryProfile, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+    #[returns(ref)]
+    unrelated: String,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input) + 1
+}
+
+fn profile<'a>(profiles: &'a [QueryProfile], debug_name: &str) -> &'a QueryProfile {
+    profiles
+        .iter()
+        .find(|profile| profile.debug_name() == debug_name)
+        .unwrap_or_else(|| panic!("no profile for {debug_name}: {profiles:#?}"))
+}
+
+#[test]
+fn executions_and_hits() {
+    let mut db = DatabaseImpl::new();
+    db.set_profiling(true);
+    let input = MyInput::new(&db, 1, String::new());
+
+    assert_eq!(outer(&db, input), 3);
+    assert_eq!(outer(&db, input), 3);
+
+    let profiles = (&db as &dyn Database).query_profiles();
+    let outer_profile = profile(&profiles, "outer");
+    assert_eq!(outer_profile.executions(), 1);
+    assert_eq!(outer_profile.shallow_hits(), 1);
+    assert_eq!(outer_profile.deep_verifications(), 0);
+    assert!(outer_profile.total_time() >= outer_profile.self_time());
+    assert!(outer_profile.total_time() >= profile(&profiles, "inner").total_time());
+    assert_eq!(profile(&profiles, "inner").executions(), 1);
+
+    // A change to an unrelated field requires a deep verification, which succeeds.
+    (&db as &dyn Database).reset_query_profiles();
+    input.set_unrelated(&mut db).to(String::from("changed"));
+    assert_eq!(outer(&db, input), 3);
+
+    let profiles = (&db as &dyn Database).query_profiles();
+    let outer_profile = profile(&profiles, "outer");
+    assert_eq!(outer_profile.executions(), 0);
+    assert_eq!(outer_profile.deep_verifications(), 1);
+    assert_eq!(outer_profile.deep_hits(), 1);
+    assert_eq!(outer_profile.hits(), 1);
+
+    // A change to the field re-executes both functions.
+    (&db as &dyn Database).reset_query_profiles();
+    input.set_field(&mut db).to(2);
+    assert_eq!(outer(&db, input), 5);
+
+    let profiles = (&db as &dyn Database).query_profiles();
+    let outer_profile = profile(&profiles, "outer");
+    assert_eq!(outer_profile.executions(), 1);
+    assert_eq!(outer_profile.deep_verifications(), 1);
+    assert_eq!(outer_profile.deep_hits(), 0);
+    assert_eq!(profile(&profiles, "inner").executions(), 1);
+}
+
+#[test]
+fn durability_shallow_hit() {
+    let mut db = DatabaseImpl::new();
+    db.set_profiling(true);
+    let input = MyInput::builder(1, String::new())
+        .durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(outer(&db, input), 3);
+
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(outer(&db, input), 3);
+
+    let profiles = (&db as &dyn Database).query_profiles();
+    let outer_profile = profile(&profiles, "outer");
+    assert_eq!(outer_profile.executions(), 1);
+    assert_eq!(outer_profile.shallow_hits(), 1);
+    assert_eq!(outer_profile.deep_verifications(), 0);
+}
+
+#[test]
+fn disabled() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1, String::new());
+    assert_eq!(outer(&db, input), 3);
+
+    assert!((&db as &dyn Database).query_profiles().is_empty());
+}
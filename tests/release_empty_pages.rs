@@ -0,0 +1,83 @@
+#![cfg(feature = "inventory")]
+
+//! Test that `Database::release_empty_pages` releases the pages of deleted tracked structs
+//! and freed interned values.
+
+use salsa::{Database, Setter};
+use test_log::test;
+
+/// The number of slots of a page.
+const PAGE_LEN: usize = 1024;
+
+#[salsa::input]
+struct Input {
+    count: usize,
+}
+
+#[salsa::tracked]
+struct Tracked<'db> {
+    value: usize,
+}
+
+#[salsa::interned(revisions = 1000)]
+struct Interned<'db> {
+    value: usize,
+}
+
+#[salsa::tracked(returns(ref))]
+fn create_tracked(db: &dyn Database, input: Input) -> Vec<Tracked<'_>> {
+    (0..input.count(db)).map(|i| Tracked::new(db, i)).collect()
+}
+
+#[salsa::tracked]
+fn sum_tracked(db: &dyn Database, input: Input) -> usize {
+    create_tracked(db, input)
+        .iter()
+        .map(|tracked| tracked.value(db))
+        .sum()
+}
+
+#[salsa::tracked]
+fn sum_interned(db: &dyn Database, input: Input) -> usize {
+    (0..input.count(db))
+        .map(|i| Interned::new(db, i).value(db))
+        .sum()
+}
+
+#[test]
+fn releases_pages_of_deleted_tracked_structs() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = Input::new(&db, 2 * PAGE_LEN);
+    assert_eq!(sum_tracked(&db, input), (0..2 * PAGE_LEN).sum());
+    assert_eq!(db.release_empty_pages(), 0);
+
+    // Deletes all tracked structs.
+    input.set_count(&mut db).to(0);
+    assert_eq!(sum_tracked(&db, input), 0);
+
+    let released = db.release_empty_pages();
+    assert!(released >= 2 * PAGE_LEN, "released {released} bytes");
+    assert_eq!(db.release_empty_pages(), 0);
+    let _ = (&db as &dyn Database).dependency_graph();
+
+    // New tracked structs are allocated on new pages.
+    input.set_count(&mut db).to(10);
+    assert_eq!(sum_tracked(&db, input), (0..10).sum());
+}
+
+#[test]
+fn releases_pages_of_freed_interned_values() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = Input::new(&db, PAGE_LEN);
+    assert_eq!(sum_interned(&db, input), (0..PAGE_LEN).sum());
+
+    input.set_count(&mut db).to(0);
+    assert_eq!(sum_interned(&db, input), 0);
+    assert_eq!(db.release_empty_pages(), 0);
+
+    assert_eq!(db.collect_interned_garbage(0), PAGE_LEN);
+    assert!(db.release_empty_pages() >= PAGE_LEN);
+
+    input.set_count(&mut db).to(PAGE_LEN);
+    assert_eq!(sum_interned(&db, input), (0..PAGE_LEN).sum());
+}
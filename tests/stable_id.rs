@@ -0,0 +1,107 @@
+#![cfg(feature = "inventory")]
+
+//! Test that `stable_id` inputs and interned structs have
+//! identities that are independent of their `Id`s.
+
+use salsa::{Database, DatabaseImpl, Setter, StableId};
+use test_log::test;
+
+#[salsa::input(stable_id, debug)]
+struct File {
+    path: String,
+    contents: String,
+}
+
+#[salsa::input]
+struct Unrelated {
+    field: u32,
+}
+
+#[salsa::interned(stable_id, debug)]
+struct Name<'db> {
+    text: String,
+}
+
+#[test]
+fn input_identities_survive_databases() {
+    let db1 = DatabaseImpl::new();
+    let a1 = File::new(&db1, "a.rs".to_string(), "fn a() {}".to_string());
+    let b1 = File::new(&db1, "b.rs".to_string(), "fn b() {}".to_string());
+
+    // Different creation order and an unrelated input shift the `Id`s.
+    let db2 = DatabaseImpl::new();
+    Unrelated::new(&db2, 0);
+    let b2 = File::new(&db2, "b.rs".to_string(), "fn b() {}".to_string());
+    let a2 = File::new(&db2, "a.rs".to_string(), "fn a() {}".to_string());
+
+    assert_eq!(a1.stable_id(&db1), a2.stable_id(&db2));
+    assert_eq!(b1.stable_id(&db1), b2.stable_id(&db2));
+    assert_ne!(a1.stable_id(&db1), b1.stable_id(&db1));
+
+    assert_eq!(File::from_stable_id(&db2, a1.stable_id(&db1)), Some(a2));
+    assert_eq!(File::from_stable_id(&db2, StableId::from_u64(0)), None);
+}
+
+#[test]
+fn input_identity_is_fixed_at_creation() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a.rs".to_string(), "fn a() {}".to_string());
+    let stable_id = file.stable_id(&db);
+
+    file.set_contents(&mut db).to("fn b() {}".to_string());
+    assert_eq!(file.stable_id(&db), stable_id);
+    assert_eq!(File::from_stable_id(&db, stable_id), Some(file));
+}
+
+#[test]
+fn identical_inputs_are_disambiguated() {
+    let db1 = DatabaseImpl::new();
+    let first1 = File::new(&db1, "a.rs".to_string(), String::new());
+    let second1 = File::new(&db1, "a.rs".to_string(), String::new());
+    assert_ne!(first1.stable_id(&db1), second1.stable_id(&db1));
+
+    let db2 = DatabaseImpl::new();
+    let first2 = File::new(&db2, "a.rs".to_string(), String::new());
+    let second2 = File::new(&db2, "a.rs".to_string(), String::new());
+    assert_eq!(first1.stable_id(&db1), first2.stable_id(&db2));
+    assert_eq!(second1.stable_id(&db1), second2.stable_id(&db2));
+}
+
+#[test]
+fn interned_identities_survive_databases() {
+    let db1 = DatabaseImpl::new();
+    let stable_id = Name::new(&db1, "salsa").stable_id(&db1);
+
+    let db2 = DatabaseImpl::new();
+    Name::new(&db2, "other");
+    let name = Name::new(&db2, "salsa");
+    assert_eq!(name.stable_id(&db2), stable_id);
+
+    db2.attach(|db| {
+        let found = Name::from_stable_id(db, stable_id).unwrap();
+        assert_eq!(found, name);
+        assert_eq!(found.text(db), "salsa");
+    });
+}
+
+fn path_key(fields: &(String, String)) -> &str {
+    &fields.0
+}
+
+#[salsa::input(stable_id = path_key)]
+struct KeyedFile {
+    path: String,
+    contents: String,
+}
+
+#[test]
+fn identities_from_key_fn() {
+    let db1 = DatabaseImpl::new();
+    let a1 = KeyedFile::new(&db1, "a.rs".to_string(), "fn a() {}".to_string());
+
+    // Only the path is part of the identity.
+    let db2 = DatabaseImpl::new();
+    let a2 = KeyedFile::new(&db2, "a.rs".to_string(), "fn b() {}".to_string());
+    assert_eq!(a1.stable_id(&db1), a2.stable_id(&db2));
+    assert_eq!(a1.stable_id(&db1), StableId::from_key("KeyedFile", "a.rs"));
+}
@@ -30,8 +30,10 @@ fn execute() {
         [
             "WillCheckCancellation",
             "WillExecute { database_key: tracked_fn(Id(0)) }",
+            "DidExecute { database_key: tracked_fn(Id(0)), backdated: false }",
             "WillCheckCancellation",
             "WillExecute { database_key: tracked_fn(Id(1)) }",
+            "DidExecute { database_key: tracked_fn(Id(1)), backdated: false }",
         ]"#]]);
 
     db.synthetic_write(Durability::LOW);
@@ -47,6 +49,7 @@ fn execute() {
     db.assert_logs(expect![[r#"
         [
             "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
             "WillCheckCancellation",
             "DidValidateMemoizedValue { database_key: tracked_fn(Id(0)) }",
             "WillCheckCancellation",
@@ -0,0 +1,121 @@
+#![cfg(feature = "inventory")]
+
+//! Test the `try_fetch` functions of tracked functions, which return cancellations and cycles
+//! as errors without unwinding.
+
+mod common;
+
+use common::{HasLogger, LogDatabase, Logger};
+use salsa::{CancellationToken, Cancelled, Database, SalsaError, Setter, Storage};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn read(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("read({:?})", input.field(db)));
+    input.field(db)
+}
+
+// Calling a failing query directly panics, so the cycle participants use `try_fetch`.
+#[salsa::tracked]
+fn cycle_a(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    cycle_b::try_fetch(db, input).unwrap_or(0)
+}
+
+#[salsa::tracked]
+fn cycle_b(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    cycle_a::try_fetch(db, input).unwrap_or(0)
+}
+
+#[salsa::tracked]
+fn uses_cycle(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("uses_cycle".to_string());
+    let cycle = cycle_a::try_fetch(db, input).unwrap_or(0);
+    cycle + read(db, input)
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct TryDatabase {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+#[salsa::db]
+impl Database for TryDatabase {}
+
+impl HasLogger for TryDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn ok() {
+    let db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    assert_eq!(read::try_fetch(&db, input).unwrap(), 1);
+    db.assert_logs(expect_test::expect![[r#"
+        [
+            "read(1)",
+        ]"#]]);
+}
+
+#[test]
+fn cancelled() {
+    let mut db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    db.storage
+        .set_cancellation_token(Some(CancellationToken::new()));
+    db.storage.cancellation_token().unwrap().cancel();
+
+    let error = read::try_fetch(&db, input).unwrap_err();
+    assert!(matches!(
+        error,
+        SalsaError::Cancelled(Cancelled::Requested { .. })
+    ));
+    db.assert_logs(expect_test::expect!["[]"]);
+}
+
+#[test]
+fn cycle() {
+    let db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    let error = cycle_a::try_fetch(&db, input).unwrap_err();
+    let SalsaError::Cycle { database_key, .. } = error else {
+        panic!("expected a cycle error, got {error:?}");
+    };
+    db.attach(|_| {
+        expect_test::expect!["cycle_a(Id(0))"].assert_eq(&format!("{database_key:?}"));
+    });
+}
+
+#[test]
+fn failures_fail_the_reading_queries() {
+    let mut db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    // `uses_cycle` falls back to a value, but it read a failed query, so it fails as well
+    // and its value is not memoized.
+    let error = uses_cycle::try_fetch(&db, input).unwrap_err();
+    assert!(matches!(error, SalsaError::Cycle { .. }));
+    let error = uses_cycle::try_fetch(&db, input).unwrap_err();
+    assert!(matches!(error, SalsaError::Cycle { .. }));
+    db.assert_logs(expect_test::expect![[r#"
+        [
+            "uses_cycle",
+            "read(1)",
+            "uses_cycle",
+        ]"#]]);
+
+    // The database stays usable.
+    input.set_field(&mut db).to(2);
+    assert_eq!(read::try_fetch(&db, input).unwrap(), 2);
+}
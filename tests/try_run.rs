@@ -0,0 +1,107 @@
+#![cfg(feature = "inventory")]
+
+//! Test `Database::try_run`, which reports cancellations and cycles as errors.
+
+use std::panic::AssertUnwindSafe;
+
+use salsa::{CancellationToken, Cancelled, Database, SalsaError, Storage};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn checked(db: &dyn Database, input: MyInput) -> u32 {
+    db.unwind_if_revision_cancelled();
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn cycle_a(db: &dyn Database, input: MyInput) -> u32 {
+    cycle_b(db, input)
+}
+
+#[salsa::tracked]
+fn cycle_b(db: &dyn Database, input: MyInput) -> u32 {
+    cycle_a(db, input)
+}
+
+#[salsa::tracked]
+fn panics(_db: &dyn Database, _input: MyInput) -> u32 {
+    panic!("user panic")
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct TryDatabase {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for TryDatabase {}
+
+#[test]
+fn ok() {
+    let db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    assert_eq!(db.try_run(|db| checked(db, input)).unwrap(), 1);
+}
+
+#[test]
+fn cancelled() {
+    let mut db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    db.storage
+        .set_cancellation_token(Some(CancellationToken::new()));
+    db.storage.cancellation_token().unwrap().cancel();
+
+    let error = db.try_run(|db| checked(db, input)).unwrap_err();
+    assert!(matches!(
+        error,
+        SalsaError::Cancelled(Cancelled::Requested { .. })
+    ));
+    expect_test::expect!["cancelled because of cancellation request"].assert_eq(&error.to_string());
+}
+
+#[test]
+fn cycle() {
+    let db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    let error = db.try_run(|db| cycle_a(db, input)).unwrap_err();
+    let SalsaError::Cycle { database_key, .. } = error else {
+        panic!("expected a cycle error, got {error:?}");
+    };
+    db.attach(|_| {
+        expect_test::expect!["cycle_a(Id(0))"].assert_eq(&format!("{database_key:?}"));
+    });
+
+    // The database stays usable.
+    assert_eq!(db.try_run(|db| checked(db, input)).unwrap(), 1);
+}
+
+#[test]
+#[should_panic(expected = "user panic")]
+fn other_panics_are_resumed() {
+    let db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    _ = db.try_run(|db| panics(db, input));
+}
+
+#[test]
+#[should_panic(expected = "user panic")]
+fn panics_after_caught_cycle_are_resumed() {
+    let db = TryDatabase::default();
+    let input = MyInput::new(&db, 1);
+
+    _ = db.try_run(|db| {
+        let cycle = std::panic::catch_unwind(AssertUnwindSafe(|| cycle_a(db, input)));
+        assert!(cycle.is_err());
+        panics(db, input)
+    });
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "inventory")]
+
+//! Test the `try_set_<field>` setters, which fail instead of blocking
+//! while other database handles are alive.
+
+use std::time::Duration;
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn sole_handle() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+
+    input.try_set_field(&mut db, Duration::ZERO).unwrap().to(2);
+    assert_eq!(double(&db, input), 4);
+}
+
+#[test]
+fn clone_on_same_thread() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let clone = db.clone();
+
+    let error = input.try_set_field(&mut db, Duration::ZERO).err().unwrap();
+    assert_eq!(error.live_handles(), 1);
+    expect_test::expect!["cannot acquire mutable database access: 1 other handle(s) still alive"]
+        .assert_eq(&error.to_string());
+
+    // Waiting cancels the other handle, but it can't be dropped while we wait on it.
+    let error = input
+        .try_set_field(&mut db, Duration::from_millis(10))
+        .err()
+        .unwrap();
+    assert_eq!(error.live_handles(), 1);
+
+    // The other handle is no longer cancelled once we gave up.
+    assert_eq!(double(&clone, input), 2);
+
+    drop(clone);
+    input.try_set_field(&mut db, Duration::ZERO).unwrap().to(2);
+    assert_eq!(double(&db, input), 4);
+}